@@ -0,0 +1,137 @@
+use chrono::prelude::*;
+use chrono::Duration;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use eva::bench::{self, Lcg, ScheduleTree};
+use eva::time_segment::NamedTimeSegment;
+use eva::{ImportanceStrategy, Strategy, Task, UrgencyStrategy};
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap()
+}
+
+/// Where `n` items of 30 minutes go when they're put close before their deadlines, spread over a
+/// year.
+fn items(n: usize) -> Vec<(DateTime<Utc>, usize)> {
+    let mut random = Lcg(7);
+    (0..n)
+        .map(|i| {
+            let deadline = start() + Duration::minutes(random.below(365 * 24 * 60) as i64);
+            (deadline, i)
+        })
+        .collect()
+}
+
+fn schedule_tree(c: &mut Criterion) {
+    let mut group = c.benchmark_group("schedule tree");
+    for &n in &[500, 5000] {
+        let items = items(n);
+        let duration = Duration::minutes(30);
+        group.bench_with_input(BenchmarkId::new("schedule", n), &items, |b, items| {
+            b.iter(|| {
+                let mut tree = ScheduleTree::new();
+                for &(deadline, item) in items {
+                    tree.schedule_close_before(deadline, duration, None, item);
+                }
+                tree
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("unschedule", n), &items, |b, items| {
+            b.iter_batched(
+                || {
+                    let mut tree = ScheduleTree::new();
+                    for &(deadline, item) in items {
+                        tree.schedule_close_before(deadline, duration, None, item);
+                    }
+                    tree
+                },
+                |mut tree| {
+                    for &(_, item) in items {
+                        tree.unschedule(&item);
+                    }
+                    tree
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Weekdays from 9:00 until 17:00.
+fn office_hours() -> NamedTimeSegment {
+    let start = start();
+    NamedTimeSegment {
+        id: 0,
+        name: "Office hours".to_owned(),
+        ranges: (0..5)
+            .map(|day| {
+                let day = start + Duration::days(day);
+                day + Duration::hours(9)..day + Duration::hours(17)
+            })
+            .collect(),
+        start,
+        period: Duration::weeks(1),
+        hue: 0,
+    }
+}
+
+/// Evenings from 19:00 until 22:00.
+fn evenings() -> NamedTimeSegment {
+    let start = start();
+    NamedTimeSegment {
+        id: 1,
+        name: "Evenings".to_owned(),
+        ranges: vec![start + Duration::hours(19)..start + Duration::hours(22)],
+        start,
+        period: Duration::days(1),
+        hue: 0,
+    }
+}
+
+/// `n` tasks of 10 to 50 minutes with deadlines spread over a year, half of them in office hours
+/// and half of them in the evening.
+fn tasks_per_segment(n: usize) -> Vec<(NamedTimeSegment, Vec<Task>)> {
+    let mut random = Lcg(42);
+    let mut task = |id: usize, time_segment_id: u32| Task {
+        id: id as u32,
+        content: format!("task {}", id),
+        deadline: start() + Duration::hours(24 + random.below(364 * 24) as i64),
+        available_from: None,
+        duration: Duration::minutes(10 * (1 + random.below(5)) as i64),
+        importance: 1 + random.below(10) as u32,
+        time_segment_id,
+        recurrence: None,
+        min_chunk_duration: None,
+        depends_on: vec![],
+        completed_at: None,
+        ical_uid: None,
+        pin: None,
+        blocked: None,
+    };
+    let office = (0..n / 2).map(|id| task(id, 0)).collect();
+    let evening = (n / 2..n).map(|id| task(id, 1)).collect();
+    vec![(office_hours(), office), (evenings(), evening)]
+}
+
+fn strategies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("strategies");
+    group.sample_size(10);
+    let strategies: [&dyn Strategy; 2] = [&ImportanceStrategy, &UrgencyStrategy];
+    for strategy in &strategies {
+        for &n in &[500, 5000] {
+            let tasks = tasks_per_segment(n);
+            group.bench_with_input(BenchmarkId::new(strategy.name(), n), &tasks, |b, tasks| {
+                b.iter_batched(
+                    || tasks.clone(),
+                    |tasks| bench::schedule(start(), tasks, *strategy),
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, schedule_tree, strategies);
+criterion_main!(benches);
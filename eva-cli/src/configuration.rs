@@ -3,8 +3,9 @@ use std::path::Path;
 
 use config;
 use directories::ProjectDirs;
-use eva::configuration::{Configuration, SchedulingStrategy};
+use eva::configuration::{Configuration, Strategies};
 use failure::Fail;
+use itertools::Itertools;
 use shellexpand;
 
 #[derive(Debug, Fail)]
@@ -32,6 +33,89 @@ pub enum Error {
 type Result<T> = std::result::Result<T, Error>;
 
 pub fn read() -> Result<Configuration> {
+    let configuration = load()?;
+
+    let database_path = configuration
+        .get_str("database")
+        .map_err(|e| Error::Read("the database path", e.into()))?
+        .expand("the database path")?;
+    ensure_exists(&database_path)
+        .map_err(|e| Error::FileCreation("the database path", e.into()))?;
+    let database = connect_to_database(&database_path)?;
+
+    let strategies = Strategies::default();
+    let scheduling_strategy = configuration
+        .get_str("scheduling_strategy")
+        .map_err(|e| Error::Read("the scheduling strategy", e.into()))?;
+    if strategies.get(&scheduling_strategy).is_none() {
+        return Err(Error::Read(
+            "the scheduling strategy",
+            failure::err_msg(format!(
+                "The scheduling strategy must be one of: {}",
+                strategies.names().map(|name| format!("`{}`", name)).join(", ")
+            )),
+        ));
+    }
+
+    let correct_estimates = configuration
+        .get_bool("correct_estimates")
+        .map_err(|e| Error::Read("whether to correct estimates", e.into()))?;
+
+    Ok(Configuration {
+        database: Box::new(database),
+        scheduling_strategy,
+        strategies,
+        correct_estimates,
+    })
+}
+
+/// Connects to the CalDAV calendar collection at `caldav_url`, if one is configured.
+pub fn caldav() -> Result<Option<eva::caldav::Client>> {
+    let configuration = load()?;
+    let url = match configuration.get_str("caldav_url") {
+        Ok(url) => url,
+        Err(config::ConfigError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(Error::Read("the CalDAV URL", e.into())),
+    };
+    let optional = |key: &str, name: &'static str| match configuration.get_str(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(config::ConfigError::NotFound(_)) => Ok(None),
+        Err(e) => Err(Error::Read(name, e.into())),
+    };
+    let username = optional("caldav_username", "the CalDAV username")?;
+    let password = optional("caldav_password", "the CalDAV password")?;
+    let client = eva::caldav::Client::new(&url, username.as_deref(), password.as_deref())
+        .map_err(|e| Error::Read("the CalDAV URL", e.into()))?;
+    Ok(Some(client))
+}
+
+/// Reads how long the work intervals and breaks of `eva focus` are.
+pub fn pomodoro() -> Result<eva::pomodoro::Pomodoro> {
+    let configuration = load()?;
+    let minutes = |key: &str| match configuration.get_int(key) {
+        Ok(minutes) if minutes >= 0 => Ok(minutes),
+        Ok(_) => Err(Error::Read(
+            "the Pomodoro intervals",
+            failure::err_msg(format!("{} can't be negative", key)),
+        )),
+        Err(e) => Err(Error::Read("the Pomodoro intervals", e.into())),
+    };
+    let work = minutes("pomodoro_work_minutes")?;
+    if work == 0 {
+        return Err(Error::Read(
+            "the Pomodoro intervals",
+            failure::err_msg("pomodoro_work_minutes must be at least 1"),
+        ));
+    }
+    Ok(eva::pomodoro::Pomodoro {
+        work: chrono::Duration::minutes(work),
+        short_break: chrono::Duration::minutes(minutes("pomodoro_break_minutes")?),
+        long_break: chrono::Duration::minutes(minutes("pomodoro_long_break_minutes")?),
+        long_break_every: minutes("pomodoro_long_break_every")? as u32,
+    })
+}
+
+fn load() -> Result<config::Config> {
     let project_dirs = ProjectDirs::from("", "", "eva").ok_or_else(|| Error::UnsupportedOS())?;
 
     let config_filename = project_dirs.config_dir().join("eva.toml");
@@ -50,33 +134,7 @@ pub fn read() -> Result<Configuration> {
         .merge(config::Environment::with_prefix("eva"))
         .map_err(|e| Error::Read("environment variables", e.into()))?;
 
-    let database_path = configuration
-        .get_str("database")
-        .map_err(|e| Error::Read("the database path", e.into()))?
-        .expand("the database path")?;
-    ensure_exists(&database_path)
-        .map_err(|e| Error::FileCreation("the database path", e.into()))?;
-    let database = connect_to_database(&database_path)?;
-
-    let scheduling_strategy = match configuration
-        .get_str("scheduling_strategy")
-        .map_err(|e| Error::Read("the scheduling strategy", e.into()))?
-        .as_str()
-    {
-        "importance" => SchedulingStrategy::Importance,
-        "urgency" => SchedulingStrategy::Urgency,
-        _ => {
-            return Err(Error::Read(
-                "the scheduling strategy",
-                failure::err_msg("The scheduling strategy must be `importance` or `urgency`"),
-            ));
-        }
-    };
-
-    Ok(Configuration {
-        database: Box::new(database),
-        scheduling_strategy,
-    })
+    Ok(configuration)
 }
 
 fn set_defaults<'a>(
@@ -91,9 +149,26 @@ fn set_defaults<'a>(
         )
     })?;
 
+    let pomodoro = eva::pomodoro::Pomodoro::default();
     Ok(configuration
         .set_default("scheduling_strategy", "importance")
         .map_err(|e| Error::Default("the scheduling strategy", e.into()))?
+        .set_default("correct_estimates", false)
+        .map_err(|e| Error::Default("whether to correct estimates", e.into()))?
+        .set_default("pomodoro_work_minutes", pomodoro.work.num_minutes())
+        .map_err(|e| Error::Default("the Pomodoro intervals", e.into()))?
+        .set_default("pomodoro_break_minutes", pomodoro.short_break.num_minutes())
+        .map_err(|e| Error::Default("the Pomodoro intervals", e.into()))?
+        .set_default(
+            "pomodoro_long_break_minutes",
+            pomodoro.long_break.num_minutes(),
+        )
+        .map_err(|e| Error::Default("the Pomodoro intervals", e.into()))?
+        .set_default(
+            "pomodoro_long_break_every",
+            i64::from(pomodoro.long_break_every),
+        )
+        .map_err(|e| Error::Default("the Pomodoro intervals", e.into()))?
         .set_default("database", db_filename)
         .map_err(|e| Error::Default("the database path", e.into()))?)
 }
@@ -0,0 +1,33 @@
+use eva::configuration::Configuration;
+use futures::executor::block_on;
+
+use crate::parse;
+
+/// Sets a single field of the task with id `id` to `value`, the way it's given on the command
+/// line. `field` has to be one of the fields that `eva set` accepts.
+pub fn set_field<E>(
+    configuration: &Configuration,
+    field: &str,
+    id: u32,
+    value: &str,
+) -> Result<(), E>
+where
+    E: From<eva::Error> + From<parse::Error>,
+{
+    let mut task = block_on(eva::get_task(configuration, id))?;
+    match field {
+        "content" => task.content = value.to_string(),
+        "deadline" => task.deadline = parse::deadline(value)?,
+        "duration" => task.duration = parse::duration(value)?,
+        "importance" => task.importance = parse::importance(value)?,
+        "from" => task.available_from = parse::available_from(value)?,
+        "recurrence" => task.recurrence = parse::recurrence(value)?,
+        "split" => task.min_chunk_duration = parse::min_chunk_duration(value)?,
+        "segment" => {
+            let segments = block_on(eva::time_segments(configuration))?;
+            task.time_segment_id = parse::time_segment(value, &segments)?.id;
+        }
+        _ => unreachable!(),
+    };
+    Ok(block_on(eva::update_task(configuration, task))?)
+}
@@ -1,10 +1,10 @@
 use crate::pretty_print::PrettyPrint;
-use eva::configuration::Configuration;
 use failure::Fail;
 use futures::executor::block_on;
 use itertools::Itertools;
 
 mod configuration;
+mod edit;
 mod parse;
 mod pretty_print;
 
@@ -39,20 +39,6 @@ impl From<eva::Error> for Error {
     }
 }
 
-type Result<T> = std::result::Result<T, Error>;
-
-fn set_field(configuration: &Configuration, field: &str, id: u32, value: &str) -> Result<()> {
-    let mut task = block_on(eva::get_task(configuration, id))?;
-    match field {
-        "content" => task.content = value.to_string(),
-        "deadline" => task.deadline = parse::deadline(value)?,
-        "duration" => task.duration = parse::duration(value)?,
-        "importance" => task.importance = parse::importance(value)?,
-        _ => unreachable!(),
-    };
-    Ok(block_on(eva::update_task(configuration, task))?)
-}
-
 #[no_mangle]
 pub extern "C" fn add(
     content_c: *const c_char,
@@ -71,9 +57,13 @@ pub extern "C" fn add(
     let new_task = eva::NewTask {
         content: content.to_owned(),
         deadline: parse::deadline(deadline).unwrap(),
+        available_from: None,
         duration: parse::duration(duration).unwrap(),
         importance: parse::importance(importance).unwrap(),
         time_segment_id: 0,
+        recurrence: None,
+        min_chunk_duration: None,
+        ical_uid: None,
     };
     let configuration = configuration::read().unwrap();
     block_on(eva::add_task(&configuration, new_task)).unwrap();
@@ -98,7 +88,7 @@ pub extern "C" fn set(id_c: *const c_char, field_c: *const c_char, value_c: *con
     };
     let id = parse::id(id).unwrap();
     let configuration = configuration::read().unwrap();
-    set_field(&configuration, field, id, value).unwrap();
+    edit::set_field::<Error>(&configuration, field, id, value).unwrap();
 }
 
 #[no_mangle]
@@ -116,6 +106,10 @@ pub extern "C" fn tasks() {
 pub extern "C" fn schedule(strategy_c: *const c_char) {
     let strategy = unsafe { CStr::from_ptr(strategy_c).to_str().unwrap() };
     let configuration = configuration::read().unwrap();
-    let schedule = block_on(eva::schedule(&configuration, &strategy)).unwrap();
+    let eva::BestEffortSchedule { schedule, unschedulable, .. } =
+        block_on(eva::schedule_best_effort(&configuration, &strategy)).unwrap();
     println!("{}", schedule.pretty_print());
+    if !unschedulable.is_empty() {
+        println!("\n{}", unschedulable.pretty_print());
+    }
 }
@@ -1,12 +1,21 @@
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use chrono::{DateTime, Utc};
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use eva::configuration::Configuration;
 use failure::Fail;
 use futures::executor::block_on;
 use itertools::Itertools;
 
+use crate::output::Format;
 use crate::pretty_print::PrettyPrint;
 
 mod configuration;
+mod edit;
+mod output;
 mod parse;
 mod pretty_print;
 
@@ -18,6 +27,29 @@ enum Error {
     Parse(#[cause] parse::Error),
     #[fail(display = "{}", _0)]
     Eva(#[cause] eva::Error),
+    #[fail(display = "I could not open {}: {}", _0, _1)]
+    Open(String, #[cause] std::io::Error),
+    #[fail(
+        display = "I don't know which calendar to synchronise with. Please set caldav_url in \
+                   eva.toml or EVA_CALDAV_URL to the URL of a CalDAV calendar collection."
+    )]
+    NoCalDav(),
+    #[fail(display = "{}", _0)]
+    Usage(#[cause] clap::Error),
+}
+
+impl Error {
+    /// A name for the kind of error, for scripts to tell errors apart.
+    fn kind(&self) -> &'static str {
+        match self {
+            Error::Configuration(_) => "configuration",
+            Error::Parse(_) => "parse",
+            Error::Eva(_) => "eva",
+            Error::Open(_, _) => "open",
+            Error::NoCalDav() => "no_caldav",
+            Error::Usage(_) => "usage",
+        }
+    }
 }
 
 impl From<configuration::Error> for Error {
@@ -41,18 +73,28 @@ impl From<eva::Error> for Error {
 type Result<T> = std::result::Result<T, Error>;
 
 fn main() {
+    // The arguments can only be parsed after reading the configuration, so find out how to
+    // report errors before that by looking at them directly
+    let format = Format::from_raw_args(std::env::args());
     if let Err(error) = run() {
-        handle_error(&error);
+        handle_error(&error, format);
     }
 }
 
 fn run() -> Result<()> {
     let configuration = configuration::read()?;
-    let matches = cli(&configuration).get_matches();
-    dispatch(&matches, &configuration)
+    let matches = cli(&configuration)
+        .get_matches_safe()
+        .map_err(|error| match error.kind {
+            clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => error.exit(),
+            _ => Error::Usage(error),
+        })?;
+    let format = Format::from_name(matches.value_of("output"));
+    dispatch(&matches, &configuration, format)
 }
 
-fn cli<'a, 'b>(configuration: &Configuration) -> App<'a, 'b> {
+fn cli<'a>(configuration: &'a Configuration) -> App<'a, 'a> {
+    let strategies = configuration.strategies.names().collect_vec();
     let add = SubCommand::with_name("add")
         .about("Adds a task")
         .arg(Arg::with_name("content").required(true)
@@ -64,52 +106,250 @@ fn cli<'a, 'b>(configuration: &Configuration) -> App<'a, 'b> {
              .help("How long do you estimate it will take? \
                    Give it in a (whole or decimal) number of hours."))
         .arg(Arg::with_name("importance").required(true)
-             .help("How important is this task to you on a scale from 1 to 10?"));
+             .help("How important is this task to you on a scale from 1 to 10?"))
+        .arg(Arg::with_name("from")
+             .long("from")
+             .takes_value(true)
+             .help("When can you start on it at the earliest? Give it in the format of \
+                   '2 Aug 2017 14:03'."))
+        .arg(Arg::with_name("repeat")
+             .long("repeat")
+             .takes_value(true)
+             .help("How often should it recur? Give it as an iCalendar recurrence rule, \
+                   like 'FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10'. The deadline is the first \
+                   occurrence."))
+        .arg(Arg::with_name("split")
+             .long("split")
+             .takes_value(true)
+             .help("Lets Eva split the task up to fit it in smaller gaps. Give the shortest \
+                   chunk you'd still want to work on in a (whole or decimal) number of hours."))
+        .arg(Arg::with_name("segment")
+             .long("segment")
+             .takes_value(true)
+             .help("During which time segment should it be done? Give the segment's name or id."));
     let rm = SubCommand::with_name("rm")
         .about("Removes a task")
         .arg(Arg::with_name("task-id").required(true));
+    let done = SubCommand::with_name("done")
+        .about("Marks a task as done, or moves a recurring task to its next occurrence")
+        .arg(Arg::with_name("task-id").required(true));
     let set = SubCommand::with_name("set")
-        .about("Changes the deadline, duration, importance or content of an existing task")
+        .about("Changes the deadline, duration, importance, content, earliest start, recurrence, \
+               splitting or time segment of an existing task")
         .arg(Arg::with_name("property").required(true)
-             .possible_values(&["content", "deadline", "duration", "importance"]))
+             .possible_values(&["content", "deadline", "duration", "importance", "from",
+                                "recurrence", "split", "segment"]))
         .arg(Arg::with_name("task-id").required(true))
         .arg(Arg::with_name("value").required(true));
+    let depend = SubCommand::with_name("depend")
+        .about("Makes a task wait until another task is done")
+        .arg(Arg::with_name("task-id").required(true))
+        .arg(Arg::with_name("prerequisite-id").required(true)
+             .help("The task that has to be done first"))
+        .arg(Arg::with_name("remove")
+             .long("remove")
+             .help("Lets the task no longer wait for the other one"));
+    let pin = SubCommand::with_name("pin")
+        .about("Pins a task to a time, so that Eva schedules it right then")
+        .arg(Arg::with_name("task-id").required(true))
+        .arg(Arg::with_name("time").required(true)
+             .help("When should it start? Give it in the format of '2 Aug 2017 14:03'."))
+        .arg(Arg::with_name("not-before")
+             .long("not-before")
+             .help("Only keeps Eva from scheduling it before that time, instead of pinning it \
+                   there exactly"));
+    let unpin = SubCommand::with_name("unpin")
+        .about("Lets Eva decide when to do a pinned task again")
+        .arg(Arg::with_name("task-id").required(true));
+    let block = SubCommand::with_name("block")
+        .about("Puts a task on hold while it waits for something or someone, so that Eva doesn't \
+               schedule it")
+        .arg(Arg::with_name("task-id").required(true))
+        .arg(Arg::with_name("reason").required(true)
+             .help("What or whom is it waiting for?"))
+        .arg(Arg::with_name("until")
+             .long("until")
+             .takes_value(true)
+             .help("When should you follow up on it? Give it in the format of \
+                   '2 Aug 2017 14:03'."));
+    let unblock = SubCommand::with_name("unblock")
+        .about("Lets Eva schedule a blocked task again")
+        .arg(Arg::with_name("task-id").required(true));
+    let waiting = SubCommand::with_name("waiting")
+        .about("Lists the blocked tasks and what they're waiting for, the ones to follow up on \
+               first");
+    let start = SubCommand::with_name("start")
+        .about("Starts tracking the time you spend on a task, stopping the timer that's running")
+        .arg(Arg::with_name("task-id").required(true));
+    let stop = SubCommand::with_name("stop")
+        .about("Stops the timer that's running");
+    let log = SubCommand::with_name("log")
+        .about("Lists the time you tracked on your tasks");
+    let focus = SubCommand::with_name("focus")
+        .about("Works on the task that's scheduled now in Pomodoro intervals, tracking the time \
+                and taking breaks in between")
+        .arg(Arg::with_name("strategy")
+             .long("strategy")
+             .takes_value(true)
+             .possible_values(&strategies)
+             .default_value(&configuration.scheduling_strategy));
     let list = SubCommand::with_name("tasks")
-        .about("Lists your tasks in the order you added them");
+        .about("Lists your tasks in the order you added them")
+        .arg(Arg::with_name("done")
+             .long("done")
+             .help("Lists the tasks you finished instead, in the order you finished them"));
     let schedule = SubCommand::with_name("schedule")
         .about("Lets Eva suggest a schedule for your tasks")
         .arg(Arg::with_name("strategy")
              .long("strategy")
              .takes_value(true)
-             .possible_values(&["importance", "urgency"])
-             .default_value(configuration.scheduling_strategy.as_str()));
+             .possible_values(&strategies)
+             .default_value(&configuration.scheduling_strategy))
+        .arg(Arg::with_name("format")
+             .long("format")
+             .takes_value(true)
+             .possible_values(&["text", "ics"])
+             .default_value("text")
+             .help("Use ics to get an iCalendar file that calendar applications can import"))
+        .arg(Arg::with_name("explain")
+             .long("explain")
+             .takes_value(true)
+             .value_name("task-id")
+             .help("Explains how the task ended up where it is in the schedule, instead of \
+                    showing the schedule"))
+        .arg(Arg::with_name("stable")
+             .long("stable")
+             .help("Keeps the tasks where they are in the accepted schedule, unless the other \
+                    tasks need the room, and shows what moved"))
+        .arg(Arg::with_name("accept")
+             .long("accept")
+             .help("Accepts the schedule, so that --stable sticks to it from now on"));
+    let free = SubCommand::with_name("free")
+        .about("Shows when you have free time, around your events and the scheduled tasks")
+        .arg(Arg::with_name("before")
+             .long("before")
+             .takes_value(true)
+             .help("Until when should Eva look? Give it in the format of '2 Aug 2017 14:03'. \
+                   By default, it looks a week ahead."))
+        .arg(Arg::with_name("at-least")
+             .long("at-least")
+             .takes_value(true)
+             .help("Only shows free slots that are at least this long. Give it in a (whole or \
+                   decimal) number of hours."))
+        .arg(Arg::with_name("segment")
+             .long("segment")
+             .takes_value(true)
+             .default_value("0")
+             .help("During which time segment? Give the segment's name or id."));
+    let import = SubCommand::with_name("import")
+        .about("Imports the events and to-dos of an iCalendar (.ics) file")
+        .arg(Arg::with_name("file").required(true)
+             .help("Events in it block your time and to-dos become tasks. Importing the same \
+                   file again only applies what changed."));
+    let sync = SubCommand::with_name("sync")
+        .about("Synchronises with your CalDAV calendar: its events block your time, and the \
+                schedule is put in it")
+        .arg(Arg::with_name("strategy")
+             .long("strategy")
+             .takes_value(true)
+             .possible_values(&strategies)
+             .default_value(&configuration.scheduling_strategy));
+    let stats = SubCommand::with_name("stats")
+        .about("Shows statistics about your tasks")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("estimates")
+            .about("Compares the time you tracked on your finished tasks to your estimates, to \
+                   show how your estimates change over time"));
+    let segment = SubCommand::with_name("segment")
+        .about("Manages the time segments, the hours during which tasks can be scheduled")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("add")
+            .about("Adds a time segment")
+            .arg(Arg::with_name("name").required(true)
+                 .help("What do you want to call it, like 'Evenings' or 'Weekends'?"))
+            .arg(Arg::with_name("ranges").required(true)
+                 .help("When does it happen every week? Give it like \
+                       'Mon-Fri 09:00-17:00, Sat 10:00-12:00'.")))
+        .subcommand(SubCommand::with_name("list")
+            .about("Lists your time segments"))
+        .subcommand(SubCommand::with_name("set")
+            .about("Changes the name or ranges of an existing time segment")
+            .arg(Arg::with_name("property").required(true)
+                 .possible_values(&["name", "ranges"]))
+            .arg(Arg::with_name("segment").required(true)
+                 .help("The name or id of the time segment"))
+            .arg(Arg::with_name("value").required(true)))
+        .subcommand(SubCommand::with_name("rm")
+            .about("Removes a time segment that no unfinished tasks belong to")
+            .arg(Arg::with_name("segment").required(true)
+                 .help("The name or id of the time segment")));
 
     App::new("eva")
         .version(env!("CARGO_PKG_VERSION"))
         .global_setting(AppSettings::ColoredHelp)
         .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(Arg::with_name("output")
+             .long("output")
+             .global(true)
+             .takes_value(true)
+             .possible_values(&["text", "json"])
+             .default_value("text")
+             .help("Use json to get documents that are easy to use in scripts"))
         .subcommand(add)
         .subcommand(rm)
+        .subcommand(done)
         .subcommand(set)
+        .subcommand(depend)
+        .subcommand(pin)
+        .subcommand(unpin)
+        .subcommand(block)
+        .subcommand(unblock)
+        .subcommand(start)
+        .subcommand(stop)
+        .subcommand(log)
+        .subcommand(focus)
         .subcommand(list)
+        .subcommand(waiting)
         .subcommand(schedule)
+        .subcommand(free)
+        .subcommand(import)
+        .subcommand(sync)
+        .subcommand(stats)
+        .subcommand(segment)
 }
 
-fn dispatch(inputs: &ArgMatches, configuration: &Configuration) -> Result<()> {
+fn dispatch(inputs: &ArgMatches, configuration: &Configuration, format: Format) -> Result<()> {
     match inputs.subcommand() {
         ("add", Some(submatches)) => {
             let content = submatches.value_of("content").unwrap();
             let deadline = submatches.value_of("deadline").unwrap();
             let duration = submatches.value_of("duration").unwrap();
             let importance = submatches.value_of("importance").unwrap();
+            let available_from = submatches.value_of("from").unwrap_or("none");
+            let recurrence = submatches.value_of("repeat").unwrap_or("none");
+            let min_chunk_duration = submatches.value_of("split").unwrap_or("none");
+            let time_segment_id = match submatches.value_of("segment") {
+                Some(segment) => {
+                    let segments = block_on(eva::time_segments(configuration))?;
+                    parse::time_segment(segment, &segments)?.id
+                }
+                None => 0,
+            };
             let new_task = eva::NewTask {
                 content: content.to_owned(),
                 deadline: parse::deadline(deadline)?,
+                available_from: parse::available_from(available_from)?,
                 duration: parse::duration(duration)?,
                 importance: parse::importance(importance)?,
-                time_segment_id: 0,
+                time_segment_id,
+                recurrence: parse::recurrence(recurrence)?,
+                min_chunk_duration: parse::min_chunk_duration(min_chunk_duration)?,
+                ical_uid: None,
             };
-            let _task = block_on(eva::add_task(configuration, new_task))?;
+            let task = block_on(eva::add_task(configuration, new_task))?;
+            if format == Format::Json {
+                output::print("task", &task);
+            }
             Ok(())
         }
         ("rm", Some(submatches)) => {
@@ -117,46 +357,535 @@ fn dispatch(inputs: &ArgMatches, configuration: &Configuration) -> Result<()> {
             let id = parse::id(id)?;
             Ok(block_on(eva::delete_task(configuration, id))?)
         }
+        ("done", Some(submatches)) => {
+            let id = submatches.value_of("task-id").unwrap();
+            let id = parse::id(id)?;
+            let next_task = block_on(eva::complete_task(configuration, id))?;
+            match (format, next_task) {
+                (Format::Json, next_task) => output::print("next_occurrence", &next_task),
+                (Format::Text, Some(next_task)) => {
+                    println!("Next occurrence:");
+                    println!("  {}", next_task.pretty_print().split("\n").join("\n  "));
+                }
+                (Format::Text, None) => {}
+            }
+            Ok(())
+        }
         ("set", Some(submatches)) => {
             let field = submatches.value_of("property").unwrap();
             let id = submatches.value_of("task-id").unwrap();
             let value = submatches.value_of("value").unwrap();
             let id = parse::id(id)?;
-            Ok(set_field(configuration, field, id, value)?)
+            edit::set_field(configuration, field, id, value)
+        }
+        ("depend", Some(submatches)) => {
+            let id = submatches.value_of("task-id").unwrap();
+            let prerequisite_id = submatches.value_of("prerequisite-id").unwrap();
+            let id = parse::id(id)?;
+            let prerequisite_id = parse::id(prerequisite_id)?;
+            if submatches.is_present("remove") {
+                Ok(block_on(eva::delete_dependency(configuration, id, prerequisite_id))?)
+            } else {
+                Ok(block_on(eva::add_dependency(configuration, id, prerequisite_id))?)
+            }
+        }
+        ("pin", Some(submatches)) => {
+            let id = parse::id(submatches.value_of("task-id").unwrap())?;
+            let time = parse::deadline(submatches.value_of("time").unwrap())?;
+            let pin = if submatches.is_present("not-before") {
+                eva::Pin::NotBefore(time)
+            } else {
+                eva::Pin::At(time)
+            };
+            let task = block_on(eva::pin_task(configuration, id, pin))?;
+            if format == Format::Json {
+                output::print("task", &task);
+            }
+            Ok(())
+        }
+        ("unpin", Some(submatches)) => {
+            let id = parse::id(submatches.value_of("task-id").unwrap())?;
+            let task = block_on(eva::unpin_task(configuration, id))?;
+            if format == Format::Json {
+                output::print("task", &task);
+            }
+            Ok(())
+        }
+        ("block", Some(submatches)) => {
+            let id = parse::id(submatches.value_of("task-id").unwrap())?;
+            let blocked = eva::Blocked {
+                reason: submatches.value_of("reason").unwrap().to_owned(),
+                follow_up: submatches.value_of("until").map(parse::deadline).transpose()?,
+            };
+            let task = block_on(eva::block_task(configuration, id, blocked))?;
+            if format == Format::Json {
+                output::print("task", &task);
+            }
+            Ok(())
         }
-        ("tasks", Some(_submatches)) => {
-            let tasks = block_on(eva::tasks(configuration))?;
-            println!("Tasks:");
+        ("unblock", Some(submatches)) => {
+            let id = parse::id(submatches.value_of("task-id").unwrap())?;
+            let task = block_on(eva::unblock_task(configuration, id))?;
+            if format == Format::Json {
+                output::print("task", &task);
+            }
+            Ok(())
+        }
+        ("start", Some(submatches)) => {
+            let id = parse::id(submatches.value_of("task-id").unwrap())?;
+            let entry = block_on(eva::start_timer(configuration, id))?;
+            if format == Format::Json {
+                output::print("time_entry", &entry);
+            }
+            Ok(())
+        }
+        ("stop", Some(_)) => {
+            let entry = block_on(eva::stop_timer(configuration))?;
+            match (format, entry) {
+                (Format::Json, entry) => output::print("time_entry", &entry),
+                (Format::Text, Some(entry)) => {
+                    let task = block_on(eva::get_task(configuration, entry.task_id))?;
+                    println!("Stopped: {}", (&entry, &task).pretty_print());
+                }
+                (Format::Text, None) => println!("No timer is running."),
+            }
+            Ok(())
+        }
+        ("log", Some(_)) => {
+            let entries = block_on(eva::time_entries(configuration))?;
+            if format == Format::Json {
+                output::print("time_entries", &entries);
+                return Ok(());
+            }
+            if entries.is_empty() {
+                println!("You haven't tracked any time yet.");
+                return Ok(());
+            }
+            println!("Time log:");
+            for entry in &entries {
+                let task = block_on(eva::get_task(configuration, entry.task_id))?;
+                println!("  {}", (entry, &task).pretty_print());
+            }
+            let now = configuration.now();
+            let total: chrono::Duration = entries.iter().map(|entry| entry.duration(now)).sum();
+            println!("In total, you tracked {}.", total.pretty_print());
+            Ok(())
+        }
+        ("focus", Some(submatches)) => {
+            let strategy = submatches.value_of("strategy").unwrap();
+            focus(configuration, strategy, format)
+        }
+        ("tasks", Some(submatches)) => {
+            let done = submatches.is_present("done");
+            let tasks = if done {
+                block_on(eva::completed_tasks(configuration))?
+            } else {
+                block_on(eva::tasks(configuration))?
+            };
+            if format == Format::Json {
+                output::print("tasks", &tasks);
+                return Ok(());
+            }
+            println!("{}", if done { "Done:" } else { "Tasks:" });
             for task in &tasks {
                 // Indent all lines of task.pretty_print() by two spaces
                 println!("  {}", task.pretty_print().split("\n").join("\n  "));
             }
             Ok(())
         }
+        ("waiting", Some(_)) => {
+            let now = configuration.now();
+            let (stale, waiting): (Vec<_>, Vec<_>) = block_on(eva::blocked_tasks(configuration))?
+                .into_iter()
+                .partition(|task| task.blocked.as_ref().unwrap().is_stale(now));
+            if format == Format::Json {
+                let tasks = stale
+                    .iter()
+                    .map(|task| (task, true))
+                    .chain(waiting.iter().map(|task| (task, false)))
+                    .map(|(task, stale)| serde_json::json!({ "task": task, "stale": stale }))
+                    .collect_vec();
+                output::print("waiting", tasks);
+                return Ok(());
+            }
+            if stale.is_empty() && waiting.is_empty() {
+                println!("None of your tasks are waiting for anything.");
+            }
+            for (title, tasks) in &[("Follow up on:", stale), ("Waiting for:", waiting)] {
+                if tasks.is_empty() {
+                    continue;
+                }
+                println!("{}", title);
+                for task in tasks {
+                    println!("  {}", task.pretty_print().split("\n").join("\n  "));
+                }
+            }
+            Ok(())
+        }
         ("schedule", Some(submatches)) => {
             let strategy = submatches.value_of("strategy").unwrap().to_owned();
-            let schedule = block_on(eva::schedule(configuration, &strategy))?;
-            println!("{}", schedule.pretty_print());
+            let (best_effort, moves) = if submatches.is_present("stable") {
+                let eva::StableSchedule { best_effort, moves } =
+                    block_on(eva::schedule_stable(configuration, &strategy))?;
+                (best_effort, Some(moves))
+            } else {
+                (block_on(eva::schedule_best_effort(configuration, &strategy))?, None)
+            };
+            let eva::BestEffortSchedule { schedule, unschedulable, explanations } = best_effort;
+            if let Some(id) = submatches.value_of("explain") {
+                let task = block_on(eva::get_task(configuration, parse::id(id)?))?;
+                let explanations = explanations
+                    .into_iter()
+                    .filter(|explanation| explanation.task.id == task.id)
+                    .collect_vec();
+                let unschedulable = unschedulable
+                    .into_iter()
+                    .filter(|unschedulable| unschedulable.task.id == task.id)
+                    .collect_vec();
+                match format {
+                    Format::Json => output::print(
+                        "explanation",
+                        serde_json::json!({
+                            "task": task,
+                            "explanations": explanations,
+                            "unschedulable": unschedulable,
+                        }),
+                    ),
+                    Format::Text if explanations.is_empty() && unschedulable.is_empty() => {
+                        let reason = if task.completed_at.is_some() {
+                            ", because it's done"
+                        } else if task.blocked.is_some() {
+                            ", because it's blocked"
+                        } else {
+                            ""
+                        };
+                        println!("{}\n  It isn't scheduled{}.", task.pretty_print(), reason);
+                    }
+                    Format::Text => {
+                        let lines = explanations
+                            .iter()
+                            .map(|explanation| explanation.pretty_print())
+                            .chain(unschedulable.iter().map(|unschedulable| {
+                                unschedulable.pretty_print()
+                            }))
+                            .join("\n");
+                        println!("{}", lines);
+                    }
+                }
+                return Ok(());
+            }
+            if submatches.is_present("accept") {
+                block_on(eva::accept_schedule(configuration, &schedule))?;
+            }
+            match (submatches.value_of("format").unwrap(), format) {
+                ("ics", _) => {
+                    print!(
+                        "{}",
+                        eva::icalendar::export_schedule(&schedule, configuration.now())
+                    );
+                    // Keep the warnings out of the calendar
+                    if !unschedulable.is_empty() {
+                        eprintln!("{}", unschedulable.pretty_print());
+                    }
+                }
+                (_, Format::Json) => {
+                    let mut json = serde_json::json!({
+                        "tasks": schedule.tasks,
+                        "events": schedule.events,
+                        "unschedulable": unschedulable,
+                    });
+                    if let Some(moves) = moves {
+                        json["moves"] = serde_json::json!(moves);
+                    }
+                    output::print("schedule", json)
+                }
+                (_, Format::Text) => {
+                    println!("{}", schedule.pretty_print());
+                    if !unschedulable.is_empty() {
+                        println!("\n{}", unschedulable.pretty_print());
+                    }
+                    if let Some(moves) = moves {
+                        println!("\n{}", moves.pretty_print());
+                    }
+                }
+            }
+            Ok(())
+        }
+        ("free", Some(submatches)) => {
+            let end = match submatches.value_of("before") {
+                Some(before) => parse::deadline(before)?,
+                None => configuration.now() + chrono::Duration::weeks(1),
+            };
+            let min_length = match submatches.value_of("at-least") {
+                Some(at_least) => parse::duration(at_least)?,
+                None => chrono::Duration::zero(),
+            };
+            let segments = block_on(eva::time_segments(configuration))?;
+            let segment = parse::time_segment(submatches.value_of("segment").unwrap(), &segments)?;
+            let free = block_on(eva::free_slots(
+                configuration,
+                configuration.now()..end,
+                min_length,
+                segment,
+            ))?;
+            match format {
+                Format::Json => output::print("free", &free),
+                Format::Text => println!("{}", free.pretty_print()),
+            }
+            Ok(())
+        }
+        ("import", Some(submatches)) => {
+            let path = submatches.value_of("file").unwrap();
+            let file = File::open(path).map_err(|e| Error::Open(path.to_owned(), e))?;
+            let report = block_on(eva::import_calendar(
+                configuration,
+                BufReader::new(file),
+                &chrono::Local,
+            ))?;
+            match format {
+                Format::Json => output::print("import", &report),
+                Format::Text => println!("{}", report.pretty_print()),
+            }
+            Ok(())
+        }
+        ("sync", Some(submatches)) => {
+            let strategy = submatches.value_of("strategy").unwrap().to_owned();
+            let client = configuration::caldav()?.ok_or_else(|| Error::NoCalDav())?;
+            let pulled = block_on(eva::pull_caldav(configuration, &client, &chrono::Local))?;
+            let eva::BestEffortSchedule { schedule, unschedulable, .. } =
+                block_on(eva::schedule_best_effort(configuration, &strategy))?;
+            let pushed = block_on(eva::push_caldav(configuration, &client, &schedule))?;
+            match format {
+                Format::Json => output::print(
+                    "sync",
+                    serde_json::json!({
+                        "pulled": pulled,
+                        "pushed": pushed,
+                        "unschedulable": unschedulable,
+                    }),
+                ),
+                Format::Text => {
+                    println!("{}", pulled.pretty_print());
+                    println!("{}", pushed.pretty_print());
+                    if !unschedulable.is_empty() {
+                        println!("\n{}", unschedulable.pretty_print());
+                    }
+                }
+            }
             Ok(())
         }
+        ("stats", Some(submatches)) => dispatch_stats(submatches, configuration, format),
+        ("segment", Some(submatches)) => dispatch_segment(submatches, configuration, format),
         _ => unreachable!(),
     }
 }
 
-fn set_field(configuration: &Configuration, field: &str, id: u32, value: &str) -> Result<()> {
-    let mut task = block_on(eva::get_task(configuration, id))?;
-    match field {
-        "content" => task.content = value.to_string(),
-        "deadline" => task.deadline = parse::deadline(value)?,
-        "duration" => task.duration = parse::duration(value)?,
-        "importance" => task.importance = parse::importance(value)?,
+fn dispatch_stats(
+    inputs: &ArgMatches,
+    configuration: &Configuration,
+    format: Format,
+) -> Result<()> {
+    match inputs.subcommand() {
+        ("estimates", Some(_)) => {
+            let stats = block_on(eva::estimate_stats(configuration))?;
+            match format {
+                Format::Json => output::print("estimates", &stats),
+                Format::Text => {
+                    let segments = block_on(eva::time_segments(configuration))?;
+                    println!("{}", (&stats, &segments[..]).pretty_print());
+                    if configuration.correct_estimates && stats.overall.tasks > 0 {
+                        println!(
+                            "\nBecause correct_estimates is on, the schedule makes tasks longer \
+                             when tasks like them took longer than estimated. It does so once \
+                             their time segment, or all of them together, have at least {} \
+                             finished tasks.",
+                            eva::estimates::MIN_TASKS
+                        );
+                    }
+                }
+            }
+            Ok(())
+        }
         _ => unreachable!(),
+    }
+}
+
+fn dispatch_segment(
+    inputs: &ArgMatches,
+    configuration: &Configuration,
+    format: Format,
+) -> Result<()> {
+    let segments = block_on(eva::time_segments(configuration))?;
+    match inputs.subcommand() {
+        ("add", Some(submatches)) => {
+            let name = submatches.value_of("name").unwrap();
+            let ranges = submatches.value_of("ranges").unwrap();
+            let segment = parse::weekly_ranges(ranges)?;
+            let new_segment = eva::time_segment::NewNamedTimeSegment {
+                name: name.to_owned(),
+                ranges: segment.ranges,
+                start: segment.start,
+                period: segment.period,
+                // Spread the hues of the segments around the colour wheel
+                hue: (segments.len() * 137 % 360) as u16,
+            };
+            Ok(block_on(eva::add_time_segment(configuration, new_segment))?)
+        }
+        ("list", Some(_submatches)) => {
+            if format == Format::Json {
+                output::print("segments", &segments);
+                return Ok(());
+            }
+            println!("Time segments:");
+            for segment in &segments {
+                // Indent all lines of segment.pretty_print() by two spaces
+                println!("  {}", segment.pretty_print().split("\n").join("\n  "));
+            }
+            Ok(())
+        }
+        ("set", Some(submatches)) => {
+            let field = submatches.value_of("property").unwrap();
+            let segment = submatches.value_of("segment").unwrap();
+            let value = submatches.value_of("value").unwrap();
+            let mut segment = parse::time_segment(segment, &segments)?.clone();
+            match field {
+                "name" => segment.name = value.to_owned(),
+                "ranges" => {
+                    let weekly = parse::weekly_ranges(value)?;
+                    segment.ranges = weekly.ranges;
+                    segment.start = weekly.start;
+                    segment.period = weekly.period;
+                }
+                _ => unreachable!(),
+            };
+            Ok(block_on(eva::update_time_segment(configuration, segment))?)
+        }
+        ("rm", Some(submatches)) => {
+            let segment = submatches.value_of("segment").unwrap();
+            let segment = parse::time_segment(segment, &segments)?.clone();
+            Ok(block_on(eva::delete_time_segment(configuration, segment))?)
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn focus(configuration: &Configuration, strategy: &str, format: Format) -> Result<()> {
+    let pomodoro = configuration::pomodoro()?;
+    let scheduled = match block_on(eva::current_task(configuration, strategy))? {
+        Some(scheduled) => scheduled,
+        None => {
+            match format {
+                Format::Json => output::print("focus", serde_json::json!({ "task": null })),
+                Format::Text => println!(
+                    "Nothing is scheduled right now. Run `eva schedule` to see what's next."
+                ),
+            }
+            return Ok(());
+        }
     };
-    Ok(block_on(eva::update_task(configuration, task))?)
+    let task = scheduled.task;
+    if format == Format::Text {
+        println!(
+            "Focusing on {}. {} for {}.",
+            task.id,
+            task.content,
+            scheduled.duration.pretty_print()
+        );
+    }
+    let mut entries = Vec::new();
+    let mut breaks = Vec::new();
+    catch_interrupts();
+    let mut interrupted = false;
+    for interval in pomodoro.intervals(scheduled.duration) {
+        match interval {
+            eva::pomodoro::Interval::Work(length) => {
+                let entry = block_on(eva::start_timer(configuration, task.id))?;
+                interrupted = !count_down(configuration, "Work", entry.start + length, format);
+                entries.extend(block_on(eva::stop_timer(configuration))?);
+            }
+            eva::pomodoro::Interval::Break(length) => {
+                let event = block_on(eva::take_break(configuration, length))?;
+                interrupted = !count_down(configuration, "Break", event.end, format);
+                // The break is only there to keep tasks out of it while it lasts
+                block_on(eva::delete_fixed_event(configuration, event.id))?;
+                breaks.push(event);
+            }
+        }
+        if interrupted {
+            break;
+        }
+    }
+    match format {
+        Format::Json => output::print(
+            "focus",
+            serde_json::json!({ "task": task, "time_entries": entries, "breaks": breaks }),
+        ),
+        Format::Text if interrupted => println!(
+            "Stopped. Run `eva focus` to go on where you left off."
+        ),
+        Format::Text => println!(
+            "Time's up. Run `eva done {}` if you finished it, or `eva focus` to go on with \
+             what's next.",
+            task.id
+        ),
+    }
+    Ok(())
+}
+
+/// Set when the user presses Ctrl-C while `focus` is counting down.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
 }
 
-fn handle_error(error: &Error) {
-    eprintln!("{}", error);
+/// Lets Ctrl-C end the count down instead of the whole program, so the timer can still be stopped.
+fn catch_interrupts() {
+    let handler: extern "C" fn(libc::c_int) = interrupt;
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+/// Waits until `end`. In text, it counts down the time that's left on one line.
+///
+/// Returns whether it got to the end, or false when it was interrupted.
+fn count_down(
+    configuration: &Configuration,
+    label: &str,
+    end: DateTime<Utc>,
+    format: Format,
+) -> bool {
+    loop {
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            if format == Format::Text {
+                println!("\r{}: interrupted.", label);
+            }
+            return false;
+        }
+        let left = end - configuration.now();
+        if left <= chrono::Duration::zero() {
+            break;
+        }
+        if format == Format::Text {
+            let (minutes, seconds) = (left.num_minutes(), left.num_seconds() % 60);
+            print!("\r{}: {:02}:{:02} left", label, minutes, seconds);
+            io::stdout().flush().ok();
+        }
+        let tick = std::cmp::min(left, chrono::Duration::seconds(1));
+        thread::sleep(tick.to_std().unwrap());
+    }
+    if format == Format::Text {
+        println!("\r{}: done.     ", label);
+    }
+    true
+}
+
+fn handle_error(error: &Error, format: Format) {
+    match format {
+        Format::Text => eprintln!("{}", error.to_string().trim_end()),
+        Format::Json => output::print_error(error.kind(), error),
+    }
 
     // Print backtrace when RUST_BACKTRACE=1
     if let Some(backtrace) = error.backtrace() {
@@ -0,0 +1,62 @@
+//! The machine-readable output of `--output json`.
+//!
+//! Every command prints at most one JSON document: an object with the version of the schema
+//! under `"version"` and the data under a key named after it, like `{"version": 1, "tasks": []}`.
+//! Errors are printed to stderr as `{"version": 1, "error": {"kind": ..., "message": ...}}`.
+//! Times are in RFC 3339 and durations in seconds.
+//!
+//! The version is increased whenever a field is removed or changes meaning. Adding fields
+//! doesn't change it.
+
+use serde::Serialize;
+use serde_json::json;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: Option<&str>) -> Format {
+        match name {
+            Some("json") => Format::Json,
+            _ => Format::Text,
+        }
+    }
+
+    /// Finds out which format was asked for when the arguments couldn't be parsed.
+    pub fn from_raw_args(args: impl Iterator<Item = String>) -> Format {
+        let args: Vec<String> = args.collect();
+        let json = args.iter().any(|arg| arg == "--output=json")
+            || args
+                .windows(2)
+                .any(|pair| pair[0] == "--output" && pair[1] == "json");
+        if json {
+            Format::Json
+        } else {
+            Format::Text
+        }
+    }
+}
+
+/// Prints the document `{"version": SCHEMA_VERSION, <key>: <value>}` on stdout.
+pub fn print(key: &str, value: impl Serialize) {
+    println!("{}", json!({ "version": SCHEMA_VERSION, key: value }));
+}
+
+/// Prints an error document on stderr.
+pub fn print_error(kind: &str, error: &dyn failure::Fail) {
+    eprintln!(
+        "{}",
+        json!({
+            "version": SCHEMA_VERSION,
+            "error": {
+                "kind": kind,
+                "message": error.to_string().trim_end(),
+            },
+        })
+    );
+}
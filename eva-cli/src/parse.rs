@@ -1,6 +1,9 @@
 use chrono::prelude::*;
 use chrono::Duration;
+use eva::recurrence::Recurrence;
+use eva::time_segment::{NamedTimeSegment, UnnamedTimeSegment};
 use failure::Fail;
+use itertools::Itertools;
 
 #[derive(Debug, Fail)]
 #[fail(
@@ -49,6 +52,13 @@ pub fn duration(duration_hours: &str) -> Result<Duration> {
     Ok(Duration::minutes((60.0 * hours) as i64))
 }
 
+pub fn min_chunk_duration(duration_hours: &str) -> Result<Option<Duration>> {
+    if duration_hours == "none" {
+        return Ok(None);
+    }
+    duration(duration_hours).map(Some)
+}
+
 pub fn deadline(datetime: &str) -> Result<DateTime<Utc>> {
     Local
         .datetime_from_str(datetime, "%-d %b %Y %-H:%M")
@@ -59,3 +69,245 @@ pub fn deadline(datetime: &str) -> Result<DateTime<Utc>> {
         })
         .map(|local_datetime| local_datetime.with_timezone(&Utc))
 }
+
+pub fn available_from(datetime: &str) -> Result<Option<DateTime<Utc>>> {
+    if datetime == "none" {
+        return Ok(None);
+    }
+    deadline(datetime).map(Some)
+}
+
+pub fn recurrence(rule: &str) -> Result<Option<Recurrence>> {
+    if rule == "none" {
+        return Ok(None);
+    }
+    rule.parse::<Recurrence>().map(Some).map_err(|e| Error {
+        type_: "recurrence".to_owned(),
+        input: rule.to_owned(),
+        suggestion: format!(
+            "{}. Try something like 'FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10', or 'none'.",
+            e.reason
+        ),
+    })
+}
+
+pub fn time_segment<'a>(
+    segment_str: &str,
+    segments: &'a [NamedTimeSegment],
+) -> Result<&'a NamedTimeSegment> {
+    segments
+        .iter()
+        .find(|segment| segment.id.to_string() == segment_str)
+        .or_else(|| {
+            segments
+                .iter()
+                .find(|segment| segment.name.to_lowercase() == segment_str.to_lowercase())
+        })
+        .ok_or_else(|| Error {
+            type_: "time segment".to_owned(),
+            input: segment_str.to_owned(),
+            suggestion: format!(
+                "Try the name or id of one of these: {}.",
+                segments.iter().map(|segment| &segment.name).join(", ")
+            ),
+        })
+}
+
+/// Parses ranges like 'Mon-Fri 09:00-17:00, Sat 10:00-12:00' into a weekly time segment starting
+/// at the beginning of the current week.
+pub fn weekly_ranges(ranges_str: &str) -> Result<UnnamedTimeSegment> {
+    let error = |reason: String| Error {
+        type_: "time ranges".to_owned(),
+        input: ranges_str.to_owned(),
+        suggestion: format!(
+            "{}. Try something like 'Mon-Fri 09:00-17:00, Sat 10:00-12:00'.",
+            reason
+        ),
+    };
+
+    let today = Local::today();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let mut ranges = vec![];
+    for part in ranges_str.split(',') {
+        let words = part.split_whitespace().collect::<Vec<_>>();
+        let (days, times) = match words[..] {
+            [days, times] => (days, times),
+            _ => {
+                return Err(error(format!(
+                    "'{}' isn't a day range and a time range",
+                    part.trim()
+                )))
+            }
+        };
+        let (first_day, last_day) = match days.splitn(2, '-').collect::<Vec<_>>()[..] {
+            ["daily"] => (Weekday::Mon, Weekday::Sun),
+            [day] => {
+                let day = day
+                    .parse::<Weekday>()
+                    .ok()
+                    .ok_or_else(|| error(format!("'{}' isn't a day", day)))?;
+                (day, day)
+            }
+            [first, last] => (
+                first
+                    .parse::<Weekday>()
+                    .ok()
+                    .ok_or_else(|| error(format!("'{}' isn't a day", first)))?,
+                last.parse::<Weekday>()
+                    .ok()
+                    .ok_or_else(|| error(format!("'{}' isn't a day", last)))?,
+            ),
+            _ => unreachable!(),
+        };
+        let (start_time, end_time) = match times
+            .splitn(2, '-')
+            .map(|time| NaiveTime::parse_from_str(time, "%H:%M"))
+            .collect::<Vec<_>>()[..]
+        {
+            [Ok(start_time), Ok(end_time)] if start_time < end_time => (start_time, end_time),
+            _ => {
+                return Err(error(format!(
+                    "'{}' isn't a time range within a single day",
+                    times
+                )))
+            }
+        };
+
+        // A range like Sat-Mon wraps around to the start of the week
+        let first = first_day.num_days_from_monday() as i64;
+        let n_days = (last_day.num_days_from_monday() as i64 - first).rem_euclid(7) + 1;
+        for day in (first..first + n_days).map(|day| monday + Duration::days(day % 7)) {
+            match (day.and_time(start_time), day.and_time(end_time)) {
+                (Some(start), Some(end)) => {
+                    ranges.push(start.with_timezone(&Utc)..end.with_timezone(&Utc))
+                }
+                _ => {
+                    return Err(error(format!(
+                        "{} doesn't exist this week because of daylight saving time",
+                        times
+                    )))
+                }
+            }
+        }
+    }
+
+    // The ranges have to be in order, and overlapping ones are merged
+    ranges.sort_by_key(|range| range.start);
+    let ranges = ranges
+        .into_iter()
+        .coalesce(|previous, range| {
+            if range.start <= previous.end {
+                Ok(previous.start..previous.end.max(range.end))
+            } else {
+                Err((previous, range))
+            }
+        })
+        .collect();
+    Ok(UnnamedTimeSegment {
+        ranges,
+        start: monday.and_hms(0, 0, 0).with_timezone(&Utc),
+        period: Duration::weeks(1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ranges of `segment` as weekdays with start and end times in the local time zone.
+    fn local_ranges(segment: &UnnamedTimeSegment) -> Vec<(Weekday, NaiveTime, NaiveTime)> {
+        segment
+            .ranges
+            .iter()
+            .map(|range| {
+                let start = range.start.with_timezone(&Local);
+                let end = range.end.with_timezone(&Local);
+                (start.weekday(), start.time(), end.time())
+            })
+            .collect()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn available_from_is_a_time_or_none() {
+        assert_eq!(available_from("none").unwrap(), None);
+        let local = Local
+            .with_ymd_and_hms(2017, 7, 4, 6, 5, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(available_from("4 Jul 2017 6:05").unwrap(), Some(local));
+        let error = available_from("tomorrow").unwrap_err();
+        assert!(error.to_string().contains("'4 Jul 2017 6:05'"));
+    }
+
+    #[test]
+    fn weekly_ranges_start_on_monday() {
+        let segment = weekly_ranges("Tue 09:00-17:00, Thu 10:00-12:30").unwrap();
+        let start = segment.start.with_timezone(&Local);
+        assert_eq!(start.weekday(), Weekday::Mon);
+        assert_eq!(start.time(), time(0, 0));
+        assert_eq!(segment.period, Duration::weeks(1));
+        assert_eq!(
+            local_ranges(&segment),
+            [
+                (Weekday::Tue, time(9, 0), time(17, 0)),
+                (Weekday::Thu, time(10, 0), time(12, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_ranges_wrap_around_the_end_of_the_week() {
+        let segment = weekly_ranges("Sat-Mon 10:00-12:00").unwrap();
+        assert_eq!(
+            local_ranges(&segment),
+            [
+                (Weekday::Mon, time(10, 0), time(12, 0)),
+                (Weekday::Sat, time(10, 0), time(12, 0)),
+                (Weekday::Sun, time(10, 0), time(12, 0)),
+            ]
+        );
+        let daily = weekly_ranges("daily 08:00-09:00").unwrap();
+        assert_eq!(daily.ranges.len(), 7);
+    }
+
+    #[test]
+    fn weekly_ranges_merge_overlapping_ranges() {
+        let segment = weekly_ranges("Mon-Tue 09:00-12:00, Mon 11:00-13:00").unwrap();
+        assert_eq!(
+            local_ranges(&segment),
+            [
+                (Weekday::Mon, time(9, 0), time(13, 0)),
+                (Weekday::Tue, time(9, 0), time(12, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_ranges_reject_invalid_days_and_times() {
+        let error = weekly_ranges("Funday 09:00-17:00").unwrap_err();
+        assert!(error.to_string().contains("'Funday' isn't a day"));
+        let error = weekly_ranges("Mon-Someday 09:00-17:00").unwrap_err();
+        assert!(error.to_string().contains("'Someday' isn't a day"));
+        for ranges in &[
+            "Mon 17:00-09:00",
+            "Mon 9-17",
+            "Mon 09:00",
+            "Mon 09:00-25:00",
+        ] {
+            let error = weekly_ranges(ranges).unwrap_err();
+            assert!(
+                error.to_string().contains("isn't a time range"),
+                "{}",
+                ranges
+            );
+        }
+        let error = weekly_ranges("Mon").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("isn't a day range and a time range"));
+    }
+}
@@ -7,16 +7,325 @@ pub(crate) trait PrettyPrint {
 
 impl PrettyPrint for eva::Schedule<eva::Task> {
     fn pretty_print(&self) -> String {
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|scheduled| (scheduled.when, scheduled.pretty_print()));
+        let events = self
+            .events
+            .iter()
+            .map(|event| (event.start, event.pretty_print()));
         format!(
             "Schedule:\n  {}",
-            self.0.iter().map(PrettyPrint::pretty_print).join("\n  ")
+            tasks
+                .merge_by(events, |(task_start, _), (event_start, _)| {
+                    task_start <= event_start
+                })
+                .map(|(_, line)| line)
+                .join("\n  ")
         )
     }
 }
 
 impl PrettyPrint for eva::Scheduled<eva::Task> {
     fn pretty_print(&self) -> String {
-        format!("{}: {}", self.when.pretty_print(), self.task.pretty_print())
+        if self.duration == self.task.duration {
+            format!("{}: {}", self.when.pretty_print(), self.task.pretty_print())
+        } else {
+            format!(
+                "{} ({} of it): {}",
+                self.when.pretty_print(),
+                self.duration.pretty_print(),
+                self.task.pretty_print()
+            )
+        }
+    }
+}
+
+impl PrettyPrint for Vec<eva::Unschedulable<eva::Task>> {
+    fn pretty_print(&self) -> String {
+        format!(
+            "Warning: these tasks don't fit in the schedule:\n  {}",
+            self.iter()
+                .map(|unschedulable| unschedulable.pretty_print())
+                .join("\n")
+                .split('\n')
+                .join("\n  ")
+        )
+    }
+}
+
+impl PrettyPrint for eva::Unschedulable<eva::Task> {
+    fn pretty_print(&self) -> String {
+        let reason = match self.reason {
+            eva::UnschedulableReason::DeadlinePassed => "its deadline has passed".to_owned(),
+            eva::UnschedulableReason::DeadlineTooSoon => {
+                "its deadline is too soon to get it done in time".to_owned()
+            }
+            eva::UnschedulableReason::NotEnoughTime => {
+                "there isn't enough free time left before its deadline".to_owned()
+            }
+            eva::UnschedulableReason::EstimateUsedUp => {
+                "its estimate has been used up, but it isn't done yet".to_owned()
+            }
+            eva::UnschedulableReason::PinConflict => match &self.pin_conflict {
+                Some(conflict) => format!("{}, so it's planned as if it weren't pinned", conflict),
+                None => {
+                    "of what else is planned, so it's planned as if it weren't pinned".to_owned()
+                }
+            },
+        };
+        let fits = match self.reason {
+            eva::UnschedulableReason::PinConflict => "fit at the time it's pinned to",
+            _ => "fit",
+        };
+        let extension = match self.deadline_extension {
+            Some(extension) => format!(
+                " It would fit if the deadline were {} later.",
+                extension.pretty_print()
+            ),
+            None => String::new(),
+        };
+        format!(
+            "{}\n{}It doesn't {} because {}.{}",
+            self.task.pretty_print(),
+            " ".repeat(format!("{}. ", self.task.id).len()),
+            fits,
+            reason,
+            extension
+        )
+    }
+}
+
+impl PrettyPrint for eva::Explanation<eva::Task> {
+    fn pretty_print(&self) -> String {
+        let indent = " ".repeat(format!("{}. ", self.task.id).len());
+        format!(
+            "{}\n{}{}",
+            self.task.pretty_print(),
+            indent,
+            self.steps
+                .iter()
+                .map(|step| step.pretty_print())
+                .join(&format!("\n{}", indent))
+        )
+    }
+}
+
+impl PrettyPrint for eva::Step<eva::Task> {
+    fn pretty_print(&self) -> String {
+        let place = format!(
+            "{} until {}",
+            self.start.pretty_print(),
+            self.end.pretty_print()
+        );
+        let (what, later) = match self.phase {
+            eva::Phase::DeadlinePacking => (format!("Put as late as possible at {}", place), true),
+            eva::Phase::ForwardPlacement => {
+                (format!("Put as early as possible at {}", place), false)
+            }
+            eva::Phase::PullForward if self.moved > chrono::Duration::zero() => (
+                format!(
+                    "Pulled forward by {} to {}",
+                    self.moved.pretty_print(),
+                    place
+                ),
+                false,
+            ),
+            eva::Phase::PullForward => ("Not pulled forward any further".to_string(), false),
+            eva::Phase::Kept => return format!("Kept at {} as in the accepted schedule.", place),
+            eva::Phase::Pinned => return format!("Pinned to {}.", place),
+        };
+        let reasons = if self.constraints.is_empty() {
+            format!(
+                "the free time {} it is too short for it",
+                if later { "after" } else { "before" }
+            )
+        } else {
+            self.constraints
+                .iter()
+                .map(|constraint| constraint_reason(constraint, later))
+                .join(", and ")
+        };
+        format!("{}: {}.", what, reasons)
+    }
+}
+
+/// Describes what a constraint on a task means, when it keeps the task from going `later` or
+/// earlier.
+fn constraint_reason(constraint: &eva::Constraint<eva::Task>, later: bool) -> String {
+    let name = |task: &eva::Task| format!("{}. {}", task.id, task.content);
+    match constraint {
+        eva::Constraint::Deadline => "it ends at its deadline".to_string(),
+        eva::Constraint::Start => "it starts right away".to_string(),
+        eva::Constraint::AvailableFrom => "it starts as soon as it's available".to_string(),
+        eva::Constraint::Prerequisite { task } => {
+            format!("it waits for {}, which ends right before it", name(task))
+        }
+        eva::Constraint::Dependent { task } => {
+            format!(
+                "{} waits for it and has to start right after it",
+                name(task)
+            )
+        }
+        eva::Constraint::Task { task } if later => format!("{} starts right after it", name(task)),
+        eva::Constraint::Task { task } => format!("{} ends right before it", name(task)),
+        eva::Constraint::Unavailable { start, end } => format!(
+            "it runs into time outside its time segment or taken by an event (from {} until {})",
+            start.pretty_print(),
+            end.pretty_print()
+        ),
+    }
+}
+
+impl PrettyPrint for Vec<eva::Move<eva::Task>> {
+    fn pretty_print(&self) -> String {
+        if self.is_empty() {
+            return "Nothing moved since the accepted schedule.".to_owned();
+        }
+        format!(
+            "Changes since the accepted schedule:\n  {}",
+            self.iter()
+                .map(|moved| moved.pretty_print())
+                .join("\n")
+                .split('\n')
+                .join("\n  ")
+        )
+    }
+}
+
+impl PrettyPrint for eva::Move<eva::Task> {
+    fn pretty_print(&self) -> String {
+        let when = |chunks: &[std::ops::Range<DateTime<Utc>>]| {
+            chunks
+                .iter()
+                .map(|chunk| chunk.start.pretty_print())
+                .join(" and ")
+        };
+        let what = if self.from.is_empty() {
+            format!("It's new in the schedule at {}.", when(&self.to))
+        } else if self.to.is_empty() {
+            format!(
+                "It was at {}, but it doesn't fit anymore.",
+                when(&self.from)
+            )
+        } else {
+            format!("Moved from {} to {}.", when(&self.from), when(&self.to))
+        };
+        format!(
+            "{}\n{}{}",
+            self.task.pretty_print(),
+            " ".repeat(format!("{}. ", self.task.id).len()),
+            what
+        )
+    }
+}
+
+impl PrettyPrint for eva::FreeSlots {
+    fn pretty_print(&self) -> String {
+        let slots = if self.slots.is_empty() {
+            "There are no free slots that are long enough.".to_owned()
+        } else {
+            format!(
+                "Free slots:\n  {}",
+                self.slots
+                    .iter()
+                    .map(|slot| format!(
+                        "{} until {} ({})",
+                        slot.start.pretty_print(),
+                        slot.end.pretty_print(),
+                        (slot.end - slot.start).pretty_print()
+                    ))
+                    .join("\n  ")
+            )
+        };
+        format!(
+            "{}\nIn total, {} is free.",
+            slots,
+            self.total.pretty_print()
+        )
+    }
+}
+
+impl PrettyPrint for eva::FixedEvent {
+    fn pretty_print(&self) -> String {
+        format!(
+            "{}: {} (until {})",
+            self.start.pretty_print(),
+            self.title,
+            self.end.pretty_print()
+        )
+    }
+}
+
+impl PrettyPrint for (&eva::TimeEntry, &eva::Task) {
+    fn pretty_print(&self) -> String {
+        let (entry, task) = self;
+        let (end, running) = match entry.end {
+            Some(end) => (end.pretty_print(), ""),
+            None => ("now".to_owned(), ", still running"),
+        };
+        format!(
+            "{} until {} ({}{}): {}. {}",
+            entry.start.pretty_print(),
+            end,
+            entry.duration(Utc::now()).pretty_print(),
+            running,
+            task.id,
+            task.content
+        )
+    }
+}
+
+impl PrettyPrint
+    for (
+        &eva::estimates::EstimateStats,
+        &[eva::time_segment::NamedTimeSegment],
+    )
+{
+    fn pretty_print(&self) -> String {
+        let (stats, segments) = self;
+        if stats.overall.tasks == 0 {
+            return "You haven't finished any tasks that you tracked time on yet, so there's \
+                    nothing to compare your estimates to."
+                .to_owned();
+        }
+        let mut per_segment = stats.per_segment.iter().map(|segment| {
+            let name = segments
+                .iter()
+                .find(|named| named.id == segment.time_segment_id)
+                .map_or_else(
+                    || segment.time_segment_id.to_string(),
+                    |named| named.name.clone(),
+                );
+            format!("{}: {}", name, segment.accuracy.pretty_print())
+        });
+        let mut per_month = stats.per_month.iter().map(|month| {
+            format!(
+                "{}: {}",
+                month.month.format("%b %Y"),
+                month.accuracy.pretty_print()
+            )
+        });
+        format!(
+            "Overall, your tasks take {}\nPer time segment:\n  {}\nPer month:\n  {}",
+            stats.overall.pretty_print(),
+            per_segment.join("\n  "),
+            per_month.join("\n  ")
+        )
+    }
+}
+
+impl PrettyPrint for eva::estimates::Accuracy {
+    fn pretty_print(&self) -> String {
+        format!(
+            "{:.2} times as long as estimated ({} {}: {} estimated, {} tracked)",
+            self.factor,
+            self.tasks,
+            if self.tasks == 1 { "task" } else { "tasks" },
+            self.estimated.pretty_print(),
+            self.actual.pretty_print()
+        )
     }
 }
 
@@ -35,14 +344,182 @@ impl PrettyPrint for DateTime<Utc> {
 impl PrettyPrint for eva::Task {
     fn pretty_print(&self) -> String {
         let prefix = format!("{}. ", self.id);
+        let available_from = match self.available_from {
+            Some(available_from) => format!(", available from: {}", available_from.pretty_print()),
+            None => String::new(),
+        };
+        let recurrence = match self.recurrence {
+            Some(ref recurrence) => format!(", repeats: {}", recurrence),
+            None => String::new(),
+        };
+        let split = match self.min_chunk_duration {
+            Some(duration) => format!(", split in chunks of at least {}", duration.pretty_print()),
+            None => String::new(),
+        };
+        let depends_on = if self.depends_on.is_empty() {
+            String::new()
+        } else {
+            format!(", after: {}", self.depends_on.iter().join(", "))
+        };
+        let pin = match self.pin {
+            Some(eva::Pin::At(at)) => format!(", pinned to: {}", at.pretty_print()),
+            Some(eva::Pin::NotBefore(at)) => format!(", not before: {}", at.pretty_print()),
+            None => String::new(),
+        };
+        let blocked = match self.blocked {
+            Some(eva::Blocked {
+                ref reason,
+                follow_up: Some(follow_up),
+            }) => format!(
+                ", waiting for: {}, follow up: {}",
+                reason,
+                follow_up.pretty_print()
+            ),
+            Some(eva::Blocked {
+                ref reason,
+                follow_up: None,
+            }) => format!(", waiting for: {}", reason),
+            None => String::new(),
+        };
+        let completed_at = match self.completed_at {
+            Some(completed_at) => format!(", done: {}", completed_at.pretty_print()),
+            None => String::new(),
+        };
         format!(
-            "{}{}\n{}(deadline: {}, duration: {}, importance: {})",
+            "{}{}\n{}(deadline: {}, duration: {}, importance: {}{}{}{}{}{}{}{})",
             prefix,
             self.content,
             " ".repeat(prefix.len()),
             self.deadline.pretty_print(),
             self.duration.pretty_print(),
-            self.importance
+            self.importance,
+            available_from,
+            recurrence,
+            split,
+            depends_on,
+            pin,
+            blocked,
+            completed_at
+        )
+    }
+}
+
+impl PrettyPrint for eva::icalendar::ImportReport {
+    fn pretty_print(&self) -> String {
+        let section = |title: &str, lines: Vec<String>| {
+            if lines.is_empty() {
+                String::new()
+            } else {
+                // Indent all lines by two spaces
+                format!(
+                    "{}:\n  {}\n",
+                    title,
+                    lines.join("\n").split('\n').join("\n  ")
+                )
+            }
+        };
+        let imported = |imported: &Vec<eva::icalendar::Imported>| {
+            imported
+                .iter()
+                .map(|imported| match imported {
+                    eva::icalendar::Imported::Task(task) => task.pretty_print(),
+                    eva::icalendar::Imported::Event(event) => event.pretty_print(),
+                })
+                .collect()
+        };
+        let skipped = self
+            .skipped
+            .iter()
+            .map(|skipped| format!("{}: {}", skipped.summary, skipped.reason))
+            .collect();
+        let report = format!(
+            "{}{}{}{}",
+            section("Created", imported(&self.created)),
+            section("Updated", imported(&self.updated)),
+            section("Deleted", imported(&self.deleted)),
+            section("Skipped", skipped)
+        );
+        if report.is_empty() {
+            "There was nothing to import.".to_owned()
+        } else {
+            report.trim_end().to_owned()
+        }
+    }
+}
+
+impl PrettyPrint for eva::caldav::PushReport {
+    fn pretty_print(&self) -> String {
+        let mut report = format!(
+            "Put the schedule in the calendar: {} new, {} updated and {} removed events.",
+            self.created.len(),
+            self.updated.len(),
+            self.deleted.len()
+        );
+        if !self.conflicts.is_empty() {
+            report.push_str(&format!(
+                "\nThese events were changed in the calendar, so I left them alone:\n  {}",
+                self.conflicts.join("\n  ")
+            ));
+        }
+        report
+    }
+}
+
+impl PrettyPrint for eva::time_segment::NamedTimeSegment {
+    fn pretty_print(&self) -> String {
+        let prefix = format!("{}. ", self.id);
+        let local_ranges = self.ranges.iter().map(|range| {
+            let start = range.start.with_timezone(&Local);
+            let end = range.end.with_timezone(&Local);
+            (start.weekday(), start.weekday(), start.time(), end.time())
+        });
+        let ranges = if self.period == chrono::Duration::days(1) {
+            local_ranges
+                .map(|(_, _, start, end)| {
+                    format!("daily {}-{}", start.format("%H:%M"), end.format("%H:%M"))
+                })
+                .join(", ")
+        } else if self.period == chrono::Duration::weeks(1) {
+            // Write consecutive days with the same hours as a single day range, like 'Mon-Fri'
+            local_ranges
+                .coalesce(|previous, range| {
+                    if range.0 == previous.1.succ()
+                        && (range.2, range.3) == (previous.2, previous.3)
+                    {
+                        Ok((previous.0, range.1, range.2, range.3))
+                    } else {
+                        Err((previous, range))
+                    }
+                })
+                .map(|(first_day, last_day, start, end)| {
+                    let days = if first_day == last_day {
+                        first_day.to_string()
+                    } else {
+                        format!("{}-{}", first_day, last_day)
+                    };
+                    format!("{} {}-{}", days, start.format("%H:%M"), end.format("%H:%M"))
+                })
+                .join(", ")
+        } else {
+            format!(
+                "every {}: {}",
+                self.period.pretty_print(),
+                self.ranges
+                    .iter()
+                    .map(|range| format!(
+                        "{} until {}",
+                        range.start.pretty_print(),
+                        range.end.pretty_print()
+                    ))
+                    .join(", ")
+            )
+        };
+        format!(
+            "{}{}\n{}({})",
+            prefix,
+            self.name,
+            " ".repeat(prefix.len()),
+            ranges
         )
     }
 }
@@ -0,0 +1 @@
+DROP TABLE fixed_events;
@@ -0,0 +1,7 @@
+CREATE TABLE fixed_events (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  title TEXT NOT NULL,
+  start INTEGER NOT NULL,
+  end INTEGER NOT NULL,
+  time_segment_id INTEGER
+);
@@ -0,0 +1,12 @@
+ALTER TABLE tasks RENAME TO old_tasks;
+CREATE TABLE tasks (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  content TEXT NOT NULL,
+  deadline TEXT NOT NULL,
+  duration INTEGER NOT NULL,
+  importance INTEGER NOT NULL,
+  time_segment_id INTEGER NOT NULL DEFAULT 0
+);
+INSERT INTO tasks (id, content, deadline, duration, importance, time_segment_id)
+SELECT id, content, deadline, duration, importance, time_segment_id FROM old_tasks;
+DROP TABLE old_tasks;
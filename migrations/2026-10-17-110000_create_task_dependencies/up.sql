@@ -0,0 +1,5 @@
+CREATE TABLE task_dependencies (
+  task_id INTEGER NOT NULL,
+  prerequisite_id INTEGER NOT NULL,
+  PRIMARY KEY (task_id, prerequisite_id)
+);
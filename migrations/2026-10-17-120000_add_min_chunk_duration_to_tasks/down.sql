@@ -0,0 +1,13 @@
+ALTER TABLE tasks RENAME TO old_tasks;
+CREATE TABLE tasks (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  content TEXT NOT NULL,
+  deadline TEXT NOT NULL,
+  duration INTEGER NOT NULL,
+  importance INTEGER NOT NULL,
+  time_segment_id INTEGER NOT NULL DEFAULT 0,
+  recurrence TEXT
+);
+INSERT INTO tasks (id, content, deadline, duration, importance, time_segment_id, recurrence)
+SELECT id, content, deadline, duration, importance, time_segment_id, recurrence FROM old_tasks;
+DROP TABLE old_tasks;
@@ -0,0 +1 @@
+ALTER TABLE tasks ADD COLUMN min_chunk_duration INTEGER;
@@ -0,0 +1,31 @@
+ALTER TABLE tasks RENAME TO old_tasks;
+CREATE TABLE tasks (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  content TEXT NOT NULL,
+  deadline TEXT NOT NULL,
+  duration INTEGER NOT NULL,
+  importance INTEGER NOT NULL,
+  time_segment_id INTEGER NOT NULL DEFAULT 0,
+  recurrence TEXT,
+  min_chunk_duration INTEGER,
+  completed_at INTEGER
+);
+INSERT INTO tasks (id, content, deadline, duration, importance, time_segment_id, recurrence,
+                   min_chunk_duration, completed_at)
+SELECT id, content, deadline, duration, importance, time_segment_id, recurrence,
+       min_chunk_duration, completed_at
+FROM old_tasks;
+DROP TABLE old_tasks;
+
+ALTER TABLE fixed_events RENAME TO old_fixed_events;
+CREATE TABLE fixed_events (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  title TEXT NOT NULL,
+  start INTEGER NOT NULL,
+  end INTEGER NOT NULL,
+  time_segment_id INTEGER
+);
+INSERT INTO fixed_events (id, title, start, end, time_segment_id)
+SELECT id, title, start, end, time_segment_id
+FROM old_fixed_events;
+DROP TABLE old_fixed_events;
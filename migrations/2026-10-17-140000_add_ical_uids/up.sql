@@ -0,0 +1,2 @@
+ALTER TABLE tasks ADD COLUMN ical_uid TEXT;
+ALTER TABLE fixed_events ADD COLUMN ical_uid TEXT;
@@ -0,0 +1,6 @@
+CREATE TABLE caldav_resources (
+  href TEXT PRIMARY KEY NOT NULL,
+  etag TEXT NOT NULL,
+  ical_uid TEXT NOT NULL,
+  pushed BOOLEAN NOT NULL
+);
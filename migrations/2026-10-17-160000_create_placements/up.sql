@@ -0,0 +1,7 @@
+CREATE TABLE placements (
+  task_id INTEGER NOT NULL,
+  deadline INTEGER NOT NULL,
+  start INTEGER NOT NULL,
+  end INTEGER NOT NULL,
+  PRIMARY KEY (task_id, deadline, start)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE tasks ADD COLUMN pinned_at INTEGER;
+ALTER TABLE tasks ADD COLUMN pinned_exactly BOOLEAN NOT NULL DEFAULT 0;
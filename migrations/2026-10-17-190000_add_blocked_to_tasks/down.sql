@@ -0,0 +1,23 @@
+ALTER TABLE tasks RENAME TO old_tasks;
+CREATE TABLE tasks (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  content TEXT NOT NULL,
+  deadline TEXT NOT NULL,
+  duration INTEGER NOT NULL,
+  importance INTEGER NOT NULL,
+  time_segment_id INTEGER NOT NULL DEFAULT 0,
+  recurrence TEXT,
+  min_chunk_duration INTEGER,
+  completed_at INTEGER,
+  ical_uid TEXT,
+  pinned_at INTEGER,
+  pinned_exactly BOOLEAN NOT NULL DEFAULT 0,
+  available_from INTEGER
+);
+INSERT INTO tasks (id, content, deadline, duration, importance, time_segment_id, recurrence,
+                   min_chunk_duration, completed_at, ical_uid, pinned_at, pinned_exactly,
+                   available_from)
+SELECT id, content, deadline, duration, importance, time_segment_id, recurrence,
+       min_chunk_duration, completed_at, ical_uid, pinned_at, pinned_exactly, available_from
+FROM old_tasks;
+DROP TABLE old_tasks;
@@ -0,0 +1,2 @@
+ALTER TABLE tasks ADD COLUMN blocked_reason TEXT;
+ALTER TABLE tasks ADD COLUMN follow_up_at INTEGER;
@@ -0,0 +1,6 @@
+CREATE TABLE time_entries (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  task_id INTEGER NOT NULL,
+  start INTEGER NOT NULL,
+  end INTEGER
+);
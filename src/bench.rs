@@ -0,0 +1,33 @@
+//! The scheduler's internals, for the benchmarks in `benches/`. This isn't part of the API.
+
+use chrono::prelude::*;
+use chrono::Duration;
+
+use crate::recurrence::Occurrence;
+use crate::scheduling;
+use crate::time_segment::TimeSegment;
+use crate::{BestEffortSchedule, Strategy, Task};
+
+pub use crate::scheduling::schedule_tree::ScheduleTree;
+pub use crate::util::Lcg;
+
+/// Schedules the tasks of every time segment after `start`, like `schedule_best_effort` does
+/// with the tasks in the database.
+pub fn schedule<Segment: TimeSegment>(
+    start: DateTime<Utc>,
+    tasks_per_segment: Vec<(Segment, Vec<Task>)>,
+    strategy: &dyn Strategy,
+) -> Option<BestEffortSchedule<Task>> {
+    let occurrences_per_segment = tasks_per_segment.into_iter().map(|(segment, tasks)| {
+        let occurrences = tasks.into_iter().map(|task| Occurrence {
+            task,
+            not_before: None,
+            tracked: Duration::zero(),
+            correction: Duration::zero(),
+        });
+        (segment, occurrences)
+    });
+    scheduling::Schedule::schedule_best_effort(start, occurrences_per_segment, vec![], strategy)
+        .map(|best_effort| best_effort.map_tasks(|occurrence| occurrence.task))
+        .ok()
+}
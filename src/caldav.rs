@@ -0,0 +1,466 @@
+//! A small client for CalDAV servers ([RFC 4791](https://tools.ietf.org/html/rfc4791)), used to
+//! put schedules in a calendar collection and to read the events in it back as busy time.
+//!
+//! Calendar objects are told apart by their href, the path to them on the server. Their ETags
+//! tell whether they changed since they were last seen.
+
+use std::collections::HashMap;
+
+use failure::Fail;
+use url::Url;
+
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "The CalDAV URL {} is invalid: {}", _0, _1)]
+    Url(String, #[cause] url::ParseError),
+    #[fail(
+        display = "I could not reach the CalDAV server while trying to {}: {}",
+        _0, _1
+    )]
+    Request(&'static str, String),
+    #[fail(display = "The CalDAV server refused to {} {} (status {})", _0, _1, _2)]
+    Status(&'static str, String, u16),
+    #[fail(
+        display = "{} changed on the CalDAV server since it was last synchronised",
+        _0
+    )]
+    Changed(String),
+    #[fail(display = "I don't understand the CalDAV server's answer: {}", _0)]
+    Response(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+const DAV: &str = "DAV:";
+const PROPFIND_ETAGS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getetag/>
+  </d:prop>
+</d:propfind>"#;
+
+/// What pushing a schedule changed on the CalDAV server, by href.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PushReport {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Events that were changed on the server since they were pushed, and so weren't touched.
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    collection: Url,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    /// Connects to the calendar collection at the given URL, like
+    /// `http://localhost:5232/me/calendar/`, optionally logging in with HTTP basic
+    /// authentication.
+    pub fn new(collection: &str, username: Option<&str>, password: Option<&str>) -> Result<Client> {
+        // Without a trailing slash, hrefs would be resolved relative to the collection's parent
+        let collection_with_slash = if collection.ends_with('/') {
+            collection.to_owned()
+        } else {
+            format!("{}/", collection)
+        };
+        let mut url =
+            Url::parse(&collection_with_slash).map_err(|e| Error::Url(collection.to_owned(), e))?;
+        if let Some(username) = username {
+            url.set_username(username)
+                .map_err(|_| Error::Url(collection.to_owned(), url::ParseError::EmptyHost))?;
+        }
+        if password.is_some() {
+            url.set_password(password)
+                .map_err(|_| Error::Url(collection.to_owned(), url::ParseError::EmptyHost))?;
+        }
+        Ok(Client {
+            collection: url,
+            agent: ureq::AgentBuilder::new().build(),
+        })
+    }
+
+    /// The href at which Eva stores the calendar object with the given UID.
+    pub fn href_for(&self, uid: &str) -> String {
+        format!("{}{}.ics", self.collection.path(), uid)
+    }
+
+    /// Lists the calendar objects in the collection: their ETags by their href.
+    pub fn etags(&self) -> Result<HashMap<String, String>> {
+        let response = self.send(
+            "list the calendar",
+            "PROPFIND",
+            self.collection.path(),
+            &[
+                ("Depth", "1"),
+                ("Content-Type", "application/xml; charset=utf-8"),
+            ],
+            PROPFIND_ETAGS,
+        )?;
+        let body = response
+            .into_string()
+            .map_err(|e| Error::Response(e.to_string()))?;
+        parse_etags(&body)
+    }
+
+    /// Downloads the calendar object at the href.
+    pub fn get(&self, href: &str) -> Result<String> {
+        self.send("download", "GET", href, &[], "")?
+            .into_string()
+            .map_err(|e| Error::Response(e.to_string()))
+    }
+
+    /// Uploads a calendar object. If an ETag is given, it only replaces the object if that's
+    /// still its ETag. Otherwise, it only creates the object if there wasn't one at the href yet.
+    pub fn put(&self, href: &str, ics: &str, etag: Option<&str>) -> Result<()> {
+        let precondition = match etag {
+            Some(etag) => ("If-Match", etag),
+            None => ("If-None-Match", "*"),
+        };
+        self.send(
+            "upload",
+            "PUT",
+            href,
+            &[
+                ("Content-Type", "text/calendar; charset=utf-8"),
+                precondition,
+            ],
+            ics,
+        )?;
+        Ok(())
+    }
+
+    /// Deletes the calendar object at the href, if its ETag is still the given one.
+    pub fn delete(&self, href: &str, etag: &str) -> Result<()> {
+        self.send("delete", "DELETE", href, &[("If-Match", etag)], "")?;
+        Ok(())
+    }
+
+    fn send(
+        &self,
+        action: &'static str,
+        method: &str,
+        href: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<ureq::Response> {
+        let url = self
+            .collection
+            .join(href)
+            .map_err(|e| Error::Url(href.to_owned(), e))?;
+        let mut request = self.agent.request_url(method, &url);
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        match request.send_string(body) {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(412, _)) => Err(Error::Changed(href.to_owned())),
+            Err(ureq::Error::Status(status, _)) => {
+                Err(Error::Status(action, href.to_owned(), status))
+            }
+            Err(error) => Err(Error::Request(action, error.to_string())),
+        }
+    }
+}
+
+/// Reads the hrefs and ETags of the calendar objects out of a PROPFIND multistatus response,
+/// leaving out the collection itself.
+fn parse_etags(multistatus: &str) -> Result<HashMap<String, String>> {
+    let document =
+        roxmltree::Document::parse(multistatus).map_err(|e| Error::Response(e.to_string()))?;
+    let mut etags = HashMap::new();
+    for response in document
+        .descendants()
+        .filter(|node| node.has_tag_name((DAV, "response")))
+    {
+        let text = |name: &str| {
+            response
+                .descendants()
+                .find(|node| node.has_tag_name((DAV, name)))
+                .and_then(|node| node.text())
+                .map(str::trim)
+        };
+        let is_collection = response
+            .descendants()
+            .any(|node| node.has_tag_name((DAV, "collection")));
+        match (text("href"), text("getetag")) {
+            (Some(href), Some(etag)) if !is_collection => {
+                etags.insert(href.to_owned(), etag.to_owned());
+            }
+            (None, _) => return Err(Error::Response("a response has no href".to_owned())),
+            _ => {}
+        }
+    }
+    Ok(etags)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// The calendar objects on a mock server, with their ETags, by href.
+    pub(crate) type Objects = Arc<Mutex<HashMap<String, (String, String)>>>;
+
+    /// Starts a CalDAV server for a single collection at /calendar/ on a background thread,
+    /// implementing just enough of the protocol for `Client`. It returns the URL of the
+    /// collection and the objects in it.
+    pub(crate) fn mock_server() -> (String, Objects) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/calendar/", listener.local_addr().unwrap());
+        let objects: Objects = Arc::new(Mutex::new(HashMap::new()));
+        let server_objects = objects.clone();
+        thread::spawn(move || {
+            let mut n_etags = 0;
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap_or_default().to_owned();
+                let path = parts.next().unwrap_or_default().to_owned();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let mut header = line.splitn(2, ':');
+                    headers.insert(
+                        header.next().unwrap().trim().to_lowercase(),
+                        header.next().unwrap_or_default().trim().to_owned(),
+                    );
+                }
+                let length = headers
+                    .get("content-length")
+                    .map_or(0, |length| length.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let body = String::from_utf8(body).unwrap();
+
+                let mut objects = server_objects.lock().unwrap();
+                let current_etag = objects.get(&path).map(|(etag, _)| etag.clone());
+                let precondition_holds = match (headers.get("if-match"), &current_etag) {
+                    (Some(expected), Some(etag)) => expected == etag,
+                    (Some(_), None) => false,
+                    (None, _) => !headers.contains_key("if-none-match") || current_etag.is_none(),
+                };
+                let (status, response) = match method.as_str() {
+                    "PROPFIND" if path == "/calendar/" => {
+                        let responses = objects
+                            .iter()
+                            .map(|(href, (etag, _))| {
+                                format!(
+                                    "<d:response><d:href>{}</d:href><d:propstat><d:prop>\
+                                     <d:resourcetype/><d:getetag>{}</d:getetag></d:prop>\
+                                     <d:status>HTTP/1.1 200 OK</d:status></d:propstat>\
+                                     </d:response>",
+                                    href,
+                                    etag.replace('"', "&quot;")
+                                )
+                            })
+                            .collect::<String>();
+                        let response = format!(
+                            "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\">\
+                             <d:response><d:href>/calendar/</d:href><d:propstat><d:prop>\
+                             <d:resourcetype><d:collection/></d:resourcetype></d:prop>\
+                             <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\
+                             {}</d:multistatus>",
+                            responses
+                        );
+                        ("207 Multi-Status", response)
+                    }
+                    "GET" => match objects.get(&path) {
+                        Some((_, ics)) => ("200 OK", ics.clone()),
+                        None => ("404 Not Found", String::new()),
+                    },
+                    "PUT" if precondition_holds => {
+                        n_etags += 1;
+                        objects.insert(path, (format!("\"{}\"", n_etags), body));
+                        ("204 No Content", String::new())
+                    }
+                    "DELETE" if precondition_holds => {
+                        objects.remove(&path);
+                        ("204 No Content", String::new())
+                    }
+                    "PUT" | "DELETE" => ("412 Precondition Failed", String::new()),
+                    _ => ("405 Method Not Allowed", String::new()),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+        (url, objects)
+    }
+
+    #[test]
+    fn uploads_lists_and_deletes_objects() {
+        let (url, objects) = mock_server();
+        let client = Client::new(&url, Some("me"), Some("secret")).unwrap();
+        assert!(client.etags().unwrap().is_empty());
+
+        let href = client.href_for("eva-task-1-0");
+        assert_eq!(href, "/calendar/eva-task-1-0.ics");
+        client.put(&href, "BEGIN:VCALENDAR", None).unwrap();
+        let etags = client.etags().unwrap();
+        assert_eq!(etags.len(), 1);
+        assert_eq!(client.get(&href).unwrap(), "BEGIN:VCALENDAR");
+
+        // Objects are only created if they don't exist yet, and only replaced or deleted if they
+        // didn't change
+        assert_matches!(
+            client.put(&href, "BEGIN:VCALENDAR", None),
+            Err(Error::Changed(_))
+        );
+        assert_matches!(
+            client.put(&href, "BEGIN:VCALENDAR", Some("\"other\"")),
+            Err(Error::Changed(_))
+        );
+        client
+            .put(&href, "BEGIN:VCALENDAR\r\n", Some(&etags[&href]))
+            .unwrap();
+        let new_etags = client.etags().unwrap();
+        assert_ne!(new_etags[&href], etags[&href]);
+        assert_matches!(client.delete(&href, &etags[&href]), Err(Error::Changed(_)));
+        client.delete(&href, &new_etags[&href]).unwrap();
+        assert!(objects.lock().unwrap().is_empty());
+        assert_matches!(client.get(&href), Err(Error::Status(_, _, 404)));
+    }
+
+    #[test]
+    fn parses_multistatus_responses() {
+        let multistatus = r#"<?xml version="1.0" encoding="utf-8"?>
+            <multistatus xmlns="DAV:">
+              <response>
+                <href>/me/calendar/</href>
+                <propstat>
+                  <prop><resourcetype><collection/></resourcetype><getetag>"c"</getetag></prop>
+                  <status>HTTP/1.1 200 OK</status>
+                </propstat>
+              </response>
+              <response>
+                <href>/me/calendar/meeting.ics</href>
+                <propstat>
+                  <prop><resourcetype/><getetag>"1"</getetag></prop>
+                  <status>HTTP/1.1 200 OK</status>
+                </propstat>
+              </response>
+            </multistatus>"#;
+        let etags = parse_etags(multistatus).unwrap();
+        assert_eq!(etags.len(), 1);
+        assert_eq!(etags["/me/calendar/meeting.ics"], "\"1\"");
+        assert!(parse_etags("<multistatus").is_err());
+    }
+
+    #[cfg(all(feature = "sqlite", feature = "clock"))]
+    #[test]
+    fn synchronises_schedules_and_busy_time() {
+        use chrono::prelude::*;
+        use chrono::Duration;
+        use futures::executor::block_on;
+
+        use crate::configuration::Configuration;
+        use crate::database::sqlite::make_connection;
+        use crate::icalendar::Imported;
+
+        let configuration = Configuration {
+            database: Box::new(make_connection(":memory:").unwrap()),
+            scheduling_strategy: "importance".to_owned(),
+            strategies: Default::default(),
+            correct_estimates: false,
+        };
+        let (url, objects) = mock_server();
+        let client = Client::new(&url, None, None).unwrap();
+        let meeting = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\n\
+                       BEGIN:VEVENT\r\nUID:meeting@example.com\r\n\
+                       DTSTAMP:20261017T120000Z\r\nDTSTART:20301019T090000Z\r\n\
+                       DTEND:20301019T100000Z\r\nSUMMARY:Planning\r\nEND:VEVENT\r\n\
+                       END:VCALENDAR\r\n";
+        objects.lock().unwrap().insert(
+            "/calendar/meeting.ics".to_owned(),
+            ("\"m\"".to_owned(), meeting.to_owned()),
+        );
+        let pull = || block_on(crate::pull_caldav(&configuration, &client, &Utc)).unwrap();
+        let push = || {
+            let schedule = block_on(crate::schedule(&configuration, "importance")).unwrap();
+            block_on(crate::push_caldav(&configuration, &client, &schedule)).unwrap()
+        };
+
+        // Events become fixed events, once
+        let report = pull();
+        match &report.created[..] {
+            [Imported::Event(event)] => assert_eq!(event.title, "Planning"),
+            created => panic!("unexpected events: {:?}", created),
+        }
+        let report = pull();
+        assert!(report.created.is_empty() && report.skipped.is_empty());
+
+        let task = block_on(crate::add_task(
+            &configuration,
+            crate::NewTask {
+                content: "Write the report".to_owned(),
+                deadline: Utc::now() + Duration::days(3),
+                available_from: None,
+                duration: Duration::hours(1),
+                importance: 5,
+                time_segment_id: 0,
+                recurrence: None,
+                min_chunk_duration: None,
+                ical_uid: None,
+            },
+        ))
+        .unwrap();
+        let href = client.href_for("eva-task-1-0");
+        assert_eq!(push().created, vec![href.clone()]);
+        assert!(objects.lock().unwrap()[&href]
+            .1
+            .contains("SUMMARY:Write the report"));
+        let report = push();
+        assert_eq!(report.updated, vec![href.clone()]);
+        assert!(report.created.is_empty() && report.conflicts.is_empty());
+        // Pushed events aren't busy time
+        assert!(pull().created.is_empty());
+        assert_eq!(
+            block_on(crate::fixed_events(&configuration)).unwrap().len(),
+            1
+        );
+
+        // Events that were changed on the server are left alone
+        objects.lock().unwrap().get_mut(&href).unwrap().0 = "\"edited\"".to_owned();
+        let report = push();
+        assert_eq!(report.conflicts, vec![href.clone()]);
+        assert!(report.updated.is_empty());
+
+        // Events of tasks that aren't scheduled anymore are removed
+        objects.lock().unwrap().remove(&href);
+        push();
+        block_on(crate::delete_task(&configuration, task.id)).unwrap();
+        let report = push();
+        assert_eq!(report.deleted, vec![href.clone()]);
+        assert!(!objects.lock().unwrap().contains_key(&href));
+
+        // Events that were removed from the server are deleted
+        objects.lock().unwrap().remove("/calendar/meeting.ics");
+        assert_eq!(pull().deleted.len(), 1);
+        assert!(block_on(crate::fixed_events(&configuration))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -2,36 +2,71 @@ use cfg_if::cfg_if;
 use chrono::{DateTime, Utc};
 
 use crate::database::Database;
+use crate::scheduling::{
+    EarliestDeadlineFirstStrategy, ImportanceStrategy, OptimalStrategy, Strategy, UrgencyStrategy,
+    WeightedShortestJobFirstStrategy,
+};
 
 cfg_if! {
     if #[cfg(feature = "clock")] {
         #[derive(Debug)]
         pub struct Configuration {
             pub database: Box<dyn Database>,
-            pub scheduling_strategy: SchedulingStrategy,
+            /// The name of the strategy to schedule with when none is chosen.
+            pub scheduling_strategy: String,
+            pub strategies: Strategies,
+            /// Whether to plan for tasks taking as much longer than estimated as the finished
+            /// tasks did, according to the time tracked on them.
+            pub correct_estimates: bool,
         }
     } else {
         #[derive(Debug)]
         pub struct Configuration {
             pub database: Box<dyn Database>,
-            pub scheduling_strategy: SchedulingStrategy,
+            /// The name of the strategy to schedule with when none is chosen.
+            pub scheduling_strategy: String,
+            pub strategies: Strategies,
+            /// Whether to plan for tasks taking as much longer than estimated as the finished
+            /// tasks did, according to the time tracked on them.
+            pub correct_estimates: bool,
             pub time_context: Box<dyn TimeContext>,
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub enum SchedulingStrategy {
-    Importance,
-    Urgency,
+/// The scheduling strategies to choose from by name. By default, these are the built-in
+/// "importance", "urgency", "edf", "wspt" and "optimal" strategies, and more can be registered.
+#[derive(Debug)]
+pub struct Strategies(Vec<Box<dyn Strategy>>);
+
+impl Strategies {
+    /// Adds `strategy`, replacing the strategy with the same name if there is one.
+    pub fn register(&mut self, strategy: Box<dyn Strategy>) {
+        self.0.retain(|other| other.name() != strategy.name());
+        self.0.push(strategy);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Strategy> {
+        self.0
+            .iter()
+            .find(|strategy| strategy.name() == name)
+            .map(|strategy| strategy.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|strategy| strategy.name())
+    }
 }
 
-impl SchedulingStrategy {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Self::Importance => "importance",
-            Self::Urgency => "urgency",
-        }
+impl Default for Strategies {
+    fn default() -> Self {
+        Strategies(vec![
+            Box::new(ImportanceStrategy),
+            Box::new(UrgencyStrategy),
+            Box::new(EarliestDeadlineFirstStrategy),
+            Box::new(WeightedShortestJobFirstStrategy),
+            Box::new(OptimalStrategy::default()),
+        ])
     }
 }
 
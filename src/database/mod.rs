@@ -1,10 +1,11 @@
 use std::fmt;
 
+use chrono::prelude::*;
 use failure::Fail;
 use futures::future::LocalFutureObj;
 
 use crate::time_segment::{NamedTimeSegment as TimeSegment, NewNamedTimeSegment as NewTimeSegment};
-use crate::{NewTask, Task};
+use crate::{CalDavResource, FixedEvent, NewFixedEvent, NewTask, Placement, Task, TimeEntry};
 
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
@@ -18,12 +19,38 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub trait Database {
     fn add_task<'a: 'b, 'b>(&'a self, task: NewTask) -> LocalFutureObj<'b, Result<Task>>;
     fn delete_task<'a: 'b, 'b>(&'a self, id: u32) -> LocalFutureObj<'b, Result<()>>;
+    fn complete_task<'a: 'b, 'b>(
+        &'a self,
+        id: u32,
+        completed_at: DateTime<Utc>,
+    ) -> LocalFutureObj<'b, Result<()>>;
     fn get_task<'a: 'b, 'b>(&'a self, id: u32) -> LocalFutureObj<'b, Result<Task>>;
     fn update_task<'a: 'b, 'b>(&'a self, task: Task) -> LocalFutureObj<'b, Result<()>>;
     fn all_tasks<'a: 'b, 'b>(&'a self) -> LocalFutureObj<'b, Result<Vec<Task>>>;
     fn all_tasks_per_time_segment<'a: 'b, 'b>(
         &'a self,
     ) -> LocalFutureObj<'b, Result<Vec<(TimeSegment, Vec<Task>)>>>;
+    fn add_dependency<'a: 'b, 'b>(
+        &'a self,
+        task_id: u32,
+        prerequisite_id: u32,
+    ) -> LocalFutureObj<'b, Result<()>>;
+    fn delete_dependency<'a: 'b, 'b>(
+        &'a self,
+        task_id: u32,
+        prerequisite_id: u32,
+    ) -> LocalFutureObj<'b, Result<()>>;
+
+    fn add_fixed_event<'a: 'b, 'b>(
+        &'a self,
+        event: NewFixedEvent,
+    ) -> LocalFutureObj<'b, Result<FixedEvent>>;
+    fn delete_fixed_event<'a: 'b, 'b>(&'a self, id: u32) -> LocalFutureObj<'b, Result<()>>;
+    fn update_fixed_event<'a: 'b, 'b>(
+        &'a self,
+        event: FixedEvent,
+    ) -> LocalFutureObj<'b, Result<()>>;
+    fn all_fixed_events<'a: 'b, 'b>(&'a self) -> LocalFutureObj<'b, Result<Vec<FixedEvent>>>;
 
     fn add_time_segment<'a: 'b, 'b>(
         &'a self,
@@ -38,6 +65,44 @@ pub trait Database {
         time_segment: TimeSegment,
     ) -> LocalFutureObj<'b, Result<()>>;
     fn all_time_segments<'a: 'b, 'b>(&'a self) -> LocalFutureObj<'b, Result<Vec<TimeSegment>>>;
+
+    fn all_caldav_resources<'a: 'b, 'b>(
+        &'a self,
+    ) -> LocalFutureObj<'b, Result<Vec<CalDavResource>>>;
+    /// Stores a resource, replacing the one with the same href if there is one.
+    fn set_caldav_resource<'a: 'b, 'b>(
+        &'a self,
+        resource: CalDavResource,
+    ) -> LocalFutureObj<'b, Result<()>>;
+    fn delete_caldav_resource<'a: 'b, 'b>(&'a self, href: &str) -> LocalFutureObj<'b, Result<()>>;
+
+    /// Returns the placements of the last accepted schedule, in chronological order.
+    fn all_placements<'a: 'b, 'b>(&'a self) -> LocalFutureObj<'b, Result<Vec<Placement>>>;
+    /// Replaces the placements of the last accepted schedule.
+    fn set_placements<'a: 'b, 'b>(
+        &'a self,
+        placements: Vec<Placement>,
+    ) -> LocalFutureObj<'b, Result<()>>;
+
+    /// Starts a time entry for a task, that runs until it's stopped.
+    fn start_time_entry<'a: 'b, 'b>(
+        &'a self,
+        task_id: u32,
+        start: DateTime<Utc>,
+    ) -> LocalFutureObj<'b, Result<TimeEntry>>;
+    /// Stops all running time entries at `end` and returns them.
+    fn stop_time_entries<'a: 'b, 'b>(
+        &'a self,
+        end: DateTime<Utc>,
+    ) -> LocalFutureObj<'b, Result<Vec<TimeEntry>>>;
+    /// Returns all time entries, in the order they were started in.
+    fn all_time_entries<'a: 'b, 'b>(&'a self) -> LocalFutureObj<'b, Result<Vec<TimeEntry>>>;
+    /// Moves the time entries of one task to another one.
+    fn move_time_entries<'a: 'b, 'b>(
+        &'a self,
+        from_task_id: u32,
+        to_task_id: u32,
+    ) -> LocalFutureObj<'b, Result<()>>;
 }
 
 impl fmt::Debug for dyn Database {
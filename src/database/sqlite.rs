@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::io;
 
 use chrono::prelude::*;
@@ -12,7 +14,12 @@ use crate::time_segment::{
     NamedTimeSegment as CrateTimeSegment, NewNamedTimeSegment as CrateNewTimeSegment,
 };
 
+use self::caldav_resources::dsl::caldav_resources as caldav_resource_table;
+use self::fixed_events::dsl::fixed_events as fixed_event_table;
+use self::placements::dsl::placements as placement_table;
+use self::task_dependencies::dsl::task_dependencies as task_dependency_table;
 use self::tasks::dsl::tasks as task_table;
+use self::time_entries::dsl::time_entries as time_entry_table;
 use self::time_segment_ranges::dsl::time_segment_ranges as time_segment_range_table;
 use self::time_segments::dsl::time_segments as time_segment_table;
 
@@ -21,6 +28,7 @@ pub struct DbConnection(SqliteConnection);
 #[derive(Debug, Clone, PartialEq, Queryable, Identifiable, AsChangeset, Associations)]
 #[belongs_to(TimeSegment)]
 #[table_name = "tasks"]
+#[changeset_options(treat_none_as_null = "true")]
 struct Task {
     pub id: i32,
     pub content: String,
@@ -28,6 +36,15 @@ struct Task {
     pub duration: i32,
     pub importance: i32,
     pub time_segment_id: i32,
+    pub recurrence: Option<String>,
+    pub min_chunk_duration: Option<i32>,
+    pub completed_at: Option<i32>,
+    pub ical_uid: Option<String>,
+    pub pinned_at: Option<i32>,
+    pub pinned_exactly: bool,
+    pub available_from: Option<i32>,
+    pub blocked_reason: Option<String>,
+    pub follow_up_at: Option<i32>,
 }
 
 #[derive(Debug, Insertable)]
@@ -38,6 +55,10 @@ struct NewTask {
     pub duration: i32,
     pub importance: i32,
     pub time_segment_id: i32,
+    pub recurrence: Option<String>,
+    pub min_chunk_duration: Option<i32>,
+    pub ical_uid: Option<String>,
+    pub available_from: Option<i32>,
 }
 
 table! {
@@ -48,6 +69,64 @@ table! {
         duration -> Integer,
         importance -> Integer,
         time_segment_id -> Integer,
+        recurrence -> Nullable<Text>,
+        min_chunk_duration -> Nullable<Integer>,
+        completed_at -> Nullable<Integer>,
+        ical_uid -> Nullable<Text>,
+        pinned_at -> Nullable<Integer>,
+        pinned_exactly -> Bool,
+        available_from -> Nullable<Integer>,
+        blocked_reason -> Nullable<Text>,
+        follow_up_at -> Nullable<Integer>,
+    }
+}
+
+#[derive(Debug, Queryable, Identifiable, AsChangeset)]
+#[table_name = "fixed_events"]
+#[changeset_options(treat_none_as_null = "true")]
+struct FixedEvent {
+    pub id: i32,
+    pub title: String,
+    pub start: i32,
+    pub end: i32,
+    pub time_segment_id: Option<i32>,
+    pub ical_uid: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "fixed_events"]
+struct NewFixedEvent {
+    pub title: String,
+    pub start: i32,
+    pub end: i32,
+    pub time_segment_id: Option<i32>,
+    pub ical_uid: Option<String>,
+}
+
+#[derive(Debug, Insertable, Queryable, Identifiable, Associations)]
+#[belongs_to(Task)]
+#[table_name = "task_dependencies"]
+#[primary_key(task_id, prerequisite_id)]
+struct TaskDependency {
+    pub task_id: i32,
+    pub prerequisite_id: i32,
+}
+
+table! {
+    task_dependencies (task_id, prerequisite_id) {
+        task_id -> Integer,
+        prerequisite_id -> Integer,
+    }
+}
+
+table! {
+    fixed_events (id) {
+        id -> Integer,
+        title -> Text,
+        start -> Integer,
+        end -> Integer,
+        time_segment_id -> Nullable<Integer>,
+        ical_uid -> Nullable<Text>,
     }
 }
 
@@ -98,6 +177,66 @@ table! {
     }
 }
 
+#[derive(Debug, Insertable, Queryable)]
+#[table_name = "caldav_resources"]
+struct CalDavResource {
+    pub href: String,
+    pub etag: String,
+    pub ical_uid: String,
+    pub pushed: bool,
+}
+
+table! {
+    caldav_resources (href) {
+        href -> Text,
+        etag -> Text,
+        ical_uid -> Text,
+        pushed -> Bool,
+    }
+}
+
+#[derive(Debug, Insertable, Queryable)]
+#[table_name = "placements"]
+struct Placement {
+    pub task_id: i32,
+    pub deadline: i32,
+    pub start: i32,
+    pub end: i32,
+}
+
+table! {
+    placements (task_id, deadline, start) {
+        task_id -> Integer,
+        deadline -> Integer,
+        start -> Integer,
+        end -> Integer,
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct TimeEntry {
+    pub id: i32,
+    pub task_id: i32,
+    pub start: i32,
+    pub end: Option<i32>,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "time_entries"]
+struct NewTimeEntry {
+    pub task_id: i32,
+    pub start: i32,
+}
+
+table! {
+    time_entries (id) {
+        id -> Integer,
+        task_id -> Integer,
+        start -> Integer,
+        end -> Nullable<Integer>,
+    }
+}
+
 embed_migrations!();
 
 no_arg_sql_function!(last_insert_rowid, diesel::sql_types::Integer);
@@ -125,19 +264,49 @@ impl Database for DbConnection {
     }
 
     fn delete_task<'a: 'b, 'b>(&'a self, id: u32) -> LocalFutureObj<'b, Result<()>> {
-        let future = async move {
-            let amount_deleted = diesel::delete(task_table.find(id as i32))
+        let result = self
+            .0
+            .transaction::<_, failure::Error, _>(|| {
+                let amount_deleted = diesel::delete(task_table.find(id as i32)).execute(&self.0)?;
+                if amount_deleted != 1 {
+                    failure::bail!("{} task(s) were deleted", amount_deleted);
+                }
+                diesel::delete(
+                    task_dependency_table.filter(
+                        task_dependencies::task_id
+                            .eq(id as i32)
+                            .or(task_dependencies::prerequisite_id.eq(id as i32)),
+                    ),
+                )
+                .execute(&self.0)?;
+                diesel::delete(placement_table.filter(placements::task_id.eq(id as i32)))
+                    .execute(&self.0)?;
+                diesel::delete(time_entry_table.filter(time_entries::task_id.eq(id as i32)))
+                    .execute(&self.0)?;
+                Ok(())
+            })
+            .map_err(|e| Error("while trying to delete a task", e));
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn complete_task<'a: 'b, 'b>(
+        &'a self,
+        id: u32,
+        completed_at: DateTime<Utc>,
+    ) -> LocalFutureObj<'b, Result<()>> {
+        let result = try {
+            let amount_updated = diesel::update(task_table.find(id as i32))
+                .set(tasks::completed_at.eq(completed_at.timestamp() as i32))
                 .execute(&self.0)
-                .map_err(|e| Error("while trying to delete a task", e.into()))?;
-            if amount_deleted != 1 {
-                return Err(Error(
-                    "while trying to delete a task",
-                    failure::format_err!("{} task(s) were deleted", amount_deleted),
-                ));
+                .map_err(|e| Error("while trying to complete a task", e.into()))?;
+            if amount_updated != 1 {
+                Err(Error(
+                    "while trying to complete a task",
+                    failure::format_err!("{} task(s) were completed", amount_updated),
+                ))?
             }
-            Ok(())
         };
-        LocalFutureObj::new(Box::new(future))
+        LocalFutureObj::new(Box::new(future::ready(result)))
     }
 
     fn get_task<'a: 'b, 'b>(&'a self, id: u32) -> LocalFutureObj<'b, Result<crate::Task>> {
@@ -146,7 +315,7 @@ impl Database for DbConnection {
                 .find(id as i32)
                 .get_result::<Task>(&self.0)
                 .map_err(|e| Error("while trying to find a task", e.into()))?;
-            crate::Task::from(db_task)
+            self.construct_tasks(vec![db_task])?.remove(0)
         };
         LocalFutureObj::new(Box::new(future::ready(task_result)))
     }
@@ -174,7 +343,7 @@ impl Database for DbConnection {
             let db_tasks = task_table
                 .load::<Task>(&self.0)
                 .map_err(|e| Error("while trying to retrieve tasks", e.into()))?;
-            db_tasks.into_iter().map(crate::Task::from).collect()
+            self.construct_tasks(db_tasks)?
         };
         LocalFutureObj::new(Box::new(future::ready(tasks_result)))
     }
@@ -191,14 +360,160 @@ impl Database for DbConnection {
                 .map_err(|e| Error("while trying to retrieve tasks", e.into()))?
                 .grouped_by(&db_time_segments)
                 .into_iter()
-                .map(|db_tasks| db_tasks.into_iter().map(crate::Task::from).collect());
+                .map(|db_tasks| self.construct_tasks(db_tasks));
             self.construct_time_segments(db_time_segments)?
                 .zip(tasks)
-                .collect()
+                .map(|(time_segment, tasks)| Ok((time_segment, tasks?)))
+                .collect::<Result<_>>()?
         };
         LocalFutureObj::new(Box::new(future::ready(tasks_result)))
     }
 
+    fn add_dependency<'a: 'b, 'b>(
+        &'a self,
+        task_id: u32,
+        prerequisite_id: u32,
+    ) -> LocalFutureObj<'b, Result<()>> {
+        let result = try {
+            for id in &[task_id, prerequisite_id] {
+                task_table
+                    .find(*id as i32)
+                    .get_result::<Task>(&self.0)
+                    .map_err(|e| Error("while trying to find a task", e.into()))?;
+            }
+            if task_id == prerequisite_id {
+                Err(Error(
+                    "while trying to add a dependency",
+                    failure::format_err!("task {} can't wait for itself", task_id),
+                ))?
+            }
+            // Refuse dependencies that would make tasks wait for each other in a circle.
+            let dependencies = task_dependency_table
+                .load::<TaskDependency>(&self.0)
+                .map_err(|e| Error("while trying to retrieve dependencies", e.into()))?;
+            let mut waiting_for = vec![prerequisite_id as i32];
+            let mut seen = HashSet::new();
+            while let Some(id) = waiting_for.pop() {
+                if id == task_id as i32 {
+                    Err(Error(
+                        "while trying to add a dependency",
+                        failure::format_err!(
+                            "task {} already waits for task {}, directly or through other tasks",
+                            prerequisite_id,
+                            task_id
+                        ),
+                    ))?
+                }
+                if seen.insert(id) {
+                    waiting_for.extend(
+                        dependencies
+                            .iter()
+                            .filter(|dependency| dependency.task_id == id)
+                            .map(|dependency| dependency.prerequisite_id),
+                    );
+                }
+            }
+            diesel::insert_into(task_dependency_table)
+                .values(&TaskDependency {
+                    task_id: task_id as i32,
+                    prerequisite_id: prerequisite_id as i32,
+                })
+                .execute(&self.0)
+                .map_err(|e| Error("while trying to add a dependency", e.into()))?;
+        };
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn delete_dependency<'a: 'b, 'b>(
+        &'a self,
+        task_id: u32,
+        prerequisite_id: u32,
+    ) -> LocalFutureObj<'b, Result<()>> {
+        let result = try {
+            let amount_deleted = diesel::delete(
+                task_dependency_table.find((task_id as i32, prerequisite_id as i32)),
+            )
+            .execute(&self.0)
+            .map_err(|e| Error("while trying to delete a dependency", e.into()))?;
+            if amount_deleted != 1 {
+                Err(Error(
+                    "while trying to delete a dependency",
+                    failure::format_err!("{} dependencies were deleted", amount_deleted),
+                ))?
+            }
+        };
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn add_fixed_event<'a: 'b, 'b>(
+        &'a self,
+        event: crate::NewFixedEvent,
+    ) -> LocalFutureObj<'b, Result<crate::FixedEvent>> {
+        let event_result = try {
+            diesel::insert_into(fixed_event_table)
+                .values(&NewFixedEvent::from(event))
+                .execute(&self.0)
+                .map_err(|e| Error("while trying to add a fixed event", e.into()))?;
+            let id = diesel::select(last_insert_rowid)
+                .get_result::<i32>(&self.0)
+                .map_err(|e| Error("while trying to fetch the id of the new event", e.into()))?;
+            let db_event = fixed_event_table
+                .find(id)
+                .get_result::<FixedEvent>(&self.0)
+                .map_err(|e| Error("while trying to fetch the newly created event", e.into()))?;
+            crate::FixedEvent::from(db_event)
+        };
+        LocalFutureObj::new(Box::new(future::ready(event_result)))
+    }
+
+    fn delete_fixed_event<'a: 'b, 'b>(&'a self, id: u32) -> LocalFutureObj<'b, Result<()>> {
+        let result = try {
+            let amount_deleted = diesel::delete(fixed_event_table.find(id as i32))
+                .execute(&self.0)
+                .map_err(|e| Error("while trying to delete a fixed event", e.into()))?;
+            if amount_deleted != 1 {
+                Err(Error(
+                    "while trying to delete a fixed event",
+                    failure::format_err!("{} event(s) were deleted", amount_deleted),
+                ))?
+            }
+        };
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn update_fixed_event<'a: 'b, 'b>(
+        &'a self,
+        event: crate::FixedEvent,
+    ) -> LocalFutureObj<'b, Result<()>> {
+        let db_event = FixedEvent::from(event);
+        let result = try {
+            let amount_updated = diesel::update(&db_event)
+                .set(&db_event)
+                .execute(&self.0)
+                .map_err(|e| Error("while trying to update a fixed event", e.into()))?;
+            if amount_updated != 1 {
+                Err(Error(
+                    "while trying to update a fixed event",
+                    failure::format_err!("{} event(s) were updated", amount_updated),
+                ))?
+            }
+        };
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn all_fixed_events<'a: 'b, 'b>(
+        &'a self,
+    ) -> LocalFutureObj<'b, Result<Vec<crate::FixedEvent>>> {
+        let events_result = try {
+            let db_events = fixed_event_table
+                .order(fixed_events::start)
+                .load::<FixedEvent>(&self.0)
+                .map_err(|e| Error("while trying to retrieve fixed events", e.into()))?;
+            db_events.into_iter().map(crate::FixedEvent::from).collect()
+        };
+        LocalFutureObj::new(Box::new(future::ready(events_result)))
+    }
+
     fn add_time_segment<'a: 'b, 'b>(
         &'a self,
         time_segment: CrateNewTimeSegment,
@@ -232,8 +547,9 @@ impl Database for DbConnection {
         let db_time_segment = TimeSegment::from(time_segment);
         let ranges = TimeSegmentRange::belonging_to(&db_time_segment);
         let result = try {
-            // Assert that there are no tasks in this time segment
+            // Assert that there are no unfinished tasks in this time segment
             let n_tasks = Task::belonging_to(&db_time_segment)
+                .filter(tasks::completed_at.is_null())
                 .count()
                 .get_result::<i64>(&self.0)
                 .map_err(|e| Error("while trying to delete a time segment", e.into()))?;
@@ -323,9 +639,192 @@ impl Database for DbConnection {
         };
         LocalFutureObj::new(Box::new(future::ready(time_segments_result)))
     }
+
+    fn all_caldav_resources<'a: 'b, 'b>(
+        &'a self,
+    ) -> LocalFutureObj<'b, Result<Vec<crate::CalDavResource>>> {
+        let resources_result = try {
+            let db_resources = caldav_resource_table
+                .order(caldav_resources::href)
+                .load::<CalDavResource>(&self.0)
+                .map_err(|e| Error("while trying to retrieve CalDAV resources", e.into()))?;
+            db_resources
+                .into_iter()
+                .map(crate::CalDavResource::from)
+                .collect()
+        };
+        LocalFutureObj::new(Box::new(future::ready(resources_result)))
+    }
+
+    fn set_caldav_resource<'a: 'b, 'b>(
+        &'a self,
+        resource: crate::CalDavResource,
+    ) -> LocalFutureObj<'b, Result<()>> {
+        let result = try {
+            diesel::replace_into(caldav_resource_table)
+                .values(&CalDavResource::from(resource))
+                .execute(&self.0)
+                .map_err(|e| Error("while trying to store a CalDAV resource", e.into()))?;
+        };
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn delete_caldav_resource<'a: 'b, 'b>(&'a self, href: &str) -> LocalFutureObj<'b, Result<()>> {
+        let result = try {
+            let amount_deleted = diesel::delete(caldav_resource_table.find(href))
+                .execute(&self.0)
+                .map_err(|e| Error("while trying to delete a CalDAV resource", e.into()))?;
+            if amount_deleted != 1 {
+                Err(Error(
+                    "while trying to delete a CalDAV resource",
+                    failure::format_err!("{} resource(s) were deleted", amount_deleted),
+                ))?
+            }
+        };
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn all_placements<'a: 'b, 'b>(&'a self) -> LocalFutureObj<'b, Result<Vec<crate::Placement>>> {
+        let placements_result = try {
+            let db_placements = placement_table
+                .order(placements::start)
+                .load::<Placement>(&self.0)
+                .map_err(|e| Error("while trying to retrieve placements", e.into()))?;
+            db_placements
+                .into_iter()
+                .map(crate::Placement::from)
+                .collect()
+        };
+        LocalFutureObj::new(Box::new(future::ready(placements_result)))
+    }
+
+    fn set_placements<'a: 'b, 'b>(
+        &'a self,
+        placements: Vec<crate::Placement>,
+    ) -> LocalFutureObj<'b, Result<()>> {
+        let result = self
+            .0
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::delete(placement_table).execute(&self.0)?;
+                for placement in placements {
+                    diesel::insert_into(placement_table)
+                        .values(&Placement::from(placement))
+                        .execute(&self.0)?;
+                }
+                Ok(())
+            })
+            .map_err(|e| Error("while trying to store placements", e.into()));
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
+
+    fn start_time_entry<'a: 'b, 'b>(
+        &'a self,
+        task_id: u32,
+        start: DateTime<Utc>,
+    ) -> LocalFutureObj<'b, Result<crate::TimeEntry>> {
+        let entry_result = try {
+            diesel::insert_into(time_entry_table)
+                .values(&NewTimeEntry {
+                    task_id: task_id as i32,
+                    start: start.timestamp() as i32,
+                })
+                .execute(&self.0)
+                .map_err(|e| Error("while trying to start a time entry", e.into()))?;
+            let id = diesel::select(last_insert_rowid)
+                .get_result::<i32>(&self.0)
+                .map_err(|e| {
+                    Error(
+                        "while trying to fetch the id of the new time entry",
+                        e.into(),
+                    )
+                })?;
+            let db_entry = time_entry_table
+                .find(id)
+                .get_result::<TimeEntry>(&self.0)
+                .map_err(|e| {
+                    Error(
+                        "while trying to fetch the newly started time entry",
+                        e.into(),
+                    )
+                })?;
+            crate::TimeEntry::from(db_entry)
+        };
+        LocalFutureObj::new(Box::new(future::ready(entry_result)))
+    }
+
+    fn stop_time_entries<'a: 'b, 'b>(
+        &'a self,
+        end: DateTime<Utc>,
+    ) -> LocalFutureObj<'b, Result<Vec<crate::TimeEntry>>> {
+        let entries_result = self
+            .0
+            .transaction::<_, diesel::result::Error, _>(|| {
+                let running = time_entry_table
+                    .filter(time_entries::end.is_null())
+                    .order(time_entries::start)
+                    .load::<TimeEntry>(&self.0)?;
+                diesel::update(time_entry_table.filter(time_entries::end.is_null()))
+                    .set(time_entries::end.eq(end.timestamp() as i32))
+                    .execute(&self.0)?;
+                Ok(running
+                    .into_iter()
+                    .map(|entry| crate::TimeEntry {
+                        end: Some(end),
+                        ..crate::TimeEntry::from(entry)
+                    })
+                    .collect())
+            })
+            .map_err(|e| Error("while trying to stop time entries", e.into()));
+        LocalFutureObj::new(Box::new(future::ready(entries_result)))
+    }
+
+    fn all_time_entries<'a: 'b, 'b>(&'a self) -> LocalFutureObj<'b, Result<Vec<crate::TimeEntry>>> {
+        let entries_result = try {
+            let db_entries = time_entry_table
+                .order((time_entries::start, time_entries::id))
+                .load::<TimeEntry>(&self.0)
+                .map_err(|e| Error("while trying to retrieve time entries", e.into()))?;
+            db_entries.into_iter().map(crate::TimeEntry::from).collect()
+        };
+        LocalFutureObj::new(Box::new(future::ready(entries_result)))
+    }
+
+    fn move_time_entries<'a: 'b, 'b>(
+        &'a self,
+        from_task_id: u32,
+        to_task_id: u32,
+    ) -> LocalFutureObj<'b, Result<()>> {
+        let result =
+            diesel::update(time_entry_table.filter(time_entries::task_id.eq(from_task_id as i32)))
+                .set(time_entries::task_id.eq(to_task_id as i32))
+                .execute(&self.0)
+                .map(|_| ())
+                .map_err(|e| Error("while trying to move time entries", e.into()));
+        LocalFutureObj::new(Box::new(future::ready(result)))
+    }
 }
 
 impl DbConnection {
+    fn construct_tasks(&self, db_tasks: Vec<Task>) -> Result<Vec<crate::Task>> {
+        let dependencies = TaskDependency::belonging_to(&db_tasks)
+            .order(task_dependencies::prerequisite_id)
+            .load::<TaskDependency>(&self.0)
+            .map_err(|e| Error("while trying to retrieve dependencies", e.into()))?
+            .grouped_by(&db_tasks);
+        db_tasks
+            .into_iter()
+            .zip(dependencies)
+            .map(|(task, dependencies)| {
+                let mut task = crate::Task::try_from(task)?;
+                task.depends_on = dependencies
+                    .into_iter()
+                    .map(|dependency| dependency.prerequisite_id as u32)
+                    .collect();
+                Ok(task)
+            })
+            .collect()
+    }
+
     fn construct_time_segments(
         &self,
         db_time_segments: Vec<TimeSegment>,
@@ -362,25 +861,68 @@ impl From<crate::NewTask> for NewTask {
             duration: task.duration.num_seconds() as i32,
             importance: task.importance as i32,
             time_segment_id: task.time_segment_id as i32,
+            recurrence: task.recurrence.map(|recurrence| recurrence.to_string()),
+            min_chunk_duration: task
+                .min_chunk_duration
+                .map(|duration| duration.num_seconds() as i32),
+            ical_uid: task.ical_uid,
+            available_from: task
+                .available_from
+                .map(|available_from| available_from.timestamp() as i32),
         }
     }
 }
 
-impl From<Task> for crate::Task {
-    fn from(task: Task) -> crate::Task {
-        crate::Task {
+impl TryFrom<Task> for crate::Task {
+    type Error = Error;
+
+    fn try_from(task: Task) -> Result<crate::Task> {
+        let recurrence = task
+            .recurrence
+            .map(|recurrence| recurrence.parse())
+            .transpose()
+            .map_err(|e: crate::recurrence::ParseError| {
+                Error("while trying to read the recurrence of a task", e.into())
+            })?;
+        let pinned_exactly = task.pinned_exactly;
+        let follow_up_at = task.follow_up_at;
+        Ok(crate::Task {
             id: task.id as u32,
             content: task.content,
             deadline: i32_to_datetime(task.deadline),
+            available_from: task.available_from.map(i32_to_datetime),
             duration: i32_to_duration(task.duration),
             importance: task.importance as u32,
             time_segment_id: task.time_segment_id as u32,
-        }
+            recurrence,
+            min_chunk_duration: task.min_chunk_duration.map(i32_to_duration),
+            depends_on: vec![],
+            completed_at: task.completed_at.map(i32_to_datetime),
+            ical_uid: task.ical_uid,
+            pin: task.pinned_at.map(|pinned_at| {
+                if pinned_exactly {
+                    crate::Pin::At(i32_to_datetime(pinned_at))
+                } else {
+                    crate::Pin::NotBefore(i32_to_datetime(pinned_at))
+                }
+            }),
+            blocked: task.blocked_reason.map(|reason| crate::Blocked {
+                reason,
+                follow_up: follow_up_at.map(i32_to_datetime),
+            }),
+        })
     }
 }
 
 impl From<crate::Task> for Task {
     fn from(task: crate::Task) -> Task {
+        let (blocked_reason, follow_up_at) = match task.blocked {
+            Some(crate::Blocked { reason, follow_up }) => (
+                Some(reason),
+                follow_up.map(|follow_up| follow_up.timestamp() as i32),
+            ),
+            None => (None, None),
+        };
         Task {
             id: task.id as i32,
             content: task.content,
@@ -388,6 +930,61 @@ impl From<crate::Task> for Task {
             duration: task.duration.num_seconds() as i32,
             importance: task.importance as i32,
             time_segment_id: task.time_segment_id as i32,
+            recurrence: task.recurrence.map(|recurrence| recurrence.to_string()),
+            min_chunk_duration: task
+                .min_chunk_duration
+                .map(|duration| duration.num_seconds() as i32),
+            completed_at: task
+                .completed_at
+                .map(|completed_at| completed_at.timestamp() as i32),
+            ical_uid: task.ical_uid,
+            pinned_at: task.pin.map(|pin| match pin {
+                crate::Pin::At(at) | crate::Pin::NotBefore(at) => at.timestamp() as i32,
+            }),
+            pinned_exactly: matches!(task.pin, Some(crate::Pin::At(_))),
+            available_from: task
+                .available_from
+                .map(|available_from| available_from.timestamp() as i32),
+            blocked_reason,
+            follow_up_at,
+        }
+    }
+}
+
+impl From<crate::NewFixedEvent> for NewFixedEvent {
+    fn from(event: crate::NewFixedEvent) -> NewFixedEvent {
+        NewFixedEvent {
+            title: event.title,
+            start: event.start.timestamp() as i32,
+            end: event.end.timestamp() as i32,
+            time_segment_id: event.time_segment_id.map(|id| id as i32),
+            ical_uid: event.ical_uid,
+        }
+    }
+}
+
+impl From<FixedEvent> for crate::FixedEvent {
+    fn from(event: FixedEvent) -> crate::FixedEvent {
+        crate::FixedEvent {
+            id: event.id as u32,
+            title: event.title,
+            start: i32_to_datetime(event.start),
+            end: i32_to_datetime(event.end),
+            time_segment_id: event.time_segment_id.map(|id| id as u32),
+            ical_uid: event.ical_uid,
+        }
+    }
+}
+
+impl From<crate::FixedEvent> for FixedEvent {
+    fn from(event: crate::FixedEvent) -> FixedEvent {
+        FixedEvent {
+            id: event.id as i32,
+            title: event.title,
+            start: event.start.timestamp() as i32,
+            end: event.end.timestamp() as i32,
+            time_segment_id: event.time_segment_id.map(|id| id as i32),
+            ical_uid: event.ical_uid,
         }
     }
 }
@@ -415,6 +1012,61 @@ impl From<CrateTimeSegment> for TimeSegment {
     }
 }
 
+impl From<CalDavResource> for crate::CalDavResource {
+    fn from(resource: CalDavResource) -> crate::CalDavResource {
+        crate::CalDavResource {
+            href: resource.href,
+            etag: resource.etag,
+            ical_uid: resource.ical_uid,
+            pushed: resource.pushed,
+        }
+    }
+}
+
+impl From<crate::CalDavResource> for CalDavResource {
+    fn from(resource: crate::CalDavResource) -> CalDavResource {
+        CalDavResource {
+            href: resource.href,
+            etag: resource.etag,
+            ical_uid: resource.ical_uid,
+            pushed: resource.pushed,
+        }
+    }
+}
+
+impl From<Placement> for crate::Placement {
+    fn from(placement: Placement) -> crate::Placement {
+        crate::Placement {
+            task_id: placement.task_id as u32,
+            deadline: i32_to_datetime(placement.deadline),
+            start: i32_to_datetime(placement.start),
+            end: i32_to_datetime(placement.end),
+        }
+    }
+}
+
+impl From<TimeEntry> for crate::TimeEntry {
+    fn from(entry: TimeEntry) -> crate::TimeEntry {
+        crate::TimeEntry {
+            id: entry.id as u32,
+            task_id: entry.task_id as u32,
+            start: i32_to_datetime(entry.start),
+            end: entry.end.map(i32_to_datetime),
+        }
+    }
+}
+
+impl From<crate::Placement> for Placement {
+    fn from(placement: crate::Placement) -> Placement {
+        Placement {
+            task_id: placement.task_id as i32,
+            deadline: placement.deadline.timestamp() as i32,
+            start: placement.start.timestamp() as i32,
+            end: placement.end.timestamp() as i32,
+        }
+    }
+}
+
 pub fn make_connection(database_url: &str) -> Result<DbConnection> {
     let connection = SqliteConnection::establish(database_url)
         .map_err(|e| Error("while trying to connect to the database", e.into()))?;
@@ -435,6 +1087,8 @@ fn i32_to_datetime(timestamp: i32) -> DateTime<Utc> {
 
 #[cfg(test)]
 mod tests {
+    use std::slice;
+
     use futures::executor::block_on;
 
     use super::*;
@@ -476,12 +1130,104 @@ mod tests {
         task.deadline = deadline;
         task.duration = Duration::minutes(7);
         task.importance = 100;
+        task.recurrence = Some("FREQ=WEEKLY;BYDAY=MO,TH;COUNT=3".parse().unwrap());
+        task.min_chunk_duration = Some(Duration::minutes(30));
+        task.available_from = Some(deadline - Duration::days(1));
+        task.pin = Some(crate::Pin::At(deadline - Duration::hours(1)));
+        block_on(connection.update_task(task.clone())).unwrap();
+
+        let task_from_db = block_on(connection.get_task(task.id)).unwrap();
+        assert_eq!(task, task_from_db);
+
+        task.pin = Some(crate::Pin::NotBefore(deadline - Duration::hours(2)));
+        task.blocked = Some(crate::Blocked {
+            reason: "the plumber".to_string(),
+            follow_up: Some(deadline - Duration::days(2)),
+        });
+        block_on(connection.update_task(task.clone())).unwrap();
+        let task_from_db = block_on(connection.get_task(task.id)).unwrap();
+        assert_eq!(task, task_from_db);
+
+        task.blocked = Some(crate::Blocked {
+            reason: "the plumber".to_string(),
+            follow_up: None,
+        });
         block_on(connection.update_task(task.clone())).unwrap();
+        let task_from_db = block_on(connection.get_task(task.id)).unwrap();
+        assert_eq!(task, task_from_db);
 
+        // The recurrence, splitting, earliest start, pin and block can be removed again
+        task.recurrence = None;
+        task.min_chunk_duration = None;
+        task.available_from = None;
+        task.pin = None;
+        task.blocked = None;
+        block_on(connection.update_task(task.clone())).unwrap();
         let task_from_db = block_on(connection.get_task(task.id)).unwrap();
         assert_eq!(task, task_from_db);
     }
 
+    #[test]
+    fn test_add_query_and_delete_dependencies() {
+        let connection = make_connection(":memory:").unwrap();
+
+        let first = block_on(connection.add_task(test_task())).unwrap();
+        let second = block_on(connection.add_task(test_task())).unwrap();
+        let third = block_on(connection.add_task(test_task())).unwrap();
+        assert!(first.depends_on.is_empty());
+
+        block_on(connection.add_dependency(third.id, second.id)).unwrap();
+        block_on(connection.add_dependency(third.id, first.id)).unwrap();
+        block_on(connection.add_dependency(second.id, first.id)).unwrap();
+        let third = block_on(connection.get_task(third.id)).unwrap();
+        assert_eq!(third.depends_on, [first.id, second.id]);
+        let tasks = block_on(connection.all_tasks()).unwrap();
+        assert_eq!(tasks[1].depends_on, [first.id]);
+        let tasks_per_segment = block_on(connection.all_tasks_per_time_segment()).unwrap();
+        assert_eq!(tasks_per_segment[0].1[2], third);
+
+        // Dependencies on tasks that don't exist are refused
+        assert!(block_on(connection.add_dependency(third.id, 42)).is_err());
+        // So are dependencies that would make tasks wait for each other in a circle
+        assert!(block_on(connection.add_dependency(first.id, first.id)).is_err());
+        assert!(block_on(connection.add_dependency(first.id, third.id)).is_err());
+        assert!(block_on(connection.add_dependency(second.id, third.id)).is_err());
+        let first = block_on(connection.get_task(first.id)).unwrap();
+        assert!(first.depends_on.is_empty());
+
+        block_on(connection.delete_dependency(third.id, second.id)).unwrap();
+        assert!(block_on(connection.delete_dependency(third.id, second.id)).is_err());
+        let third = block_on(connection.get_task(third.id)).unwrap();
+        assert_eq!(third.depends_on, [first.id]);
+
+        // Deleting a task removes it from the dependencies of others
+        block_on(connection.delete_task(first.id)).unwrap();
+        let tasks = block_on(connection.all_tasks()).unwrap();
+        assert!(tasks.iter().all(|task| task.depends_on.is_empty()));
+    }
+
+    #[test]
+    fn test_complete_task() {
+        let connection = make_connection(":memory:").unwrap();
+
+        let task = block_on(connection.add_task(test_task())).unwrap();
+        assert_eq!(task.completed_at, None);
+
+        let completed_at = Utc::now().with_nanosecond(0).unwrap();
+        block_on(connection.complete_task(task.id, completed_at)).unwrap();
+        let task = block_on(connection.get_task(task.id)).unwrap();
+        assert_eq!(task.completed_at, Some(completed_at));
+        assert!(block_on(connection.complete_task(task.id + 1, completed_at)).is_err());
+
+        // Finished tasks don't keep a time segment from being deleted
+        let time_segment = block_on(connection.all_time_segments())
+            .unwrap()
+            .pop()
+            .unwrap();
+        block_on(connection.add_time_segment(test_time_segment())).unwrap();
+        block_on(connection.delete_time_segment(time_segment)).unwrap();
+    }
+
     #[test]
     fn test_default_time_segment() {
         let connection = make_connection(":memory:").unwrap();
@@ -589,13 +1335,186 @@ mod tests {
         assert_eq!(time_segment_from_db, time_segment);
     }
 
+    #[test]
+    fn test_insert_update_query_and_delete_fixed_event() {
+        let connection = make_connection(":memory:").unwrap();
+
+        // Fresh database has no events
+        assert!(block_on(connection.all_fixed_events()).unwrap().is_empty());
+
+        // Inserting an event and querying for it, returns the same one
+        let new_event = test_fixed_event();
+        let event = block_on(connection.add_fixed_event(new_event.clone())).unwrap();
+        assert_eq!(event, new_event);
+        assert_eq!(
+            block_on(connection.all_fixed_events()).unwrap(),
+            slice::from_ref(&event)
+        );
+
+        // Updates are persisted, including clearing the time segment
+        let mut event = event;
+        event.title = "stand-up".to_string();
+        event.end += Duration::minutes(15);
+        event.time_segment_id = None;
+        block_on(connection.update_fixed_event(event.clone())).unwrap();
+        assert_eq!(
+            block_on(connection.all_fixed_events()).unwrap(),
+            slice::from_ref(&event)
+        );
+
+        // Deleting an event leaves the database empty
+        block_on(connection.delete_fixed_event(event.id)).unwrap();
+        assert!(block_on(connection.all_fixed_events()).unwrap().is_empty());
+        assert!(block_on(connection.delete_fixed_event(event.id)).is_err());
+    }
+
+    #[test]
+    fn test_set_query_and_delete_caldav_resources() {
+        let connection = make_connection(":memory:").unwrap();
+        assert!(block_on(connection.all_caldav_resources())
+            .unwrap()
+            .is_empty());
+
+        let meeting = crate::CalDavResource {
+            href: "/calendars/me/meeting.ics".to_string(),
+            etag: "\"1\"".to_string(),
+            ical_uid: "meeting@example.com".to_string(),
+            pushed: false,
+        };
+        let task = crate::CalDavResource {
+            href: "/calendars/me/eva-task-1-0.ics".to_string(),
+            etag: "\"2\"".to_string(),
+            ical_uid: "eva-task-1-0".to_string(),
+            pushed: true,
+        };
+        block_on(connection.set_caldav_resource(meeting.clone())).unwrap();
+        block_on(connection.set_caldav_resource(task.clone())).unwrap();
+        assert_eq!(
+            block_on(connection.all_caldav_resources()).unwrap(),
+            [task.clone(), meeting.clone()]
+        );
+
+        // Setting a resource with the same href replaces it
+        let meeting = crate::CalDavResource {
+            etag: "\"3\"".to_string(),
+            ..meeting
+        };
+        block_on(connection.set_caldav_resource(meeting.clone())).unwrap();
+        assert_eq!(
+            block_on(connection.all_caldav_resources()).unwrap(),
+            [task.clone(), meeting.clone()]
+        );
+
+        block_on(connection.delete_caldav_resource(&task.href)).unwrap();
+        assert_eq!(
+            block_on(connection.all_caldav_resources()).unwrap(),
+            [meeting]
+        );
+        assert!(block_on(connection.delete_caldav_resource(&task.href)).is_err());
+    }
+
+    #[test]
+    fn test_set_and_query_placements() {
+        let connection = make_connection(":memory:").unwrap();
+        assert!(block_on(connection.all_placements()).unwrap().is_empty());
+
+        let task = block_on(connection.add_task(test_task())).unwrap();
+        let start = Utc::now().with_nanosecond(0).unwrap();
+        let later = crate::Placement {
+            task_id: task.id,
+            deadline: task.deadline,
+            start: start + Duration::hours(2),
+            end: start + Duration::hours(3),
+        };
+        let sooner = crate::Placement {
+            start,
+            end: start + Duration::hours(1),
+            ..later.clone()
+        };
+        block_on(connection.set_placements(vec![later.clone(), sooner.clone()])).unwrap();
+        assert_eq!(
+            block_on(connection.all_placements()).unwrap(),
+            [sooner.clone(), later]
+        );
+
+        // Setting placements replaces all of the previous ones
+        block_on(connection.set_placements(vec![sooner.clone()])).unwrap();
+        assert_eq!(block_on(connection.all_placements()).unwrap(), [sooner]);
+
+        // Deleting a task deletes its placements
+        block_on(connection.delete_task(task.id)).unwrap();
+        assert!(block_on(connection.all_placements()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_start_stop_and_query_time_entries() {
+        let connection = make_connection(":memory:").unwrap();
+        let first = block_on(connection.add_task(test_task())).unwrap();
+        let second = block_on(connection.add_task(test_task())).unwrap();
+        let start = Utc::now().with_nanosecond(0).unwrap();
+        assert!(block_on(connection.stop_time_entries(start))
+            .unwrap()
+            .is_empty());
+
+        let entry = block_on(connection.start_time_entry(first.id, start)).unwrap();
+        assert_eq!(
+            (entry.task_id, entry.start, entry.end),
+            (first.id, start, None)
+        );
+        let end = start + Duration::minutes(25);
+        let stopped = block_on(connection.stop_time_entries(end)).unwrap();
+        assert_eq!(
+            stopped,
+            [crate::TimeEntry {
+                end: Some(end),
+                ..entry
+            }]
+        );
+        // Stopped entries stay stopped
+        assert!(
+            block_on(connection.stop_time_entries(end + Duration::hours(1)))
+                .unwrap()
+                .is_empty()
+        );
+
+        let running = block_on(connection.start_time_entry(second.id, end)).unwrap();
+        assert_eq!(
+            block_on(connection.all_time_entries()).unwrap(),
+            [stopped[0].clone(), running.clone()]
+        );
+
+        // Time entries can be moved to another task
+        block_on(connection.move_time_entries(second.id, first.id)).unwrap();
+        let entries = block_on(connection.all_time_entries()).unwrap();
+        assert!(entries.iter().all(|entry| entry.task_id == first.id));
+
+        // Deleting a task deletes its time entries
+        block_on(connection.delete_task(first.id)).unwrap();
+        assert!(block_on(connection.all_time_entries()).unwrap().is_empty());
+    }
+
     fn test_task() -> crate::NewTask {
         crate::NewTask {
             content: "do me".to_string(),
             deadline: Utc::now().with_nanosecond(0).unwrap(),
+            available_from: None,
             duration: Duration::seconds(6),
             importance: 42,
             time_segment_id: 0,
+            recurrence: None,
+            min_chunk_duration: None,
+            ical_uid: None,
+        }
+    }
+
+    fn test_fixed_event() -> crate::NewFixedEvent {
+        let start = Utc::now().with_nanosecond(0).unwrap();
+        crate::NewFixedEvent {
+            title: "meeting".to_string(),
+            start,
+            end: start + Duration::hours(1),
+            time_segment_id: Some(0),
+            ical_uid: Some("meeting@example.com".to_string()),
         }
     }
 
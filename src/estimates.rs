@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::prelude::*;
+use chrono::Duration;
+
+use crate::{Task, TimeEntry};
+
+/// How many finished tasks with tracked time it takes before a correction factor is trusted enough
+/// to plan with.
+pub const MIN_TASKS: usize = 3;
+
+/// How the time that some finished tasks took compares to how long you estimated them to take.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Accuracy {
+    /// How many finished tasks with tracked time this is about.
+    pub tasks: usize,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::duration")
+    )]
+    pub estimated: Duration,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::duration")
+    )]
+    pub actual: Duration,
+    /// How many times as long as estimated the tasks took. Above 1, you underestimate them, and
+    /// below 1, you overestimate them.
+    pub factor: f64,
+}
+
+impl Accuracy {
+    fn of<'a>(samples: impl IntoIterator<Item = &'a Sample>) -> Accuracy {
+        let mut tasks = 0;
+        let mut estimated = Duration::zero();
+        let mut actual = Duration::zero();
+        for sample in samples {
+            tasks += 1;
+            estimated += sample.estimated;
+            actual += sample.actual;
+        }
+        let factor = if tasks == 0 {
+            1.0
+        } else {
+            actual.num_seconds() as f64 / estimated.num_seconds() as f64
+        };
+        Accuracy {
+            tasks,
+            estimated,
+            actual,
+            factor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SegmentAccuracy {
+    pub time_segment_id: u32,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub accuracy: Accuracy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MonthAccuracy {
+    /// The first day of the month in which the tasks were finished.
+    pub month: NaiveDate,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub accuracy: Accuracy,
+}
+
+/// How accurate your estimates of the tasks you finished were, overall, per time segment and per
+/// month.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EstimateStats {
+    pub overall: Accuracy,
+    /// The time segments with finished tasks, in the order of their ids.
+    pub per_segment: Vec<SegmentAccuracy>,
+    /// The months in which tasks were finished, oldest first, to show how your estimates change
+    /// over time.
+    pub per_month: Vec<MonthAccuracy>,
+}
+
+/// A finished task with the time that was tracked on it.
+struct Sample {
+    time_segment_id: u32,
+    month: NaiveDate,
+    estimated: Duration,
+    actual: Duration,
+}
+
+impl EstimateStats {
+    /// Compares the estimates of the finished tasks among `tasks` to the time tracked on them in
+    /// `entries`. Tasks that weren't tracked and entries that are still running are left out.
+    pub(crate) fn new<'a>(
+        tasks: impl IntoIterator<Item = &'a Task>,
+        entries: &[TimeEntry],
+    ) -> EstimateStats {
+        let mut tracked = HashMap::new();
+        for entry in entries {
+            if let Some(end) = entry.end {
+                *tracked.entry(entry.task_id).or_insert_with(Duration::zero) += end - entry.start;
+            }
+        }
+        let samples: Vec<Sample> = tasks
+            .into_iter()
+            .filter(|task| task.duration > Duration::zero())
+            .filter_map(|task| {
+                let completed_at = task.completed_at?;
+                let actual = *tracked.get(&task.id)?;
+                Some(Sample {
+                    time_segment_id: task.time_segment_id,
+                    month: NaiveDate::from_ymd_opt(completed_at.year(), completed_at.month(), 1)?,
+                    estimated: task.duration,
+                    actual,
+                })
+            })
+            .filter(|sample| sample.actual > Duration::zero())
+            .collect();
+
+        let mut per_segment = BTreeMap::new();
+        let mut per_month = BTreeMap::new();
+        for sample in &samples {
+            per_segment
+                .entry(sample.time_segment_id)
+                .or_insert_with(Vec::new)
+                .push(sample);
+            per_month
+                .entry(sample.month)
+                .or_insert_with(Vec::new)
+                .push(sample);
+        }
+        EstimateStats {
+            overall: Accuracy::of(&samples),
+            per_segment: per_segment
+                .into_iter()
+                .map(|(time_segment_id, samples)| SegmentAccuracy {
+                    time_segment_id,
+                    accuracy: Accuracy::of(samples),
+                })
+                .collect(),
+            per_month: per_month
+                .into_iter()
+                .map(|(month, samples)| MonthAccuracy {
+                    month,
+                    accuracy: Accuracy::of(samples),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the factor to multiply the estimates of the tasks in the time segment with id
+    /// `time_segment_id` by, to plan for how long they really take. That's the factor of the time
+    /// segment once it has at least `MIN_TASKS` finished tasks, and the overall factor before
+    /// that. It's None as long as there are too few finished tasks overall.
+    pub fn factor(&self, time_segment_id: u32) -> Option<f64> {
+        self.per_segment
+            .iter()
+            .find(|segment| segment.time_segment_id == time_segment_id)
+            .map(|segment| segment.accuracy)
+            .filter(|accuracy| accuracy.tasks >= MIN_TASKS)
+            .or_else(|| Some(self.overall).filter(|overall| overall.tasks >= MIN_TASKS))
+            .map(|accuracy| accuracy.factor)
+    }
+}
+
+/// Returns how much time to add to `estimate` to plan for a task taking `factor` times as long.
+/// Estimates are only ever made longer, never shorter.
+pub(crate) fn extra_time(estimate: Duration, factor: f64) -> Duration {
+    if factor <= 1.0 {
+        return Duration::zero();
+    }
+    Duration::seconds((estimate.num_seconds() as f64 * (factor - 1.0)).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(s: &str) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap())
+    }
+
+    fn finished_task(id: u32, segment: u32, hours: i64, completed_at: &str) -> Task {
+        Task {
+            id,
+            content: format!("task {}", id),
+            deadline: datetime("2026-12-01 17:00"),
+            available_from: None,
+            duration: Duration::hours(hours),
+            importance: 5,
+            time_segment_id: segment,
+            recurrence: None,
+            min_chunk_duration: None,
+            depends_on: vec![],
+            completed_at: Some(datetime(completed_at)),
+            ical_uid: None,
+            pin: None,
+            blocked: None,
+        }
+    }
+
+    fn entry(id: u32, task_id: u32, start: &str, hours: i64) -> TimeEntry {
+        TimeEntry {
+            id,
+            task_id,
+            start: datetime(start),
+            end: Some(datetime(start) + Duration::hours(hours)),
+        }
+    }
+
+    #[test]
+    fn stats_compare_estimates_to_tracked_time() {
+        let mut unfinished = finished_task(4, 0, 1, "2026-10-05 12:00");
+        unfinished.completed_at = None;
+        let tasks = vec![
+            finished_task(1, 0, 2, "2026-09-10 12:00"),
+            finished_task(2, 1, 1, "2026-10-01 12:00"),
+            finished_task(3, 0, 2, "2026-10-02 12:00"),
+            unfinished,
+            // Finished, but not tracked
+            finished_task(5, 0, 1, "2026-10-03 12:00"),
+        ];
+        let entries = vec![
+            entry(1, 1, "2026-09-09 09:00", 2),
+            entry(2, 1, "2026-09-10 09:00", 1),
+            entry(3, 2, "2026-10-01 09:00", 2),
+            entry(4, 3, "2026-10-02 09:00", 2),
+            entry(5, 4, "2026-10-04 09:00", 5),
+            // Still running
+            TimeEntry {
+                id: 6,
+                task_id: 3,
+                start: datetime("2026-10-03 09:00"),
+                end: None,
+            },
+        ];
+        let stats = EstimateStats::new(&tasks, &entries);
+
+        assert_eq!(
+            stats.overall,
+            Accuracy {
+                tasks: 3,
+                estimated: Duration::hours(5),
+                actual: Duration::hours(7),
+                factor: 1.4,
+            }
+        );
+        let segments = stats
+            .per_segment
+            .iter()
+            .map(|segment| (segment.time_segment_id, segment.accuracy.factor))
+            .collect::<Vec<_>>();
+        assert_eq!(segments, [(0, 1.25), (1, 2.0)]);
+        let months = stats
+            .per_month
+            .iter()
+            .map(|month| (month.month, month.accuracy.tasks, month.accuracy.factor))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            months,
+            [
+                (NaiveDate::from_ymd_opt(2026, 9, 1).unwrap(), 1, 1.5),
+                (NaiveDate::from_ymd_opt(2026, 10, 1).unwrap(), 2, 4.0 / 3.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn factors_need_enough_finished_tasks() {
+        let tasks = (1..=4)
+            .map(|id| finished_task(id, if id == 4 { 1 } else { 0 }, 1, "2026-10-01 12:00"))
+            .collect::<Vec<_>>();
+        let entries = (1..=4)
+            .map(|id| entry(id, id, "2026-10-01 09:00", if id == 4 { 6 } else { 2 }))
+            .collect::<Vec<_>>();
+
+        assert_eq!(EstimateStats::new(&tasks[..2], &entries).factor(0), None);
+        let stats = EstimateStats::new(&tasks, &entries);
+        assert_eq!(stats.factor(0), Some(2.0));
+        // Segment 1 has a single task, so the overall factor is used
+        assert_eq!(stats.factor(1), Some(3.0));
+        assert_eq!(stats.factor(2), Some(3.0));
+    }
+
+    #[test]
+    fn extra_time_only_makes_estimates_longer() {
+        assert_eq!(extra_time(Duration::hours(2), 1.25), Duration::minutes(30));
+        assert_eq!(extra_time(Duration::hours(2), 1.0), Duration::zero());
+        assert_eq!(extra_time(Duration::hours(2), 0.5), Duration::zero());
+    }
+}
@@ -0,0 +1,740 @@
+//! Reads and writes the iCalendar format ([RFC 5545](https://tools.ietf.org/html/rfc5545)), so
+//! that schedules can be shown in calendar applications and appointments and to-dos can be
+//! imported from them.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use failure::Fail;
+use ical::parser::ical::component::{IcalEvent, IcalTodo};
+use ical::property::Property;
+use ical::IcalParser;
+
+use crate::recurrence::Recurrence;
+use crate::{FixedEvent, NewFixedEvent, NewTask, Schedule, Task};
+
+const DATETIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Serialises a schedule into an iCalendar with one event for every scheduled task (or chunk of
+/// a task). `stamp` is the moment the calendar is created at.
+///
+/// The UIDs of the events are derived from the task ids, so that exporting a new schedule
+/// updates the events of the previous one in calendar applications instead of duplicating them.
+pub fn export_schedule(schedule: &Schedule<Task>, stamp: DateTime<Utc>) -> String {
+    let events = scheduled_events(schedule, stamp)
+        .into_iter()
+        .flat_map(|(_, lines)| lines)
+        .collect();
+    write_calendar(events)
+}
+
+/// Like `export_schedule`, but with every event in an iCalendar of its own, as CalDAV servers
+/// store them. The events are returned together with their UIDs.
+pub fn export_events(schedule: &Schedule<Task>, stamp: DateTime<Utc>) -> Vec<(String, String)> {
+    scheduled_events(schedule, stamp)
+        .into_iter()
+        .map(|(uid, lines)| (uid, write_calendar(lines)))
+        .collect()
+}
+
+/// Returns the UID and content lines of the VEVENT of every scheduled task.
+fn scheduled_events(schedule: &Schedule<Task>, stamp: DateTime<Utc>) -> Vec<(String, Vec<String>)> {
+    // A recurring or split up task is scheduled several times, so number its entries
+    let mut n_entries: HashMap<u32, usize> = HashMap::new();
+    schedule
+        .tasks
+        .iter()
+        .map(|scheduled| {
+            let task = &scheduled.task;
+            let index = n_entries.entry(task.id).or_insert(0);
+            let uid = format!("{}{}-{}", TASK_UID_PREFIX, task.id, index);
+            *index += 1;
+            let lines = vec![
+                "BEGIN:VEVENT".to_owned(),
+                format!("UID:{}", uid),
+                format!("DTSTAMP:{}", stamp.format(DATETIME_FORMAT)),
+                format!("DTSTART:{}", scheduled.when.format(DATETIME_FORMAT)),
+                format!(
+                    "DTEND:{}",
+                    (scheduled.when + scheduled.duration).format(DATETIME_FORMAT)
+                ),
+                format!("SUMMARY:{}", escape_text(&task.content)),
+                format!(
+                    "DESCRIPTION:{}",
+                    escape_text(&format!(
+                        "Deadline: {}\nImportance: {}",
+                        task.deadline.format("%Y-%m-%d %H:%M UTC"),
+                        task.importance
+                    ))
+                ),
+                "END:VEVENT".to_owned(),
+            ];
+            (uid, lines)
+        })
+        .collect()
+}
+
+/// Wraps the content lines of some components in a VCALENDAR and folds them.
+fn write_calendar(components: Vec<String>) -> String {
+    let mut ics = String::new();
+    let header = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        format!("PRODID:-//eva//eva {}//EN", env!("CARGO_PKG_VERSION")),
+        "CALSCALE:GREGORIAN".to_owned(),
+    ];
+    for line in header
+        .into_iter()
+        .chain(components)
+        .chain(Some("END:VCALENDAR".to_owned()))
+    {
+        write_folded(&mut ics, &line);
+    }
+    ics
+}
+
+/// What the UIDs of the events of exported schedules start with.
+pub(crate) const TASK_UID_PREFIX: &str = "eva-task-";
+
+#[derive(Debug, Fail)]
+#[fail(display = "I could not read the iCalendar file: {}", reason)]
+pub struct ParseError {
+    pub reason: String,
+}
+
+/// The parts of an iCalendar file that Eva can use.
+#[derive(Debug, Default)]
+pub struct Calendar {
+    /// The events, which block your time like fixed events do.
+    pub events: Vec<NewFixedEvent>,
+    /// The to-dos, which become tasks.
+    pub todos: Vec<NewTask>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// A component of an iCalendar file that wasn't imported.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Skipped {
+    pub summary: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Imported {
+    Task(Task),
+    Event(FixedEvent),
+}
+
+/// What importing an iCalendar file changed.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ImportReport {
+    pub created: Vec<Imported>,
+    pub updated: Vec<Imported>,
+    /// Events that were removed from a synchronised calendar since the last time.
+    pub deleted: Vec<Imported>,
+    pub skipped: Vec<Skipped>,
+}
+
+/// Parses the events and to-dos of an iCalendar file. Everything that can't be imported ends up
+/// in `Calendar::skipped`, together with the reason why.
+///
+/// Times that aren't in UTC are taken to be in the given time zone: time zone definitions in the
+/// file itself are ignored.
+pub fn parse_calendar<Tz: TimeZone>(
+    reader: impl BufRead,
+    timezone: &Tz,
+) -> Result<Calendar, ParseError> {
+    let mut calendar = Calendar::default();
+    for ical_calendar in IcalParser::new(reader) {
+        let ical_calendar = ical_calendar.map_err(|e| ParseError {
+            reason: e.to_string(),
+        })?;
+        for event in ical_calendar.events {
+            match parse_event(&event, timezone) {
+                Ok(event) => calendar.events.push(event),
+                Err(reason) => calendar.skipped.push(Skipped {
+                    summary: summary(&event.properties, "event"),
+                    reason,
+                }),
+            }
+        }
+        for todo in ical_calendar.todos {
+            match parse_todo(&todo, timezone) {
+                Ok(todo) => calendar.todos.push(todo),
+                Err(reason) => calendar.skipped.push(Skipped {
+                    summary: summary(&todo.properties, "to-do"),
+                    reason,
+                }),
+            }
+        }
+    }
+    Ok(calendar)
+}
+
+fn parse_event<Tz: TimeZone>(
+    event: &IcalEvent,
+    timezone: &Tz,
+) -> std::result::Result<NewFixedEvent, String> {
+    let properties = &event.properties;
+    if value(properties, "STATUS") == Some("CANCELLED") {
+        Err("it was cancelled")?;
+    }
+    if value(properties, "TRANSP") == Some("TRANSPARENT") {
+        Err("it doesn't block your time")?;
+    }
+    if ["RRULE", "RDATE", "RECURRENCE-ID"]
+        .iter()
+        .any(|name| value(properties, name).is_some())
+    {
+        Err("repeating events aren't supported yet")?;
+    }
+    let start = property(properties, "DTSTART").ok_or("it has no start")?;
+    if is_date(start) {
+        Err("all-day events don't block your time")?;
+    }
+    let start = parse_datetime(start, timezone).ok_or("its start can't be read")?;
+    let end = match (property(properties, "DTEND"), value(properties, "DURATION")) {
+        (Some(end), _) => parse_datetime(end, timezone).ok_or("its end can't be read")?,
+        (None, Some(duration)) => {
+            start + parse_duration(duration).ok_or("its duration can't be read")?
+        }
+        (None, None) => start,
+    };
+    if end <= start {
+        Err("it doesn't take any time")?;
+    }
+    Ok(NewFixedEvent {
+        title: summary(properties, "event"),
+        start,
+        end,
+        time_segment_id: None,
+        ical_uid: value(properties, "UID").map(str::to_owned),
+    })
+}
+
+fn parse_todo<Tz: TimeZone>(
+    todo: &IcalTodo,
+    timezone: &Tz,
+) -> std::result::Result<NewTask, String> {
+    let properties = &todo.properties;
+    match value(properties, "STATUS") {
+        Some("COMPLETED") => Err("it's already completed")?,
+        Some("CANCELLED") => Err("it was cancelled")?,
+        _ => {}
+    }
+    if value(properties, "COMPLETED").is_some() {
+        Err("it's already completed")?;
+    }
+    let due = property(properties, "DUE").ok_or("it has no due date")?;
+    let deadline = parse_datetime(due, timezone).ok_or("its due date can't be read")?;
+    let duration = ["DURATION", "ESTIMATED-DURATION", "X-ESTIMATED-DURATION"]
+        .iter()
+        .filter_map(|name| value(properties, name))
+        .next()
+        .ok_or("it has no duration or estimate")?;
+    let duration = parse_duration(duration)
+        .filter(|duration| *duration > Duration::zero())
+        .ok_or("its duration can't be read")?;
+    // PRIORITY goes from 1 (highest) to 9 (lowest), with 0 meaning undefined
+    let importance = match value(properties, "PRIORITY").map(str::parse::<u32>) {
+        Some(Ok(priority)) if (1..=9).contains(&priority) => 11 - priority,
+        _ => 5,
+    };
+    let recurrence = value(properties, "RRULE")
+        .map(str::parse::<Recurrence>)
+        .transpose()
+        .map_err(|e| format!("its repetition can't be read ({})", e.reason))?;
+    Ok(NewTask {
+        content: summary(properties, "to-do"),
+        deadline,
+        available_from: None,
+        duration,
+        importance,
+        time_segment_id: 0,
+        recurrence,
+        min_chunk_duration: None,
+        ical_uid: value(properties, "UID").map(str::to_owned),
+    })
+}
+
+fn property<'a>(properties: &'a [Property], name: &str) -> Option<&'a Property> {
+    properties.iter().find(|property| property.name == name)
+}
+
+fn value<'a>(properties: &'a [Property], name: &str) -> Option<&'a str> {
+    property(properties, name).and_then(|property| property.value.as_deref())
+}
+
+fn summary(properties: &[Property], kind: &str) -> String {
+    value(properties, "SUMMARY")
+        .map(unescape_text)
+        .unwrap_or_else(|| format!("Untitled {}", kind))
+}
+
+/// Whether the property is a date without a time of day.
+fn is_date(property: &Property) -> bool {
+    let has_date_type = property
+        .params
+        .iter()
+        .flatten()
+        .any(|(name, values)| name == "VALUE" && values.iter().any(|value| value == "DATE"));
+    has_date_type || property.value.as_deref().map(str::len) == Some(8)
+}
+
+/// Parses a DATE-TIME or DATE value. Dates are taken to mean the end of that day.
+fn parse_datetime<Tz: TimeZone>(property: &Property, timezone: &Tz) -> Option<DateTime<Utc>> {
+    let value = property.value.as_ref()?;
+    if value.ends_with('Z') {
+        return NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+            .ok()
+            .map(|utc| Utc.from_utc_datetime(&utc));
+    }
+    let local = if is_date(property) {
+        NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()?
+            .succ_opt()?
+            .and_hms_opt(0, 0, 0)?
+    } else {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?
+    };
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// Parses a DURATION value like 'PT1H30M' or 'P2D'.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.chars().next()? {
+        '-' => (true, &value[1..]),
+        '+' => (false, &value[1..]),
+        _ => (false, value),
+    };
+    if !value.starts_with('P') {
+        return None;
+    }
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value[1..].chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if number.is_empty() && !in_time => in_time = true,
+            unit => {
+                let n = number.parse::<i64>().ok()?;
+                number.clear();
+                duration += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(if negative { -duration } else { duration })
+}
+
+/// Undoes `escape_text`.
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(escaped) => unescaped.push(escaped),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Escapes the characters that have a special meaning in TEXT values.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a content line, folding it so that no line is longer than 75 octets.
+fn write_folded(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            // The space that continues the line counts towards its length
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Duration;
+    use ical::IcalParser;
+
+    use crate::Scheduled;
+
+    fn datetime(rfc3339: &str) -> DateTime<Utc> {
+        rfc3339.parse().unwrap()
+    }
+
+    fn task(id: u32, content: &str) -> Task {
+        Task {
+            id,
+            content: content.to_owned(),
+            deadline: datetime("2026-10-20T17:00:00Z"),
+            available_from: None,
+            duration: Duration::hours(2),
+            importance: 5,
+            time_segment_id: 0,
+            recurrence: None,
+            min_chunk_duration: None,
+            depends_on: vec![],
+            completed_at: None,
+            ical_uid: None,
+            pin: None,
+            blocked: None,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_a_parser() {
+        let stamp = datetime("2026-10-17T12:00:00Z");
+        let long_content = "Write the introduction, the methods section; and the results \
+                            of the experiment with the 🐙 (it took ages)";
+        let schedule = Schedule {
+            tasks: vec![
+                Scheduled {
+                    task: task(1, long_content),
+                    when: datetime("2026-10-18T09:00:00Z"),
+                    duration: Duration::hours(1),
+                },
+                Scheduled {
+                    task: task(2, "Call mum"),
+                    when: datetime("2026-10-18T10:00:00Z"),
+                    duration: Duration::hours(2),
+                },
+                Scheduled {
+                    task: task(1, long_content),
+                    when: datetime("2026-10-19T09:00:00Z"),
+                    duration: Duration::hours(1),
+                },
+            ],
+            events: vec![],
+        };
+        let ics = export_schedule(&schedule, stamp);
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+
+        let mut calendars = IcalParser::new(ics.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(calendars.len(), 1);
+        let calendar = calendars.pop().unwrap();
+        let events = calendar
+            .events
+            .iter()
+            .map(|event| {
+                event
+                    .properties
+                    .iter()
+                    .map(|property| (property.name.as_str(), property.value.clone().unwrap()))
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(events.len(), 3);
+
+        let uids = events.iter().map(|event| &event["UID"]).collect::<Vec<_>>();
+        assert_eq!(uids, vec!["eva-task-1-0", "eva-task-2-0", "eva-task-1-1"]);
+        assert_eq!(events[1]["DTSTAMP"], "20261017T120000Z");
+        assert_eq!(events[1]["DTSTART"], "20261018T100000Z");
+        assert_eq!(events[1]["DTEND"], "20261018T120000Z");
+        assert_eq!(events[1]["SUMMARY"], "Call mum");
+        assert_eq!(
+            events[1]["DESCRIPTION"],
+            "Deadline: 2026-10-20 17:00 UTC\\nImportance: 5"
+        );
+        assert_eq!(events[2]["DTEND"], "20261019T100000Z");
+        assert_eq!(events[0]["SUMMARY"], escape_text(long_content));
+    }
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Example//Calendar//EN\r
+BEGIN:VEVENT\r
+UID:meeting@example.com\r
+DTSTAMP:20261017T120000Z\r
+DTSTART:20261019T090000Z\r
+DTEND:20261019T103000Z\r
+SUMMARY:Planning\\, with the team\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:lunch@example.com\r
+DTSTAMP:20261017T120000Z\r
+DTSTART:20261019T120000\r
+DURATION:PT45M\r
+SUMMARY:Lunch\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:birthday@example.com\r
+DTSTAMP:20261017T120000Z\r
+DTSTART;VALUE=DATE:20261020\r
+SUMMARY:Birthday\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup@example.com\r
+DTSTAMP:20261017T120000Z\r
+DTSTART:20261019T083000Z\r
+DTEND:20261019T084500Z\r
+RRULE:FREQ=DAILY\r
+SUMMARY:Stand-up\r
+END:VEVENT\r
+BEGIN:VTODO\r
+UID:report@example.com\r
+DTSTAMP:20261017T120000Z\r
+DUE:20261023T170000Z\r
+DURATION:PT3H\r
+PRIORITY:1\r
+SUMMARY:Write the report\r
+END:VTODO\r
+BEGIN:VTODO\r
+UID:taxes@example.com\r
+DTSTAMP:20261017T120000Z\r
+DUE;VALUE=DATE:20261031\r
+X-ESTIMATED-DURATION:P1DT2H\r
+SUMMARY:Taxes\r
+END:VTODO\r
+BEGIN:VTODO\r
+UID:someday@example.com\r
+DTSTAMP:20261017T120000Z\r
+DURATION:PT1H\r
+SUMMARY:Learn the banjo\r
+END:VTODO\r
+BEGIN:VTODO\r
+UID:groceries@example.com\r
+DTSTAMP:20261017T120000Z\r
+DUE:20261018T170000Z\r
+DURATION:PT1H\r
+STATUS:COMPLETED\r
+SUMMARY:Groceries\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn parses_events_and_todos() {
+        let calendar = parse_calendar(
+            CALENDAR.as_bytes(),
+            &FixedOffset::east_opt(2 * 3600).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(calendar.events.len(), 2);
+        let planning = &calendar.events[0];
+        assert_eq!(planning.title, "Planning, with the team");
+        assert_eq!(planning.start, datetime("2026-10-19T09:00:00Z"));
+        assert_eq!(planning.end, datetime("2026-10-19T10:30:00Z"));
+        assert_eq!(planning.ical_uid, Some("meeting@example.com".to_owned()));
+        // Times without a time zone are in the given one
+        let lunch = &calendar.events[1];
+        assert_eq!(lunch.start, datetime("2026-10-19T10:00:00Z"));
+        assert_eq!(lunch.end, datetime("2026-10-19T10:45:00Z"));
+
+        assert_eq!(calendar.todos.len(), 2);
+        let report = &calendar.todos[0];
+        assert_eq!(report.content, "Write the report");
+        assert_eq!(report.deadline, datetime("2026-10-23T17:00:00Z"));
+        assert_eq!(report.duration, Duration::hours(3));
+        assert_eq!(report.importance, 10);
+        assert_eq!(report.ical_uid, Some("report@example.com".to_owned()));
+        // A due date means the end of that day
+        let taxes = &calendar.todos[1];
+        assert_eq!(taxes.deadline, datetime("2026-10-31T22:00:00Z"));
+        assert_eq!(taxes.duration, Duration::hours(26));
+        assert_eq!(taxes.importance, 5);
+
+        let skipped = calendar
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.summary.as_str(), skipped.reason.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            skipped,
+            vec![
+                ("Birthday", "all-day events don't block your time"),
+                ("Stand-up", "repeating events aren't supported yet"),
+                ("Learn the banjo", "it has no due date"),
+                ("Groceries", "it's already completed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let result = parse_calendar("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\n".as_bytes(), &Utc);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P2W"), Some(Duration::weeks(2)));
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("P1DT12H"), Some(Duration::hours(36)));
+        assert_eq!(parse_duration("PT1D"), None);
+        assert_eq!(parse_duration("P1H"), None);
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("PT15"), None);
+    }
+
+    #[cfg(all(feature = "sqlite", feature = "clock"))]
+    #[test]
+    fn importing_twice_changes_nothing() {
+        use futures::executor::block_on;
+
+        use crate::configuration::Configuration;
+        use crate::database::sqlite::make_connection;
+
+        let configuration = Configuration {
+            database: Box::new(make_connection(":memory:").unwrap()),
+            scheduling_strategy: "importance".to_owned(),
+            strategies: Default::default(),
+            correct_estimates: false,
+        };
+        let import = |calendar: &str| {
+            block_on(crate::import_calendar(
+                &configuration,
+                calendar.as_bytes(),
+                &Utc,
+            ))
+            .unwrap()
+        };
+
+        let report = import(CALENDAR);
+        assert_eq!(report.created.len(), 4);
+        assert!(report.updated.is_empty());
+        assert_eq!(report.skipped.len(), 4);
+
+        let report = import(CALENDAR);
+        assert!(report.created.is_empty());
+        assert!(report.updated.is_empty());
+        assert_eq!(report.skipped.len(), 8);
+        assert_eq!(
+            block_on(crate::fixed_events(&configuration)).unwrap().len(),
+            2
+        );
+        assert_eq!(block_on(crate::tasks(&configuration)).unwrap().len(), 2);
+
+        // Changes are recognised by the UID
+        let report = import(&CALENDAR.replace("DURATION:PT3H", "DURATION:PT4H"));
+        assert!(report.created.is_empty());
+        match &report.updated[..] {
+            [Imported::Task(task)] => {
+                assert_eq!(task.content, "Write the report");
+                assert_eq!(task.duration, Duration::hours(4));
+            }
+            updated => panic!("unexpected updates: {:?}", updated),
+        }
+        assert_eq!(block_on(crate::tasks(&configuration)).unwrap().len(), 2);
+    }
+
+    #[cfg(all(feature = "sqlite", feature = "clock"))]
+    #[test]
+    fn imports_events_without_a_uid() {
+        use futures::executor::block_on;
+        use itertools::Itertools;
+
+        use crate::configuration::Configuration;
+        use crate::database::sqlite::make_connection;
+
+        let configuration = Configuration {
+            database: Box::new(make_connection(":memory:").unwrap()),
+            scheduling_strategy: "importance".to_owned(),
+            strategies: Default::default(),
+            correct_estimates: false,
+        };
+        let calendar = "\
+BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Example//Example//EN\r
+BEGIN:VEVENT\r
+DTSTART:20261019T090000Z\r
+DTEND:20261019T100000Z\r
+SUMMARY:Dentist\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:20261020T090000Z\r
+DTEND:20261020T100000Z\r
+SUMMARY:Hairdresser\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+        let import = || {
+            block_on(crate::import_calendar(
+                &configuration,
+                calendar.as_bytes(),
+                &Utc,
+            ))
+            .unwrap()
+        };
+
+        let report = import();
+        assert_eq!(report.created.len(), 2);
+        let events = block_on(crate::fixed_events(&configuration)).unwrap();
+        let titles = events
+            .iter()
+            .map(|event| event.title.as_str())
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["Dentist", "Hairdresser"]);
+
+        // They're recognised by their title and start instead
+        let report = import();
+        assert!(report.created.is_empty());
+        assert!(report.updated.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(
+            block_on(crate::fixed_events(&configuration)).unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn escapes_text() {
+        assert_eq!(
+            escape_text("a, b; c\\d\r\ne"),
+            "a\\, b\\; c\\\\d\\ne".to_owned()
+        );
+        assert_eq!(unescape_text("a\\, b\\; c\\\\d\\ne"), "a, b; c\\d\ne");
+    }
+}
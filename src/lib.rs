@@ -8,18 +8,40 @@ extern crate diesel;
 #[macro_use]
 extern crate diesel_migrations;
 
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
 use chrono::prelude::*;
 use chrono::Duration;
 use derive_new::new;
 use failure::Fail;
 use futures::prelude::*;
+use itertools::Itertools;
 
-use crate::configuration::{Configuration, SchedulingStrategy};
+use crate::configuration::Configuration;
+use crate::estimates::EstimateStats;
+use crate::icalendar::{ImportReport, Imported, Skipped};
+use crate::recurrence::Recurrence;
 
-pub use crate::scheduling::{Schedule, Scheduled};
+pub use crate::scheduling::{
+    BestEffortSchedule, Constraint, EarliestDeadlineFirstStrategy, Explanation, FreeSlots,
+    ImportanceStrategy, Move, OptimalStrategy, Phase, Planner, Schedule, Scheduled, StableSchedule,
+    Step, Strategy, Unschedulable, UnschedulableReason, UrgencyStrategy,
+    WeightedShortestJobFirstStrategy,
+};
 
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+#[cfg(feature = "caldav")]
+pub mod caldav;
 pub mod configuration;
 pub mod database;
+pub mod estimates;
+pub mod icalendar;
+pub mod pomodoro;
+pub mod recurrence;
 mod scheduling;
 pub mod time_segment;
 mod util;
@@ -30,36 +52,224 @@ pub enum Error {
     Database(#[cause] crate::database::Error),
     #[fail(display = "{}", _0)]
     Schedule(#[cause] crate::scheduling::Error<Task>),
+    #[fail(display = "{}", _0)]
+    Import(#[cause] crate::icalendar::ParseError),
+    #[cfg(feature = "caldav")]
+    #[fail(display = "{}", _0)]
+    CalDav(#[cause] crate::caldav::Error),
+    #[fail(
+        display = "I don't know a scheduling strategy called {}. You can choose from: {}",
+        name, known
+    )]
+    UnknownStrategy {
+        name: String,
+        /// The names of the strategies in the configuration, separated by commas.
+        known: String,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, new, Clone)]
+#[derive(Debug, Clone)]
 pub struct NewTask {
     pub content: String,
     pub deadline: DateTime<Utc>,
+    /// When set, the task isn't scheduled before this time, like when you can only start once a
+    /// letter has arrived.
+    pub available_from: Option<DateTime<Utc>>,
     pub duration: Duration,
     pub importance: u32,
     pub time_segment_id: u32,
+    pub recurrence: Option<Recurrence>,
+    /// When set, the task can be split up into chunks of at least this long to fit in smaller
+    /// gaps.
+    pub min_chunk_duration: Option<Duration>,
+    /// The UID of the iCalendar to-do the task was imported from, if it was.
+    pub ical_uid: Option<String>,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Task {
     pub id: u32,
     pub content: String,
     pub deadline: DateTime<Utc>,
+    /// When set, the task isn't scheduled before this time.
+    pub available_from: Option<DateTime<Utc>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::duration")
+    )]
     pub duration: Duration,
     pub importance: u32,
     pub time_segment_id: u32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::optional_recurrence")
+    )]
+    pub recurrence: Option<Recurrence>,
+    /// When set, the task can be split up into chunks of at least this long to fit in smaller
+    /// gaps.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::optional_duration")
+    )]
+    pub min_chunk_duration: Option<Duration>,
+    /// The ids of the tasks that have to be finished before this one can be started. Use
+    /// `add_dependency` and `delete_dependency` to change them.
+    pub depends_on: Vec<u32>,
+    /// When the task was finished, if it was. Finished tasks aren't scheduled anymore.
+    pub completed_at: Option<DateTime<Utc>>,
+    /// The UID of the iCalendar to-do the task was imported from, if it was.
+    pub ical_uid: Option<String>,
+    /// The time the task is pinned to, if it is. Use `pin_task` and `unpin_task` to change it.
+    pub pin: Option<Pin>,
+    /// What the task is waiting for, if it's blocked. Use `block_task` and `unblock_task` to
+    /// change it.
+    pub blocked: Option<Blocked>,
+}
+
+/// A time a task is pinned to, regardless of where the scheduling strategy would put it.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Pin {
+    /// The task starts exactly at this time.
+    At(DateTime<Utc>),
+    /// The task doesn't start before this time.
+    NotBefore(DateTime<Utc>),
+}
+
+/// Why a task can't be worked on for now. Blocked tasks aren't scheduled until they're unblocked.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Blocked {
+    /// What or whom the task is waiting for.
+    pub reason: String,
+    /// When to check in on it again, if there's a moment for that.
+    pub follow_up: Option<DateTime<Utc>>,
+}
+
+impl Blocked {
+    /// Returns whether it's time to follow up on what the task is waiting for.
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        self.follow_up.is_some_and(|follow_up| follow_up <= now)
+    }
 }
 
 impl PartialEq<NewTask> for Task {
     fn eq(&self, other: &NewTask) -> bool {
         self.content == other.content
             && self.deadline == other.deadline
+            && self.available_from == other.available_from
             && self.duration == other.duration
             && self.importance == other.importance
             && self.time_segment_id == other.time_segment_id
+            && self.recurrence == other.recurrence
+            && self.min_chunk_duration == other.min_chunk_duration
+            && self.ical_uid == other.ical_uid
+    }
+}
+
+impl Task {
+    /// Returns this task as it will be due on its next occurrence, or None if it doesn't recur
+    /// anymore.
+    pub fn next_occurrence(&self) -> Option<Task> {
+        let recurrence = self.recurrence.as_ref()?;
+        let deadline = recurrence.occurrences(self.deadline).nth(1)?;
+        Some(Task {
+            deadline,
+            recurrence: recurrence.advance(),
+            // The pin was meant for this occurrence.
+            pin: None,
+            ..self.clone()
+        })
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+}
+
+#[derive(Debug, new, Clone)]
+pub struct NewFixedEvent {
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub time_segment_id: Option<u32>,
+    /// The UID of the iCalendar event the event was imported from, if it was.
+    pub ical_uid: Option<String>,
+}
+
+/// An appointment at a fixed time, like a meeting. The scheduler plans tasks around it.
+///
+/// An event with a time segment only blocks the time of that segment, so the tasks of other time
+/// segments can still be planned during it. Without one, it blocks your time in every segment.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FixedEvent {
+    pub id: u32,
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub time_segment_id: Option<u32>,
+    /// The UID of the iCalendar event the event was imported from, if it was.
+    pub ical_uid: Option<String>,
+}
+
+impl FixedEvent {
+    /// Returns whether the event keeps the tasks of `segment` from being planned during it.
+    pub(crate) fn blocks(&self, segment: &impl time_segment::TimeSegment) -> bool {
+        self.time_segment_id.is_none() || self.time_segment_id == segment.id()
+    }
+}
+
+impl PartialEq<NewFixedEvent> for FixedEvent {
+    fn eq(&self, other: &NewFixedEvent) -> bool {
+        self.title == other.title
+            && self.start == other.start
+            && self.end == other.end
+            && self.time_segment_id == other.time_segment_id
+            && self.ical_uid == other.ical_uid
+    }
+}
+
+/// A calendar object on a CalDAV server as Eva last saw it, used to find out what changed since
+/// the last synchronisation.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct CalDavResource {
+    pub href: String,
+    pub etag: String,
+    pub ical_uid: String,
+    /// Whether Eva put it there as part of a schedule, rather than it being one of your events.
+    pub pushed: bool,
+}
+
+/// Where a task, or a chunk of it, is in the last accepted schedule.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Placement {
+    pub task_id: u32,
+    /// The deadline of the occurrence, which tells the occurrences of a recurring task apart.
+    pub deadline: DateTime<Utc>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// A stretch of time spent on a task. Use `start_timer` and `stop_timer` to track them.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TimeEntry {
+    pub id: u32,
+    pub task_id: u32,
+    pub start: DateTime<Utc>,
+    /// When the timer was stopped, or None while it's still running.
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimeEntry {
+    /// Returns how long the entry lasted, counting a running entry up to `now`.
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        self.end.unwrap_or(now) - self.start
     }
 }
 
@@ -83,6 +293,82 @@ pub fn delete_task<'a: 'b, 'b>(
         .map_err(Error::Database)
 }
 
+/// Marks a task as done at the current time. A recurring task moves on to its next occurrence,
+/// which is returned, and the occurrence that was done is kept as a separate, finished task.
+pub fn complete_task<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    id: u32,
+) -> impl Future<Output = Result<Option<Task>>> + 'b {
+    async move {
+        let task = configuration
+            .database
+            .get_task(id)
+            .await
+            .map_err(Error::Database)?;
+        let completed_at = configuration.now();
+        // Finishing a task stops the timer on it.
+        let timer_running = configuration
+            .database
+            .all_time_entries()
+            .await
+            .map_err(Error::Database)?
+            .iter()
+            .any(|entry| entry.task_id == id && entry.end.is_none());
+        if timer_running {
+            configuration
+                .database
+                .stop_time_entries(completed_at)
+                .await
+                .map_err(Error::Database)?;
+        }
+        match task.next_occurrence() {
+            Some(next_task) => {
+                let done = NewTask {
+                    content: task.content,
+                    deadline: task.deadline,
+                    available_from: task.available_from,
+                    duration: task.duration,
+                    importance: task.importance,
+                    time_segment_id: task.time_segment_id,
+                    recurrence: None,
+                    min_chunk_duration: task.min_chunk_duration,
+                    ical_uid: None,
+                };
+                let done = configuration
+                    .database
+                    .add_task(done)
+                    .await
+                    .map_err(Error::Database)?;
+                configuration
+                    .database
+                    .complete_task(done.id, completed_at)
+                    .await
+                    .map_err(Error::Database)?;
+                // The time spent on this occurrence doesn't count for the next one.
+                configuration
+                    .database
+                    .move_time_entries(id, done.id)
+                    .await
+                    .map_err(Error::Database)?;
+                configuration
+                    .database
+                    .update_task(next_task.clone())
+                    .await
+                    .map_err(Error::Database)?;
+                Ok(Some(next_task))
+            }
+            None => {
+                configuration
+                    .database
+                    .complete_task(id, completed_at)
+                    .await
+                    .map_err(Error::Database)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
 pub fn get_task<'a: 'b, 'b>(
     configuration: &'a Configuration,
     id: u32,
@@ -100,33 +386,647 @@ pub fn update_task<'a: 'b, 'b>(
         .map_err(Error::Database)
 }
 
+/// Pins a task to a time. When the task wouldn't be done by its deadline from that time on, it
+/// isn't pinned and that's returned as an error. A task that's pinned to start at an exact time
+/// is tried out there right away as well: when it runs into time outside its time segment, a
+/// fixed event, another pinned task or a prerequisite that isn't done by then, the pin is undone
+/// and the conflict is returned as an error. So is any other error that comes up while trying.
+///
+/// Pins to a time that has passed don't have any effect.
+pub fn pin_task<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    id: u32,
+    pin: Pin,
+) -> impl Future<Output = Result<Task>> + 'b {
+    async move {
+        let task = configuration
+            .database
+            .get_task(id)
+            .await
+            .map_err(Error::Database)?;
+        let pinned = Task {
+            pin: Some(pin),
+            ..task.clone()
+        };
+        let (Pin::At(at) | Pin::NotBefore(at)) = pin;
+        if task.deadline < at + task.duration {
+            return Err(Error::Schedule(scheduling::Error::PinConflict {
+                task: Box::new(pinned),
+                conflict: Box::new(scheduling::PinConflict::Deadline),
+            }));
+        }
+        configuration
+            .database
+            .update_task(pinned.clone())
+            .await
+            .map_err(Error::Database)?;
+        if let Pin::At(_) = pin {
+            if let Err(error) = check_pin(configuration, id).await {
+                configuration
+                    .database
+                    .update_task(task)
+                    .await
+                    .map_err(Error::Database)?;
+                return Err(error);
+            }
+        }
+        Ok(pinned)
+    }
+}
+
+/// Schedules the tasks to find out whether the task with id `id` can be done at the time it's
+/// pinned to, and returns the conflict as an error when it can't.
+fn check_pin<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    id: u32,
+) -> impl Future<Output = Result<()>> + 'b {
+    async move {
+        let best_effort =
+            schedule_best_effort(configuration, &configuration.scheduling_strategy).await?;
+        let conflict = best_effort
+            .unschedulable
+            .into_iter()
+            .find_map(|unschedulable| {
+                let conflict = unschedulable.pin_conflict?;
+                let involved = unschedulable.task.id == id
+                    || conflict.task().is_some_and(|other| other.id == id);
+                involved.then_some((unschedulable.task, conflict))
+            });
+        let error = match conflict {
+            None => return Ok(()),
+            // Tell about the conflict from the point of view of the task that was pinned.
+            Some((failed, scheduling::PinConflict::Task(ours))) if failed.id != id => {
+                scheduling::Error::PinConflict {
+                    task: Box::new(ours),
+                    conflict: Box::new(scheduling::PinConflict::Task(failed)),
+                }
+            }
+            Some((failed, conflict)) => scheduling::Error::PinConflict {
+                task: Box::new(failed),
+                conflict: Box::new(conflict),
+            },
+        };
+        Err(Error::Schedule(error))
+    }
+}
+
+/// Lets the scheduling strategy decide when a pinned task is done again.
+pub fn unpin_task<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    id: u32,
+) -> impl Future<Output = Result<Task>> + 'b {
+    async move {
+        let task = configuration
+            .database
+            .get_task(id)
+            .await
+            .map_err(Error::Database)?;
+        let unpinned = Task { pin: None, ..task };
+        configuration
+            .database
+            .update_task(unpinned.clone())
+            .await
+            .map_err(Error::Database)?;
+        Ok(unpinned)
+    }
+}
+
+/// Blocks a task until it's unblocked again. Neither the task nor the tasks that wait for it are
+/// scheduled in the meantime.
+pub fn block_task<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    id: u32,
+    blocked: Blocked,
+) -> impl Future<Output = Result<Task>> + 'b {
+    async move {
+        let task = configuration
+            .database
+            .get_task(id)
+            .await
+            .map_err(Error::Database)?;
+        let blocked = Task {
+            blocked: Some(blocked),
+            ..task
+        };
+        configuration
+            .database
+            .update_task(blocked.clone())
+            .await
+            .map_err(Error::Database)?;
+        Ok(blocked)
+    }
+}
+
+/// Lets a blocked task be scheduled again.
+pub fn unblock_task<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    id: u32,
+) -> impl Future<Output = Result<Task>> + 'b {
+    async move {
+        let task = configuration
+            .database
+            .get_task(id)
+            .await
+            .map_err(Error::Database)?;
+        let unblocked = Task {
+            blocked: None,
+            ..task
+        };
+        configuration
+            .database
+            .update_task(unblocked.clone())
+            .await
+            .map_err(Error::Database)?;
+        Ok(unblocked)
+    }
+}
+
+/// Returns the unfinished tasks that are blocked, the ones to follow up on first. Tasks without a
+/// follow-up date come last.
+pub fn blocked_tasks<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+) -> impl Future<Output = Result<Vec<Task>>> + 'b {
+    configuration
+        .database
+        .all_tasks()
+        .map_ok(|tasks| {
+            let mut tasks: Vec<Task> = tasks
+                .into_iter()
+                .filter(|task| !task.is_completed() && task.blocked.is_some())
+                .collect();
+            tasks.sort_by_key(|task| {
+                let follow_up = task.blocked.as_ref().and_then(|blocked| blocked.follow_up);
+                (follow_up.is_none(), follow_up)
+            });
+            tasks
+        })
+        .map_err(Error::Database)
+}
+
+/// Returns the tasks that haven't been finished yet.
 pub fn tasks<'a: 'b, 'b>(
     configuration: &'a Configuration,
 ) -> impl Future<Output = Result<Vec<Task>>> + 'b {
-    configuration.database.all_tasks().map_err(Error::Database)
+    configuration
+        .database
+        .all_tasks()
+        .map_ok(|tasks| {
+            tasks
+                .into_iter()
+                .filter(|task| !task.is_completed())
+                .collect()
+        })
+        .map_err(Error::Database)
+}
+
+/// Returns the tasks that have been finished, in the order they were finished in.
+pub fn completed_tasks<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+) -> impl Future<Output = Result<Vec<Task>>> + 'b {
+    configuration
+        .database
+        .all_tasks()
+        .map_ok(|tasks| {
+            let mut tasks: Vec<Task> = tasks
+                .into_iter()
+                .filter(|task| task.is_completed())
+                .collect();
+            tasks.sort_by_key(|task| task.completed_at);
+            tasks
+        })
+        .map_err(Error::Database)
+}
+
+/// Starts tracking the time spent on a task. The timer that's running on another task, if any, is
+/// stopped first.
+pub fn start_timer<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    task_id: u32,
+) -> impl Future<Output = Result<TimeEntry>> + 'b {
+    async move {
+        // Make sure the task exists
+        configuration
+            .database
+            .get_task(task_id)
+            .await
+            .map_err(Error::Database)?;
+        let now = configuration.now();
+        configuration
+            .database
+            .stop_time_entries(now)
+            .await
+            .map_err(Error::Database)?;
+        configuration
+            .database
+            .start_time_entry(task_id, now)
+            .await
+            .map_err(Error::Database)
+    }
+}
+
+/// Stops the running timer and returns its time entry, or None if no timer was running.
+pub fn stop_timer<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+) -> impl Future<Output = Result<Option<TimeEntry>>> + 'b {
+    configuration
+        .database
+        .stop_time_entries(configuration.now())
+        .map_ok(|mut entries| entries.pop())
+        .map_err(Error::Database)
+}
+
+/// Returns all time entries, in the order they were started in.
+pub fn time_entries<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+) -> impl Future<Output = Result<Vec<TimeEntry>>> + 'b {
+    configuration
+        .database
+        .all_time_entries()
+        .map_err(Error::Database)
+}
+
+/// Compares how long the finished tasks took, according to the time tracked on them, to how long
+/// they were estimated to take.
+pub fn estimate_stats<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+) -> impl Future<Output = Result<EstimateStats>> + 'b {
+    async move {
+        let tasks = configuration
+            .database
+            .all_tasks()
+            .await
+            .map_err(Error::Database)?;
+        let entries = configuration
+            .database
+            .all_time_entries()
+            .await
+            .map_err(Error::Database)?;
+        Ok(EstimateStats::new(&tasks, &entries))
+    }
+}
+
+/// Makes the task with id `task_id` wait for the task with id `prerequisite_id` to be finished.
+pub fn add_dependency<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    task_id: u32,
+    prerequisite_id: u32,
+) -> impl Future<Output = Result<()>> + 'b {
+    configuration
+        .database
+        .add_dependency(task_id, prerequisite_id)
+        .map_err(Error::Database)
+}
+
+pub fn delete_dependency<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    task_id: u32,
+    prerequisite_id: u32,
+) -> impl Future<Output = Result<()>> + 'b {
+    configuration
+        .database
+        .delete_dependency(task_id, prerequisite_id)
+        .map_err(Error::Database)
 }
 
 pub fn schedule<'a: 'c, 'b: 'c, 'c>(
     configuration: &'a Configuration,
     strategy: &'b str,
 ) -> impl Future<Output = Result<Schedule<Task>>> + 'c {
-    let strategy = match strategy {
-        "importance" => SchedulingStrategy::Importance,
-        "urgency" => SchedulingStrategy::Urgency,
-        _ => panic!("Unsupported scheduling strategy provided"),
-    };
     // Ensure everything is scheduled for some time after the algorithm has
     // finished.
     let start = configuration.now() + Duration::minutes(1);
 
+    async move {
+        let strategy = configuration
+            .strategies
+            .get(strategy)
+            .ok_or_else(|| unknown_strategy(configuration, strategy))?;
+        let (occurrences_per_segment, events) =
+            occurrences_to_schedule(configuration, start).await?;
+        Schedule::schedule(start, occurrences_per_segment, events, strategy)
+            .map(|schedule| schedule.map_tasks(|occurrence| occurrence.task))
+            .map_err(|error| Error::Schedule(error.map_task(|occurrence| occurrence.task)))
+    }
+}
+
+/// Like `schedule`, but when some tasks don't fit, it schedules all the others and returns the
+/// ones that didn't fit next to the schedule, together with the reasons why. It also explains how
+/// every scheduled task ended up where it is.
+pub fn schedule_best_effort<'a: 'c, 'b: 'c, 'c>(
+    configuration: &'a Configuration,
+    strategy: &'b str,
+) -> impl Future<Output = Result<BestEffortSchedule<Task>>> + 'c {
+    let start = configuration.now() + Duration::minutes(1);
+
+    async move {
+        let strategy = configuration
+            .strategies
+            .get(strategy)
+            .ok_or_else(|| unknown_strategy(configuration, strategy))?;
+        let (occurrences_per_segment, events) =
+            occurrences_to_schedule(configuration, start).await?;
+        Schedule::schedule_best_effort(start, occurrences_per_segment, events, strategy)
+            .map(|best_effort| best_effort.map_tasks(|occurrence| occurrence.task))
+            .map_err(|error| Error::Schedule(error.map_task(|occurrence| occurrence.task)))
+    }
+}
+
+/// Like `schedule_best_effort`, but it keeps the tasks where the last accepted schedule has them
+/// as long as they still fit there, and only moves them when the other tasks need the room. Next
+/// to the schedule, it tells which tasks moved. The tasks that were done or deleted since aren't
+/// among them.
+pub fn schedule_stable<'a: 'c, 'b: 'c, 'c>(
+    configuration: &'a Configuration,
+    strategy: &'b str,
+) -> impl Future<Output = Result<StableSchedule<Task>>> + 'c {
+    let start = configuration.now() + Duration::minutes(1);
+
+    async move {
+        let strategy = configuration
+            .strategies
+            .get(strategy)
+            .ok_or_else(|| unknown_strategy(configuration, strategy))?;
+        let (occurrences_per_segment, events) =
+            occurrences_to_schedule(configuration, start).await?;
+        let placements = configuration
+            .database
+            .all_placements()
+            .await
+            .map_err(Error::Database)?;
+        let previous = previous_placements(&occurrences_per_segment, placements);
+        Schedule::schedule_stable(start, occurrences_per_segment, events, strategy, &previous)
+            .map(|stable| stable.map_tasks(|occurrence| occurrence.task))
+            .map_err(|error| Error::Schedule(error.map_task(|occurrence| occurrence.task)))
+    }
+}
+
+/// Returns what the best-effort schedule of `strategy` has you work on right now, if anything:
+/// the (chunk of a) task that starts as soon as the schedule does.
+pub fn current_task<'a: 'c, 'b: 'c, 'c>(
+    configuration: &'a Configuration,
+    strategy: &'b str,
+) -> impl Future<Output = Result<Option<Scheduled<Task>>>> + 'c {
+    async move {
+        let BestEffortSchedule { schedule, .. } =
+            schedule_best_effort(configuration, strategy).await?;
+        // The schedule starts a minute from when it was made
+        let start = configuration.now() + Duration::minutes(1);
+        Ok(schedule
+            .tasks
+            .into_iter()
+            .next()
+            .filter(|scheduled| scheduled.when <= start))
+    }
+}
+
+/// Makes `schedule` the last accepted schedule, which `schedule_stable` sticks to.
+pub fn accept_schedule<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    schedule: &Schedule<Task>,
+) -> impl Future<Output = Result<()>> + 'b {
+    let placements = schedule
+        .tasks
+        .iter()
+        .map(|scheduled| Placement {
+            task_id: scheduled.task.id,
+            deadline: scheduled.task.deadline,
+            start: scheduled.when,
+            end: scheduled.when + scheduled.duration,
+        })
+        .collect();
     configuration
         .database
-        .all_tasks_per_time_segment()
+        .set_placements(placements)
         .map_err(Error::Database)
-        .and_then(move |tasks_per_segment| {
-            let schedule = Schedule::schedule(start, tasks_per_segment, strategy);
-            future::ready(schedule).map_err(Error::Schedule)
+}
+
+/// Finds the free time within `range` during `segment`: the time that no fixed event takes up and
+/// no task in the schedule of the default strategy does. Only the free ranges of at least
+/// `min_length` are given as slots. Time that has already passed isn't free.
+pub fn free_slots<'a: 'c, 'b: 'c, 'c>(
+    configuration: &'a Configuration,
+    range: Range<DateTime<Utc>>,
+    min_length: Duration,
+    segment: &'b time_segment::NamedTimeSegment,
+) -> impl Future<Output = Result<FreeSlots>> + 'c {
+    let start = max(range.start, configuration.now());
+    let range = start..max(start, range.end);
+
+    async move {
+        let BestEffortSchedule { schedule, .. } =
+            schedule_best_effort(configuration, &configuration.scheduling_strategy).await?;
+        let tasks = schedule
+            .tasks
+            .iter()
+            .map(|scheduled| scheduled.when..scheduled.when + scheduled.duration);
+        let events = schedule
+            .events
+            .iter()
+            .filter(|event| event.blocks(segment))
+            .map(|event| event.start..event.end);
+        Ok(scheduling::free_slots(
+            range,
+            min_length,
+            segment.clone(),
+            tasks.chain(events),
+        ))
+    }
+}
+
+fn unknown_strategy(configuration: &Configuration, name: &str) -> Error {
+    Error::UnknownStrategy {
+        name: name.to_owned(),
+        known: configuration.strategies.names().join(", "),
+    }
+}
+
+type OccurrencesPerSegment = Vec<(time_segment::NamedTimeSegment, Vec<recurrence::Occurrence>)>;
+
+/// Returns the occurrences of the unfinished tasks that aren't blocked per time segment, and the
+/// fixed events to plan around. The time that's already been tracked on a task is subtracted from
+/// the duration of its current occurrence. When estimates are corrected, the occurrences get the
+/// extra time that the finished tasks of their time segment needed.
+fn occurrences_to_schedule<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    start: DateTime<Utc>,
+) -> impl Future<Output = Result<(OccurrencesPerSegment, Vec<FixedEvent>)>> + 'b {
+    async move {
+        let tasks_per_segment = configuration
+            .database
+            .all_tasks_per_time_segment()
+            .await
+            .map_err(Error::Database)?;
+        let events = configuration
+            .database
+            .all_fixed_events()
+            .await
+            .map_err(Error::Database)?;
+        let entries = configuration
+            .database
+            .all_time_entries()
+            .await
+            .map_err(Error::Database)?;
+        let mut tracked = HashMap::new();
+        for entry in &entries {
+            *tracked.entry(entry.task_id).or_insert_with(Duration::zero) += entry.duration(start);
+        }
+        let stats = if configuration.correct_estimates {
+            Some(EstimateStats::new(
+                tasks_per_segment.iter().flat_map(|(_, tasks)| tasks),
+                &entries,
+            ))
+        } else {
+            None
+        };
+        let blocked = blocked_task_ids(
+            tasks_per_segment
+                .iter()
+                .flat_map(|(_, tasks)| tasks)
+                .filter(|task| !task.is_completed()),
+        );
+        // Recurring tasks are scheduled once for every occurrence within the horizon.
+        let horizon = start + Duration::weeks(recurrence::HORIZON_WEEKS);
+        let occurrences_per_segment = tasks_per_segment
+            .into_iter()
+            .map(|(segment, tasks)| {
+                let occurrences = tasks
+                    .into_iter()
+                    .filter(|task| !task.is_completed() && !blocked.contains(&task.id))
+                    .flat_map(|task| {
+                        let tracked = tracked
+                            .get(&task.id)
+                            .cloned()
+                            .unwrap_or_else(Duration::zero);
+                        recurrence::expand(task, tracked, horizon)
+                    })
+                    .map(|mut occurrence| {
+                        let factor = stats
+                            .as_ref()
+                            .and_then(|stats| stats.factor(occurrence.task.time_segment_id));
+                        if let Some(factor) = factor {
+                            occurrence.correction =
+                                estimates::extra_time(occurrence.task.duration, factor);
+                        }
+                        occurrence
+                    })
+                    .collect::<Vec<_>>();
+                (segment, occurrences)
+            })
+            .collect();
+        Ok((occurrences_per_segment, events))
+    }
+}
+
+/// Returns the ids of the blocked tasks among `tasks`, together with the ids of the tasks that wait
+/// for them, directly or through other tasks.
+fn blocked_task_ids<'a>(tasks: impl Iterator<Item = &'a Task>) -> HashSet<u32> {
+    let tasks = tasks.collect_vec();
+    let mut blocked: HashSet<u32> = tasks
+        .iter()
+        .filter(|task| task.blocked.is_some())
+        .map(|task| task.id)
+        .collect();
+    loop {
+        let waiting = tasks
+            .iter()
+            .filter(|task| {
+                !blocked.contains(&task.id) && task.depends_on.iter().any(|id| blocked.contains(id))
+            })
+            .map(|task| task.id)
+            .collect_vec();
+        if waiting.is_empty() {
+            return blocked;
+        }
+        blocked.extend(waiting);
+    }
+}
+
+/// Looks up when the chunks of every occurrence are scheduled in the last accepted schedule. The
+/// occurrences of a recurring task are told apart by their deadlines, but a task that doesn't
+/// recur keeps its placement when its deadline changes.
+fn previous_placements(
+    occurrences_per_segment: &OccurrencesPerSegment,
+    placements: Vec<Placement>,
+) -> scheduling::Placements<recurrence::Occurrence> {
+    let placements_per_task = placements
+        .into_iter()
+        .map(|placement| (placement.task_id, placement))
+        .into_group_map();
+    occurrences_per_segment
+        .iter()
+        .flat_map(|(_, occurrences)| occurrences)
+        .filter_map(|occurrence| {
+            let chunks = placements_per_task
+                .get(&occurrence.task.id)?
+                .iter()
+                .filter(|placement| {
+                    occurrence.task.recurrence.is_none()
+                        || placement.deadline == occurrence.task.deadline
+                })
+                .map(|placement| placement.start..placement.end)
+                .collect_vec();
+            (!chunks.is_empty()).then(|| (occurrence.clone(), chunks))
         })
+        .collect()
+}
+
+pub fn add_fixed_event<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    new_event: NewFixedEvent,
+) -> impl Future<Output = Result<FixedEvent>> + 'b {
+    configuration
+        .database
+        .add_fixed_event(new_event)
+        .map_err(Error::Database)
+}
+
+/// Takes a break of `length` from now on. It's added as a fixed event, so no tasks are scheduled
+/// during it. Delete it with `delete_fixed_event` once the break is over, so that it doesn't stay
+/// around with the other events.
+pub fn take_break<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    length: Duration,
+) -> impl Future<Output = Result<FixedEvent>> + 'b {
+    let start = configuration.now();
+    add_fixed_event(
+        configuration,
+        NewFixedEvent {
+            title: "Break".to_owned(),
+            start,
+            end: start + length,
+            time_segment_id: None,
+            ical_uid: None,
+        },
+    )
+}
+
+pub fn delete_fixed_event<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    id: u32,
+) -> impl Future<Output = Result<()>> + 'b {
+    configuration
+        .database
+        .delete_fixed_event(id)
+        .map_err(Error::Database)
+}
+
+pub fn update_fixed_event<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    event: FixedEvent,
+) -> impl Future<Output = Result<()>> + 'b {
+    configuration
+        .database
+        .update_fixed_event(event)
+        .map_err(Error::Database)
+}
+
+pub fn fixed_events<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+) -> impl Future<Output = Result<Vec<FixedEvent>>> + 'b {
+    configuration
+        .database
+        .all_fixed_events()
+        .map_err(Error::Database)
 }
 
 pub fn add_time_segment<'a: 'b, 'b>(
@@ -167,3 +1067,359 @@ pub fn time_segments<'a: 'b, 'b>(
         .all_time_segments()
         .map_err(Error::Database)
 }
+
+/// Imports the events of an iCalendar file as fixed events and its to-dos as tasks. Times that
+/// aren't in UTC are taken to be in `timezone`.
+///
+/// Events and tasks that were imported before are recognised by their UID and updated instead,
+/// so importing the same file twice doesn't change anything the second time. Events without a UID
+/// are recognised by their title and start instead, and to-dos without one by their summary and
+/// due date.
+pub fn import_calendar<'a: 'b, 'b, Tz: TimeZone>(
+    configuration: &'a Configuration,
+    reader: impl std::io::BufRead,
+    timezone: &Tz,
+) -> impl Future<Output = Result<ImportReport>> + 'b {
+    let calendar = icalendar::parse_calendar(reader, timezone);
+    async move {
+        let calendar = calendar.map_err(Error::Import)?;
+        merge_calendar(configuration, calendar).await
+    }
+}
+
+/// What an imported event or to-do is matched with the existing ones on: its UID, or what it's
+/// called and when it is if it has none.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ImportKey {
+    Uid(String),
+    Derived(String, DateTime<Utc>),
+}
+
+impl ImportKey {
+    fn new(ical_uid: &Option<String>, summary: &str, when: DateTime<Utc>) -> ImportKey {
+        match ical_uid {
+            Some(uid) => ImportKey::Uid(uid.clone()),
+            None => ImportKey::Derived(summary.to_owned(), when),
+        }
+    }
+}
+
+/// Adds the events and to-dos of the calendar, or updates them if they were imported before.
+fn merge_calendar<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    calendar: icalendar::Calendar,
+) -> impl Future<Output = Result<ImportReport>> + 'b {
+    async move {
+        let mut report = ImportReport {
+            skipped: calendar.skipped,
+            ..ImportReport::default()
+        };
+        let up_to_date = |summary: &str| Skipped {
+            summary: summary.to_owned(),
+            reason: "it's already up to date".to_owned(),
+        };
+
+        let mut events: HashMap<ImportKey, FixedEvent> = configuration
+            .database
+            .all_fixed_events()
+            .await
+            .map_err(Error::Database)?
+            .into_iter()
+            .map(|event| {
+                (
+                    ImportKey::new(&event.ical_uid, &event.title, event.start),
+                    event,
+                )
+            })
+            .collect();
+        for new_event in calendar.events {
+            let key = ImportKey::new(&new_event.ical_uid, &new_event.title, new_event.start);
+            let event = match events.get(&key) {
+                Some(event)
+                    if event.title == new_event.title
+                        && event.start == new_event.start
+                        && event.end == new_event.end =>
+                {
+                    report.skipped.push(up_to_date(&event.title));
+                    continue;
+                }
+                Some(event) => {
+                    let event = FixedEvent {
+                        title: new_event.title,
+                        start: new_event.start,
+                        end: new_event.end,
+                        ..event.clone()
+                    };
+                    configuration
+                        .database
+                        .update_fixed_event(event.clone())
+                        .await
+                        .map_err(Error::Database)?;
+                    report.updated.push(Imported::Event(event.clone()));
+                    event
+                }
+                None => {
+                    let event = configuration
+                        .database
+                        .add_fixed_event(new_event)
+                        .await
+                        .map_err(Error::Database)?;
+                    report.created.push(Imported::Event(event.clone()));
+                    event
+                }
+            };
+            events.insert(key, event);
+        }
+
+        let mut tasks: HashMap<ImportKey, Task> = configuration
+            .database
+            .all_tasks()
+            .await
+            .map_err(Error::Database)?
+            .into_iter()
+            .map(|task| {
+                (
+                    ImportKey::new(&task.ical_uid, &task.content, task.deadline),
+                    task,
+                )
+            })
+            .collect();
+        for new_task in calendar.todos {
+            let key = ImportKey::new(&new_task.ical_uid, &new_task.content, new_task.deadline);
+            let task = match tasks.get(&key) {
+                Some(task) if task.is_completed() => {
+                    report.skipped.push(Skipped {
+                        summary: task.content.clone(),
+                        reason: "you already finished it".to_owned(),
+                    });
+                    continue;
+                }
+                Some(task)
+                    if task.content == new_task.content
+                        && task.deadline == new_task.deadline
+                        && task.duration == new_task.duration
+                        && task.importance == new_task.importance
+                        && task.recurrence == new_task.recurrence =>
+                {
+                    report.skipped.push(up_to_date(&task.content));
+                    continue;
+                }
+                Some(task) => {
+                    // Keep what can only be set in Eva, like the time segment
+                    let task = Task {
+                        content: new_task.content,
+                        deadline: new_task.deadline,
+                        duration: new_task.duration,
+                        importance: new_task.importance,
+                        recurrence: new_task.recurrence,
+                        ..task.clone()
+                    };
+                    configuration
+                        .database
+                        .update_task(task.clone())
+                        .await
+                        .map_err(Error::Database)?;
+                    report.updated.push(Imported::Task(task.clone()));
+                    task
+                }
+                None => {
+                    let task = configuration
+                        .database
+                        .add_task(new_task)
+                        .await
+                        .map_err(Error::Database)?;
+                    report.created.push(Imported::Task(task.clone()));
+                    task
+                }
+            };
+            tasks.insert(key, task);
+        }
+
+        Ok(report)
+    }
+}
+
+/// Reads the events in a CalDAV calendar collection as fixed events, except for the ones Eva put
+/// there itself. Only the calendar objects whose ETag changed since the last time are downloaded,
+/// and events that were removed from the collection are deleted again.
+#[cfg(feature = "caldav")]
+pub fn pull_caldav<'a: 'b, 'b, Tz: TimeZone>(
+    configuration: &'a Configuration,
+    client: &'b caldav::Client,
+    timezone: &'b Tz,
+) -> impl Future<Output = Result<ImportReport>> + 'b {
+    async move {
+        let resources: HashMap<String, CalDavResource> = configuration
+            .database
+            .all_caldav_resources()
+            .await
+            .map_err(Error::Database)?
+            .into_iter()
+            .map(|resource| (resource.href.clone(), resource))
+            .collect();
+        let etags = client.etags().map_err(Error::CalDav)?;
+
+        let mut calendar = icalendar::Calendar::default();
+        let mut pulled_resources = Vec::new();
+        // The UIDs of the events that may have been removed
+        let mut stale_uids = HashSet::new();
+        for (href, etag) in &etags {
+            let resource = resources.get(href);
+            if resource.is_some_and(|resource| resource.pushed || &resource.etag == etag) {
+                continue;
+            }
+            let ics = client.get(href).map_err(Error::CalDav)?;
+            let mut object =
+                icalendar::parse_calendar(ics.as_bytes(), timezone).map_err(Error::Import)?;
+            // Schedules pushed by another Eva aren't busy time either
+            object.events.retain(|event| {
+                !event
+                    .ical_uid
+                    .as_ref()
+                    .is_some_and(|uid| uid.starts_with(icalendar::TASK_UID_PREFIX))
+            });
+            if let Some(resource) = resource {
+                stale_uids.insert(resource.ical_uid.clone());
+            }
+            pulled_resources.push(CalDavResource {
+                href: href.clone(),
+                etag: etag.clone(),
+                ical_uid: object
+                    .events
+                    .first()
+                    .and_then(|event| event.ical_uid.clone())
+                    .unwrap_or_default(),
+                pushed: false,
+            });
+            calendar.events.extend(object.events);
+            calendar.skipped.extend(object.skipped);
+        }
+        for resource in resources.values() {
+            if !resource.pushed && !etags.contains_key(&resource.href) {
+                stale_uids.insert(resource.ical_uid.clone());
+                configuration
+                    .database
+                    .delete_caldav_resource(&resource.href)
+                    .await
+                    .map_err(Error::Database)?;
+            }
+        }
+        for event in &calendar.events {
+            if let Some(uid) = &event.ical_uid {
+                stale_uids.remove(uid);
+            }
+        }
+
+        let mut report = merge_calendar(configuration, calendar).await?;
+        let events = configuration
+            .database
+            .all_fixed_events()
+            .await
+            .map_err(Error::Database)?;
+        for event in events {
+            if event
+                .ical_uid
+                .as_ref()
+                .is_some_and(|uid| stale_uids.contains(uid))
+            {
+                configuration
+                    .database
+                    .delete_fixed_event(event.id)
+                    .await
+                    .map_err(Error::Database)?;
+                report.deleted.push(Imported::Event(event));
+            }
+        }
+        for resource in pulled_resources {
+            configuration
+                .database
+                .set_caldav_resource(resource)
+                .await
+                .map_err(Error::Database)?;
+        }
+        Ok(report)
+    }
+}
+
+/// Puts every scheduled task in a CalDAV calendar collection as an event, replacing the schedule
+/// that was put there before. Events that were changed on the server in the meantime are left
+/// alone and reported as conflicts.
+#[cfg(feature = "caldav")]
+pub fn push_caldav<'a: 'b, 'b>(
+    configuration: &'a Configuration,
+    client: &'b caldav::Client,
+    schedule: &'b Schedule<Task>,
+) -> impl Future<Output = Result<caldav::PushReport>> + 'b {
+    async move {
+        let pushed: HashMap<String, CalDavResource> = configuration
+            .database
+            .all_caldav_resources()
+            .await
+            .map_err(Error::Database)?
+            .into_iter()
+            .filter(|resource| resource.pushed)
+            .map(|resource| (resource.href.clone(), resource))
+            .collect();
+        let etags = client.etags().map_err(Error::CalDav)?;
+
+        let mut report = caldav::PushReport::default();
+        let mut uids = HashMap::new();
+        for (uid, ics) in icalendar::export_events(schedule, configuration.now()) {
+            let href = client.href_for(&uid);
+            let result = match (pushed.get(&href), etags.get(&href)) {
+                (Some(resource), Some(etag)) if &resource.etag == etag => client
+                    .put(&href, &ics, Some(etag))
+                    .map(|()| &mut report.updated),
+                // Someone else changed it, or it wasn't put there by Eva
+                (_, Some(_)) => Err(caldav::Error::Changed(href.clone())),
+                (_, None) => client.put(&href, &ics, None).map(|()| &mut report.created),
+            };
+            match result {
+                Ok(hrefs) => hrefs.push(href.clone()),
+                Err(caldav::Error::Changed(_)) => report.conflicts.push(href.clone()),
+                Err(error) => return Err(Error::CalDav(error)),
+            }
+            uids.insert(href, uid);
+        }
+        let mut forgotten = Vec::new();
+        for resource in pushed.values() {
+            if uids.contains_key(&resource.href) {
+                continue;
+            }
+            match etags.get(&resource.href) {
+                Some(etag) if etag == &resource.etag => match client.delete(&resource.href, etag) {
+                    Ok(()) => report.deleted.push(resource.href.clone()),
+                    Err(caldav::Error::Changed(href)) => report.conflicts.push(href),
+                    Err(error) => return Err(Error::CalDav(error)),
+                },
+                Some(_) => report.conflicts.push(resource.href.clone()),
+                None => forgotten.push(resource.href.clone()),
+            }
+        }
+
+        // Remember what the events look like now, to recognise changes the next time
+        let etags = client.etags().map_err(Error::CalDav)?;
+        for href in report.created.iter().chain(&report.updated) {
+            if let Some(etag) = etags.get(href) {
+                configuration
+                    .database
+                    .set_caldav_resource(CalDavResource {
+                        href: href.clone(),
+                        etag: etag.clone(),
+                        ical_uid: uids[href].clone(),
+                        pushed: true,
+                    })
+                    .await
+                    .map_err(Error::Database)?;
+            }
+        }
+        for href in report.deleted.iter().chain(&forgotten) {
+            configuration
+                .database
+                .delete_caldav_resource(href)
+                .await
+                .map_err(Error::Database)?;
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,117 @@
+use std::cmp::min;
+
+use chrono::Duration;
+
+/// How long the work intervals and breaks of a focus session are, following the Pomodoro
+/// technique: after every few short breaks, you take a long one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pomodoro {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    /// After how many work intervals the break is a long one. With 0, it never is.
+    pub long_break_every: u32,
+}
+
+impl Default for Pomodoro {
+    fn default() -> Self {
+        Pomodoro {
+            work: Duration::minutes(25),
+            short_break: Duration::minutes(5),
+            long_break: Duration::minutes(15),
+            long_break_every: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    Work(Duration),
+    Break(Duration),
+}
+
+impl Pomodoro {
+    /// Splits `work` into work intervals with breaks in between. The last work interval is
+    /// shorter when `work` doesn't divide evenly, and no break follows it.
+    pub fn intervals(&self, mut work: Duration) -> Vec<Interval> {
+        let mut intervals = Vec::new();
+        let mut done = 0;
+        while work > Duration::zero() {
+            if done > 0 {
+                let long = self.long_break_every > 0 && done % self.long_break_every == 0;
+                let length = if long {
+                    self.long_break
+                } else {
+                    self.short_break
+                };
+                intervals.push(Interval::Break(length));
+            }
+            let length = if self.work > Duration::zero() {
+                min(self.work, work)
+            } else {
+                work
+            };
+            intervals.push(Interval::Work(length));
+            work -= length;
+            done += 1;
+        }
+        intervals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Interval::*;
+
+    #[test]
+    fn intervals_alternate_work_and_breaks() {
+        let pomodoro = Pomodoro::default();
+        assert_eq!(
+            pomodoro.intervals(Duration::minutes(60)),
+            vec![
+                Work(Duration::minutes(25)),
+                Break(Duration::minutes(5)),
+                Work(Duration::minutes(25)),
+                Break(Duration::minutes(5)),
+                Work(Duration::minutes(10)),
+            ]
+        );
+        assert_eq!(
+            pomodoro.intervals(Duration::minutes(20)),
+            vec![Work(Duration::minutes(20))]
+        );
+        assert_eq!(pomodoro.intervals(Duration::zero()), vec![]);
+    }
+
+    #[test]
+    fn intervals_take_a_long_break_every_few_work_intervals() {
+        let pomodoro = Pomodoro {
+            work: Duration::minutes(10),
+            short_break: Duration::minutes(2),
+            long_break: Duration::minutes(8),
+            long_break_every: 2,
+        };
+        assert_eq!(
+            pomodoro.intervals(Duration::minutes(50)),
+            vec![
+                Work(Duration::minutes(10)),
+                Break(Duration::minutes(2)),
+                Work(Duration::minutes(10)),
+                Break(Duration::minutes(8)),
+                Work(Duration::minutes(10)),
+                Break(Duration::minutes(2)),
+                Work(Duration::minutes(10)),
+                Break(Duration::minutes(8)),
+                Work(Duration::minutes(10)),
+            ]
+        );
+        let never = Pomodoro {
+            long_break_every: 0,
+            ..pomodoro
+        };
+        assert!(!never
+            .intervals(Duration::minutes(50))
+            .contains(&Break(Duration::minutes(8))));
+    }
+}
@@ -0,0 +1,533 @@
+use std::cmp::max;
+use std::fmt;
+use std::iter;
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use failure::Fail;
+use itertools::Itertools;
+
+/// How far ahead the occurrences of recurring tasks are scheduled.
+pub(crate) const HORIZON_WEEKS: i64 = 4;
+
+/// A single occurrence of a (possibly recurring) task, as it is handed to the scheduler.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Occurrence {
+    pub task: crate::Task,
+    /// The deadline of the previous occurrence. This occurrence can't be started before it.
+    pub not_before: Option<DateTime<Utc>>,
+    /// The time that has already been spent on this occurrence. Only the current occurrence can
+    /// have any.
+    pub tracked: Duration,
+    /// The time that's planned for on top of the estimate, because tasks like this one usually
+    /// take longer than estimated.
+    pub correction: Duration,
+}
+
+impl fmt::Display for Occurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.task)
+    }
+}
+
+/// Expands `task` into all its occurrences that are due before `horizon`. The current occurrence
+/// is always included, with `tracked` as the time that was spent on it.
+pub(crate) fn expand(
+    task: crate::Task,
+    tracked: Duration,
+    horizon: DateTime<Utc>,
+) -> Vec<Occurrence> {
+    let deadlines = match task.recurrence {
+        None => vec![task.deadline],
+        Some(ref recurrence) => recurrence
+            .occurrences(task.deadline)
+            .enumerate()
+            .take_while(|(i, deadline)| *i == 0 || *deadline <= horizon)
+            .map(|(_, deadline)| deadline)
+            .collect(),
+    };
+    iter::once(None)
+        .chain(deadlines.iter().cloned().map(Some))
+        .zip(&deadlines)
+        .map(|(not_before, deadline)| Occurrence {
+            task: crate::Task {
+                deadline: *deadline,
+                ..task.clone()
+            },
+            not_before,
+            tracked: if not_before.is_none() {
+                tracked
+            } else {
+                Duration::zero()
+            },
+            correction: Duration::zero(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "I don't understand the recurrence rule {}: {}",
+    rule, reason
+)]
+pub struct ParseError {
+    pub rule: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// A subset of the recurrence rules of iCalendar (RFC 5545), e.g.
+/// `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=10`.
+///
+/// The deadline of a task is the first occurrence of its recurrence rule. Like in iCalendar, it
+/// always counts as an occurrence, even if it doesn't match `by_day`. All calculations are done
+/// in UTC.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    /// The weekdays to recur on. Only supported for daily and weekly recurrences.
+    pub by_day: Vec<Weekday>,
+    /// The number of occurrences that are left, including the current one.
+    pub count: Option<u32>,
+    /// The last moment an occurrence can be due.
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Recurrence {
+    /// Generates the deadlines of all occurrences, starting with `first`.
+    pub fn occurrences(&self, first: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        let interval = i64::from(max(self.interval, 1));
+        let candidates: Box<dyn Iterator<Item = DateTime<Utc>>> = match self.frequency {
+            Frequency::Daily => {
+                let by_day = &self.by_day;
+                // With a multiple of seven days as interval, the weekday never changes.
+                if interval % 7 == 0 && !by_day.is_empty() && !by_day.contains(&first.weekday()) {
+                    Box::new(iter::empty())
+                } else {
+                    Box::new(
+                        (1..)
+                            .map(move |i| first + Duration::days(i * interval))
+                            .filter(move |candidate| {
+                                by_day.is_empty() || by_day.contains(&candidate.weekday())
+                            }),
+                    )
+                }
+            }
+            Frequency::Weekly if self.by_day.is_empty() => {
+                Box::new((1..).map(move |i| first + Duration::weeks(i * interval)))
+            }
+            Frequency::Weekly => {
+                let offsets = self
+                    .by_day
+                    .iter()
+                    .map(|weekday| i64::from(weekday.num_days_from_monday()))
+                    .sorted()
+                    .dedup()
+                    .collect_vec();
+                let week_start =
+                    first - Duration::days(i64::from(first.weekday().num_days_from_monday()));
+                Box::new((0..).flat_map(move |i| {
+                    let week_start = week_start + Duration::weeks(i * interval);
+                    offsets
+                        .clone()
+                        .into_iter()
+                        .map(move |offset| week_start + Duration::days(offset))
+                        .filter(move |candidate| *candidate > first)
+                }))
+            }
+            Frequency::Monthly => Box::new((1..).filter_map(move |i| {
+                let months = i64::from(first.month0()) + i * interval;
+                let year = first.year() + (months / 12) as i32;
+                let month = (months % 12) as u32 + 1;
+                // Months without this day are skipped, like in iCalendar.
+                NaiveDate::from_ymd_opt(year, month, first.day())
+                    .map(|date| Utc.from_utc_datetime(&date.and_time(first.time())))
+            })),
+        };
+        let until = self.until;
+        iter::once(first)
+            .chain(candidates)
+            .take_while(move |occurrence| until.is_none_or(|until| *occurrence <= until))
+            .take(self.count.map_or(usize::MAX, |count| count as usize))
+    }
+
+    /// Returns the recurrence rule for the occurrences following the current one, or None if
+    /// this is the last occurrence.
+    pub fn advance(&self) -> Option<Recurrence> {
+        match self.count {
+            Some(count) if count <= 1 => None,
+            count => Some(Recurrence {
+                count: count.map(|count| count - 1),
+                ..self.clone()
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days = self.by_day.iter().map(|weekday| weekday_code(*weekday));
+            write!(f, ";BYDAY={}", days.format(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = ParseError;
+
+    fn from_str(rule: &str) -> Result<Recurrence, ParseError> {
+        let error = |reason: &str| ParseError {
+            rule: rule.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = vec![];
+        let mut count = None;
+        let mut until = None;
+        for part in rule.trim().trim_start_matches("RRULE:").split(';') {
+            let (key, value) = part
+                .splitn(2, '=')
+                .collect_tuple()
+                .ok_or_else(|| error("Every part should look like KEY=VALUE"))?;
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(error("FREQ should be DAILY, WEEKLY or MONTHLY")),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| error("INTERVAL should be a positive integer"))?
+                }
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(|code| {
+                            parse_weekday_code(code)
+                                .ok_or_else(|| error("BYDAY should be a list like MO,WE,FR"))
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or_else(|| error("COUNT should be a positive integer"))?,
+                    )
+                }
+                "UNTIL" => {
+                    until = Some(parse_until(value).ok_or_else(|| {
+                        error("UNTIL should be a date like 20200101 or 20200101T120000Z")
+                    })?)
+                }
+                _ => return Err(error(&format!("{} is not supported", key))),
+            }
+        }
+
+        let frequency = frequency.ok_or_else(|| error("FREQ is missing"))?;
+        if frequency == Frequency::Monthly && !by_day.is_empty() {
+            return Err(error("BYDAY is not supported for monthly recurrences"));
+        }
+        if count.is_some() && until.is_some() {
+            return Err(error("COUNT and UNTIL can't be combined"));
+        }
+        Ok(Recurrence {
+            frequency,
+            interval,
+            by_day,
+            count,
+            until,
+        })
+    }
+}
+
+fn weekday_code(weekday: Weekday) -> String {
+    weekday.to_string()[..2].to_uppercase()
+}
+
+fn parse_weekday_code(code: &str) -> Option<Weekday> {
+    [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ]
+    .iter()
+    .cloned()
+    .find(|weekday| weekday_code(*weekday) == code.trim().to_uppercase())
+}
+
+fn parse_until(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(23, 59, 59))
+        })
+        .map(|datetime| Utc.from_utc_datetime(&datetime))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(s: &str) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap())
+    }
+
+    fn occurrences(rule: &str, first: &str, n: usize) -> Vec<DateTime<Utc>> {
+        let recurrence = rule.parse::<Recurrence>().unwrap();
+        recurrence.occurrences(datetime(first)).take(n).collect()
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for rule in &[
+            "FREQ=DAILY",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=10",
+            "FREQ=MONTHLY;UNTIL=20200101T120000Z",
+        ] {
+            assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), *rule);
+        }
+        assert_eq!(
+            "freq=weekly;byday=fr;until=20200131"
+                .parse::<Recurrence>()
+                .unwrap(),
+            Recurrence {
+                frequency: Frequency::Weekly,
+                interval: 1,
+                by_day: vec![Weekday::Fri],
+                count: None,
+                until: Some(datetime("2020-01-31 23:59") + Duration::seconds(59)),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for rule in &[
+            "",
+            "INTERVAL=2",
+            "FREQ=YEARLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=MONTHLY;BYDAY=MO",
+            "FREQ=DAILY;COUNT=2;UNTIL=20200101",
+            "FREQ=DAILY;BYSETPOS=1",
+        ] {
+            assert!(rule.parse::<Recurrence>().is_err(), "{} was accepted", rule);
+        }
+    }
+
+    #[test]
+    fn daily_occurrences() {
+        assert_eq!(
+            occurrences("FREQ=DAILY;INTERVAL=2", "2020-01-01 09:00", 3),
+            [
+                datetime("2020-01-01 09:00"),
+                datetime("2020-01-03 09:00"),
+                datetime("2020-01-05 09:00"),
+            ]
+        );
+        // 2020-01-03 is a Friday, so the weekend is skipped
+        assert_eq!(
+            occurrences("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR", "2020-01-03 17:00", 3),
+            [
+                datetime("2020-01-03 17:00"),
+                datetime("2020-01-06 17:00"),
+                datetime("2020-01-07 17:00"),
+            ]
+        );
+        // A weekday that can never be reached ends the recurrence
+        assert_eq!(
+            occurrences("FREQ=DAILY;INTERVAL=7;BYDAY=MO", "2020-01-03 17:00", 3),
+            [datetime("2020-01-03 17:00")]
+        );
+    }
+
+    #[test]
+    fn weekly_occurrences() {
+        assert_eq!(
+            occurrences("FREQ=WEEKLY", "2020-01-03 17:00", 2),
+            [datetime("2020-01-03 17:00"), datetime("2020-01-10 17:00")]
+        );
+        // Every other week on Monday and Friday, starting on a Wednesday
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;INTERVAL=2;BYDAY=FR,MO", "2020-01-01 12:00", 5),
+            [
+                datetime("2020-01-01 12:00"),
+                datetime("2020-01-03 12:00"),
+                datetime("2020-01-13 12:00"),
+                datetime("2020-01-17 12:00"),
+                datetime("2020-01-27 12:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_occurrences() {
+        // Months without a 31st are skipped
+        assert_eq!(
+            occurrences("FREQ=MONTHLY", "2020-01-31 12:00", 3),
+            [
+                datetime("2020-01-31 12:00"),
+                datetime("2020-03-31 12:00"),
+                datetime("2020-05-31 12:00"),
+            ]
+        );
+        assert_eq!(
+            occurrences("FREQ=MONTHLY;INTERVAL=5", "2020-11-15 12:00", 3),
+            [
+                datetime("2020-11-15 12:00"),
+                datetime("2021-04-15 12:00"),
+                datetime("2021-09-15 12:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_and_until_limit_occurrences() {
+        assert_eq!(
+            occurrences("FREQ=DAILY;COUNT=2", "2020-01-01 09:00", 5).len(),
+            2
+        );
+        assert_eq!(
+            occurrences("FREQ=DAILY;UNTIL=20200103T090000Z", "2020-01-01 09:00", 5).len(),
+            3
+        );
+
+        let recurrence = "FREQ=DAILY;COUNT=2".parse::<Recurrence>().unwrap();
+        let next = recurrence.advance().unwrap();
+        assert_eq!(next.count, Some(1));
+        assert_eq!(next.advance(), None);
+        let forever = "FREQ=DAILY".parse::<Recurrence>().unwrap();
+        assert_eq!(forever.advance(), Some(forever));
+    }
+
+    fn recurring_task(rule: &str, deadline: &str) -> crate::Task {
+        crate::Task {
+            id: 1,
+            content: "weekly report".to_string(),
+            deadline: datetime(deadline),
+            available_from: None,
+            duration: Duration::hours(1),
+            importance: 5,
+            time_segment_id: 0,
+            recurrence: Some(rule.parse().unwrap()),
+            min_chunk_duration: None,
+            depends_on: vec![],
+            completed_at: None,
+            ical_uid: None,
+            pin: None,
+            blocked: None,
+        }
+    }
+
+    #[test]
+    fn expand_within_horizon() {
+        let task = recurring_task("FREQ=WEEKLY", "2020-01-03 17:00");
+        let occurrences = expand(
+            task.clone(),
+            Duration::minutes(20),
+            datetime("2020-01-17 17:00"),
+        );
+        assert_eq!(
+            occurrences
+                .iter()
+                .map(|occurrence| (occurrence.task.deadline, occurrence.not_before))
+                .collect_vec(),
+            [
+                (datetime("2020-01-03 17:00"), None),
+                (
+                    datetime("2020-01-10 17:00"),
+                    Some(datetime("2020-01-03 17:00"))
+                ),
+                (
+                    datetime("2020-01-17 17:00"),
+                    Some(datetime("2020-01-10 17:00"))
+                ),
+            ]
+        );
+        // The time that was tracked only counts for the current occurrence
+        assert_eq!(
+            occurrences
+                .iter()
+                .map(|occurrence| occurrence.tracked)
+                .collect_vec(),
+            [Duration::minutes(20), Duration::zero(), Duration::zero()]
+        );
+
+        // The current occurrence is always included, even beyond the horizon
+        let occurrences = expand(task, Duration::zero(), datetime("2019-01-01 00:00"));
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].task.deadline, datetime("2020-01-03 17:00"));
+
+        // Tasks that don't recur are passed as they are
+        let mut task = recurring_task("FREQ=DAILY", "2020-01-03 17:00");
+        task.recurrence = None;
+        let occurrences = expand(task.clone(), Duration::zero(), datetime("2020-02-01 00:00"));
+        assert_eq!(
+            occurrences,
+            [Occurrence {
+                task,
+                not_before: None,
+                tracked: Duration::zero(),
+                correction: Duration::zero(),
+            }]
+        );
+    }
+
+    #[test]
+    fn next_occurrence() {
+        let task = recurring_task("FREQ=WEEKLY;BYDAY=MO,FR;COUNT=3", "2020-01-03 17:00");
+        let next = task.next_occurrence().unwrap();
+        assert_eq!(next.deadline, datetime("2020-01-06 17:00"));
+        assert_eq!(next.recurrence.as_ref().unwrap().count, Some(2));
+        let last = next.next_occurrence().unwrap();
+        assert_eq!(last.deadline, datetime("2020-01-10 17:00"));
+        assert_eq!(last.next_occurrence(), None);
+
+        let mut task = task;
+        task.recurrence = None;
+        assert_eq!(task.next_occurrence(), None);
+    }
+}
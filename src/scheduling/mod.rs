@@ -1,18 +1,29 @@
+use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Debug, Display};
 use std::hash::Hash;
+use std::mem;
+use std::ops::Range;
 use std::rc::Rc;
+use std::slice;
 
 use chrono::prelude::*;
 use chrono::Duration;
 use failure::Fail;
 use itertools::Itertools;
 
-use crate::configuration::SchedulingStrategy;
+use crate::recurrence::Occurrence;
 use crate::time_segment::TimeSegment;
+use crate::{FixedEvent, Pin};
 
-use self::schedule_tree::{Entry, ScheduleTree};
+use self::schedule_tree::ScheduleTree;
+pub use self::strategy::{
+    EarliestDeadlineFirstStrategy, ImportanceStrategy, OptimalStrategy, Planner, Strategy,
+    UrgencyStrategy, WeightedShortestJobFirstStrategy,
+};
 
-mod schedule_tree;
+pub(crate) mod schedule_tree;
+mod strategy;
 
 pub(crate) trait Task:
     Debug + Display + Send + Sync + PartialEq + Eq + Clone + Hash
@@ -20,19 +31,52 @@ pub(crate) trait Task:
     fn deadline(&self) -> DateTime<Utc>;
     fn duration(&self) -> Duration;
     fn importance(&self) -> u32;
+    /// The moment before which the task can't be started, if any.
+    fn available_from(&self) -> Option<DateTime<Utc>>;
+    /// Whether `other` has to be finished before this task can be started.
+    fn depends_on(&self, other: &Self) -> bool;
+    /// The shortest chunk this task can be split into, if it can be split at all.
+    fn min_chunk_duration(&self) -> Option<Duration>;
+    /// The exact moment the task has to start, if it's pinned to one.
+    fn pinned_at(&self) -> Option<DateTime<Utc>>;
 }
 
-impl Task for crate::Task {
+impl Task for Occurrence {
     fn deadline(&self) -> DateTime<Utc> {
-        self.deadline
+        self.task.deadline
     }
 
     fn duration(&self) -> Duration {
-        self.duration
+        self.task.duration + self.correction - self.tracked
     }
 
     fn importance(&self) -> u32 {
-        self.importance
+        self.task.importance
+    }
+
+    fn available_from(&self) -> Option<DateTime<Utc>> {
+        let available_from = max(self.not_before, self.task.available_from);
+        match self.task.pin {
+            Some(Pin::NotBefore(not_before)) => max(available_from, Some(not_before)),
+            _ => available_from,
+        }
+    }
+
+    fn depends_on(&self, other: &Occurrence) -> bool {
+        // Only the upcoming occurrence of a recurring task holds up the tasks that depend on it.
+        other.not_before.is_none() && self.task.depends_on.contains(&other.task.id)
+    }
+
+    fn min_chunk_duration(&self) -> Option<Duration> {
+        self.task.min_chunk_duration
+    }
+
+    fn pinned_at(&self) -> Option<DateTime<Utc>> {
+        match self.task.pin {
+            // Only the upcoming occurrence of a recurring task is pinned.
+            Some(Pin::At(at)) if self.not_before.is_none() => Some(at),
+            _ => None,
+        }
     }
 }
 
@@ -43,14 +87,39 @@ pub enum Error<TaskT: Debug + Display + Send + Sync + 'static> {
                    postpone this task or remove it if it's not longer relevant",
         task, tense
     )]
-    DeadlineMissed { task: TaskT, tense: &'static str },
+    DeadlineMissed {
+        task: Box<TaskT>,
+        tense: &'static str,
+    },
     #[fail(
         display = "I could not schedule {} because you don't have enough time to do \
                    everything.\nYou might want to decide not to do some things or relax their \
                    deadlines",
         task
     )]
-    NotEnoughTime { task: TaskT },
+    NotEnoughTime { task: Box<TaskT> },
+    #[fail(
+        display = "I could not schedule {} because you've already spent as long on it as you \
+                   estimated.\nYou might want to estimate how much longer it will take or mark it \
+                   as done",
+        task
+    )]
+    EstimateUsedUp { task: Box<TaskT> },
+    #[fail(
+        display = "I could not schedule {} because each of these tasks waits for the next \
+                   one.\nYou might want to remove one of these dependencies",
+        cycle
+    )]
+    DependencyCycle { cycle: Cycle<TaskT> },
+    #[fail(
+        display = "I could not schedule {} at the time it's pinned to, because {}.\nYou might \
+                   want to pin it to another time or unpin it",
+        task, conflict
+    )]
+    PinConflict {
+        task: Box<TaskT>,
+        conflict: Box<PinConflict<TaskT>>,
+    },
     #[fail(
         display = "An internal error occurred (This shouldn't happen.): {}",
         _0
@@ -58,10 +127,456 @@ pub enum Error<TaskT: Debug + Display + Send + Sync + 'static> {
     Internal(&'static str),
 }
 
+impl<TaskT: Debug + Display + Send + Sync + 'static> Error<TaskT> {
+    pub(crate) fn map_task<U, F>(self, mut f: F) -> Error<U>
+    where
+        U: Debug + Display + Send + Sync + 'static,
+        F: FnMut(TaskT) -> U,
+    {
+        match self {
+            Error::DeadlineMissed { task, tense } => Error::DeadlineMissed {
+                task: Box::new(f(*task)),
+                tense,
+            },
+            Error::NotEnoughTime { task } => Error::NotEnoughTime {
+                task: Box::new(f(*task)),
+            },
+            Error::EstimateUsedUp { task } => Error::EstimateUsedUp {
+                task: Box::new(f(*task)),
+            },
+            Error::DependencyCycle { cycle } => Error::DependencyCycle {
+                cycle: Cycle(cycle.0.into_iter().map(f).collect()),
+            },
+            Error::PinConflict { task, conflict } => Error::PinConflict {
+                task: Box::new(f(*task)),
+                conflict: Box::new(conflict.map_task(f)),
+            },
+            Error::Internal(message) => Error::Internal(message),
+        }
+    }
+}
+
+/// What keeps a task from being scheduled at the time it's pinned to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PinConflict<TaskT> {
+    /// The time is outside of the task's time segment or taken by a fixed event.
+    Unavailable,
+    /// This other task is pinned to (part of) the same time.
+    Task(TaskT),
+    /// The task waits for this other task, which ends after the pinned time.
+    Prerequisite(TaskT),
+    /// The task wouldn't be done by its deadline.
+    Deadline,
+}
+
+impl<TaskT> PinConflict<TaskT> {
+    /// Returns the other task that's in the way, if it's a task.
+    pub fn task(&self) -> Option<&TaskT> {
+        match self {
+            PinConflict::Unavailable | PinConflict::Deadline => None,
+            PinConflict::Task(task) | PinConflict::Prerequisite(task) => Some(task),
+        }
+    }
+
+    fn map_task<U>(self, f: impl FnOnce(TaskT) -> U) -> PinConflict<U> {
+        match self {
+            PinConflict::Unavailable => PinConflict::Unavailable,
+            PinConflict::Task(task) => PinConflict::Task(f(task)),
+            PinConflict::Prerequisite(task) => PinConflict::Prerequisite(f(task)),
+            PinConflict::Deadline => PinConflict::Deadline,
+        }
+    }
+}
+
+impl<TaskT: Display> Display for PinConflict<TaskT> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PinConflict::Unavailable => write!(
+                f,
+                "that time is outside of its time segment or taken by an event"
+            ),
+            PinConflict::Task(task) => write!(f, "{} is pinned to the same time", task),
+            PinConflict::Prerequisite(task) => {
+                write!(f, "it waits for {}, which isn't done by then", task)
+            }
+            PinConflict::Deadline => write!(f, "it wouldn't be done by its deadline"),
+        }
+    }
+}
+
+/// Why a task couldn't be scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UnschedulableReason {
+    /// The deadline has already passed.
+    DeadlinePassed,
+    /// The deadline is so soon that the task can't be finished in time, even if you started
+    /// right away.
+    DeadlineTooSoon,
+    /// There isn't enough free time left before the deadline, with everything else that's
+    /// planned.
+    NotEnoughTime,
+    /// The task can't be done at the time it's pinned to, so it's scheduled like a task that
+    /// isn't pinned, if it fits.
+    PinConflict,
+    /// As much time as the task was estimated to take has been spent on it, but it isn't done.
+    EstimateUsedUp,
+}
+
+/// A task that didn't fit in a best-effort schedule.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Unschedulable<TaskT> {
+    pub task: TaskT,
+    pub reason: UnschedulableReason,
+    /// How much later the deadline would have to be for the task to fit in around the tasks
+    /// that were scheduled, if it would fit within a few weeks after the last deadline.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::optional_duration")
+    )]
+    pub deadline_extension: Option<Duration>,
+    /// What keeps the task from the time it's pinned to, when that's the reason.
+    pub pin_conflict: Option<PinConflict<TaskT>>,
+}
+
+impl<TaskT> Unschedulable<TaskT> {
+    pub(crate) fn map_task<U>(self, mut f: impl FnMut(TaskT) -> U) -> Unschedulable<U> {
+        Unschedulable {
+            task: f(self.task),
+            reason: self.reason,
+            deadline_extension: self.deadline_extension,
+            pin_conflict: self.pin_conflict.map(|conflict| conflict.map_task(f)),
+        }
+    }
+}
+
+impl<TaskT: Debug + Display + Send + Sync + 'static> From<Unschedulable<TaskT>> for Error<TaskT> {
+    fn from(unschedulable: Unschedulable<TaskT>) -> Error<TaskT> {
+        let task = Box::new(unschedulable.task);
+        match unschedulable.reason {
+            UnschedulableReason::DeadlinePassed => Error::DeadlineMissed {
+                task,
+                tense: "missed",
+            },
+            UnschedulableReason::DeadlineTooSoon => Error::DeadlineMissed {
+                task,
+                tense: "will miss",
+            },
+            UnschedulableReason::NotEnoughTime => Error::NotEnoughTime { task },
+            UnschedulableReason::EstimateUsedUp => Error::EstimateUsedUp { task },
+            UnschedulableReason::PinConflict => Error::PinConflict {
+                task,
+                conflict: Box::new(
+                    unschedulable
+                        .pin_conflict
+                        .unwrap_or(PinConflict::Unavailable),
+                ),
+            },
+        }
+    }
+}
+
+/// A schedule of the tasks that fit, next to the tasks that didn't and an explanation of how
+/// every scheduled task ended up where it is.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BestEffortSchedule<TaskT> {
+    pub schedule: Schedule<TaskT>,
+    pub unschedulable: Vec<Unschedulable<TaskT>>,
+    /// The explanations of the scheduled tasks, in the order in which the tasks start.
+    pub explanations: Vec<Explanation<TaskT>>,
+}
+
+impl<TaskT> BestEffortSchedule<TaskT> {
+    pub(crate) fn map_tasks<U>(self, mut f: impl FnMut(TaskT) -> U) -> BestEffortSchedule<U> {
+        BestEffortSchedule {
+            schedule: self.schedule.map_tasks(&mut f),
+            unschedulable: self
+                .unschedulable
+                .into_iter()
+                .map(|unschedulable| unschedulable.map_task(&mut f))
+                .collect(),
+            explanations: self
+                .explanations
+                .into_iter()
+                .map(|explanation| explanation.map_task(&mut f))
+                .collect(),
+        }
+    }
+}
+
+/// A best-effort schedule that sticks to the last accepted schedule, next to the tasks that moved.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StableSchedule<TaskT> {
+    pub best_effort: BestEffortSchedule<TaskT>,
+    /// The tasks that aren't where the last accepted schedule has them, in the order in which
+    /// they start now, followed by the ones that don't fit anymore.
+    pub moves: Vec<Move<TaskT>>,
+}
+
+impl<TaskT> StableSchedule<TaskT> {
+    pub(crate) fn map_tasks<U>(self, mut f: impl FnMut(TaskT) -> U) -> StableSchedule<U> {
+        StableSchedule {
+            best_effort: self.best_effort.map_tasks(&mut f),
+            moves: self
+                .moves
+                .into_iter()
+                .map(|moved| moved.map_task(&mut f))
+                .collect(),
+        }
+    }
+}
+
+/// A task that isn't scheduled where the last accepted schedule has it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Move<TaskT> {
+    pub task: TaskT,
+    /// When the chunks of the task were scheduled, in chronological order. It's empty when the
+    /// task wasn't scheduled at all.
+    pub from: Vec<Range<DateTime<Utc>>>,
+    /// When the chunks of the task are scheduled now. It's empty when the task doesn't fit
+    /// anymore.
+    pub to: Vec<Range<DateTime<Utc>>>,
+}
+
+impl<TaskT> Move<TaskT> {
+    fn map_task<U>(self, f: impl FnOnce(TaskT) -> U) -> Move<U> {
+        Move {
+            task: f(self.task),
+            from: self.from,
+            to: self.to,
+        }
+    }
+}
+
+/// When the chunks of tasks are scheduled, in chronological order.
+pub(crate) type Placements<TaskT> = HashMap<TaskT, Vec<Range<DateTime<Utc>>>>;
+
+/// Returns the tasks in `best_effort` that aren't where `previous` has them, in the order in which
+/// they start, followed by the ones that don't fit anymore.
+fn moves<TaskT: Task>(
+    previous: &Placements<TaskT>,
+    best_effort: &BestEffortSchedule<TaskT>,
+) -> Vec<Move<TaskT>> {
+    let mut placed: Vec<(&TaskT, Vec<Range<DateTime<Utc>>>)> = vec![];
+    let mut positions: HashMap<&TaskT, usize> = HashMap::new();
+    for scheduled in &best_effort.schedule.tasks {
+        let chunk = scheduled.when..scheduled.when + scheduled.duration;
+        match positions.get(&scheduled.task) {
+            Some(&i) => placed[i].1.push(chunk),
+            None => {
+                positions.insert(&scheduled.task, placed.len());
+                placed.push((&scheduled.task, vec![chunk]));
+            }
+        }
+    }
+    // Tasks that can't be done at the time they're pinned to are scheduled like the others, so
+    // they're among the placed tasks, or listed again for why they don't fit at all.
+    let unschedulable = best_effort
+        .unschedulable
+        .iter()
+        .filter(|unschedulable| unschedulable.reason != UnschedulableReason::PinConflict)
+        .map(|unschedulable| (&unschedulable.task, vec![]));
+    placed
+        .into_iter()
+        .chain(unschedulable)
+        .filter_map(|(task, to)| {
+            let from = previous.get(task).cloned().unwrap_or_default();
+            (from != to).then(|| Move {
+                task: task.clone(),
+                from,
+                to,
+            })
+        })
+        .collect()
+}
+
+/// Fails on the first task that didn't fit in a best-effort schedule, if any.
+fn all_or_nothing<TaskT: Debug + Display + Send + Sync + 'static>(
+    best_effort: BestEffortSchedule<TaskT>,
+) -> Result<Schedule<TaskT>, Error<TaskT>> {
+    match best_effort.unschedulable.into_iter().next() {
+        Some(unschedulable) => Err(unschedulable.into()),
+        None => Ok(best_effort.schedule),
+    }
+}
+
+/// The phases of the scheduling strategies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Phase {
+    /// The tasks are put as close to their deadlines as possible, least important first.
+    DeadlinePacking,
+    /// The tasks are moved towards the present to fill up the gaps.
+    PullForward,
+    /// The tasks are put as close to the present as possible, one after the other.
+    ForwardPlacement,
+    /// The tasks are kept where the last accepted schedule has them.
+    Kept,
+    /// The tasks are put at the time they're pinned to.
+    Pinned,
+}
+
+/// Something that kept a task from moving any further in a phase of scheduling.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Constraint<TaskT> {
+    /// The task ends right at its deadline.
+    Deadline,
+    /// The task starts right away.
+    Start,
+    /// The task starts as soon as it's available.
+    AvailableFrom,
+    /// The task starts right after this task it waits for has to end.
+    Prerequisite { task: TaskT },
+    /// The task ends right before this task that waits for it has to start.
+    Dependent { task: TaskT },
+    /// The task is right next to this other task.
+    Task { task: TaskT },
+    /// The task is right next to time it can't be scheduled in, because it's outside of its time
+    /// segment or there's a fixed event.
+    Unavailable {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+}
+
+impl<TaskT> Constraint<TaskT> {
+    fn map_task<U>(self, mut f: impl FnMut(TaskT) -> U) -> Constraint<U> {
+        match self {
+            Constraint::Deadline => Constraint::Deadline,
+            Constraint::Start => Constraint::Start,
+            Constraint::AvailableFrom => Constraint::AvailableFrom,
+            Constraint::Prerequisite { task } => Constraint::Prerequisite { task: f(task) },
+            Constraint::Dependent { task } => Constraint::Dependent { task: f(task) },
+            Constraint::Task { task } => Constraint::Task { task: f(task) },
+            Constraint::Unavailable { start, end } => Constraint::Unavailable { start, end },
+        }
+    }
+}
+
+/// Where a phase of scheduling put a task.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Step<TaskT> {
+    pub phase: Phase,
+    /// When the first chunk of the task starts.
+    pub start: DateTime<Utc>,
+    /// When the last chunk of the task ends.
+    pub end: DateTime<Utc>,
+    /// How much earlier the task starts than before this step. Deadline packing puts the task in
+    /// the schedule for the first time, so it doesn't move it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::duration")
+    )]
+    pub moved: Duration,
+    /// What kept the task from going any later when packing it before its deadline, or any
+    /// earlier when pulling it forward. If there's nothing, there was some free time next to the
+    /// task, but not enough to move it there.
+    pub constraints: Vec<Constraint<TaskT>>,
+}
+
+/// How a task ended up where it's scheduled.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Explanation<TaskT> {
+    pub task: TaskT,
+    /// The steps that put the task where it is, in the order in which they were taken.
+    pub steps: Vec<Step<TaskT>>,
+}
+
+impl<TaskT> Explanation<TaskT> {
+    pub(crate) fn map_task<U>(self, mut f: impl FnMut(TaskT) -> U) -> Explanation<U> {
+        Explanation {
+            task: f(self.task),
+            steps: self
+                .steps
+                .into_iter()
+                .map(|step| Step {
+                    phase: step.phase,
+                    start: step.start,
+                    end: step.end,
+                    moved: step.moved,
+                    constraints: step
+                        .constraints
+                        .into_iter()
+                        .map(|constraint| constraint.map_task(&mut f))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The steps the scheduling strategies took for every task, to explain them afterwards.
+struct Trace<TaskT> {
+    steps: HashMap<Rc<TaskT>, Vec<Step<TaskT>>>,
+}
+
+impl<TaskT: Task> Trace<TaskT> {
+    fn new() -> Trace<TaskT> {
+        Trace {
+            steps: HashMap::new(),
+        }
+    }
+
+    fn last_step(&self, task: &TaskT) -> Option<&Step<TaskT>> {
+        self.steps.get(task)?.last()
+    }
+
+    /// Returns the explanations of the tasks that are in `schedule`, in the order in which they
+    /// start.
+    fn into_explanations(mut self, schedule: &Schedule<TaskT>) -> Vec<Explanation<TaskT>> {
+        schedule
+            .tasks
+            .iter()
+            .filter_map(|scheduled| {
+                let (task, steps) = self.steps.remove_entry(&scheduled.task)?;
+                Some(Explanation {
+                    task: (*task).clone(),
+                    steps,
+                })
+            })
+            .collect()
+    }
+}
+
+/// How far after the last deadline to look for room for the tasks that don't fit, to tell how
+/// much later their deadlines would have to be.
+const EXTENSION_HORIZON_WEEKS: i64 = 4;
+
+/// Tasks that depend on each other in a circle: each task waits for the next one and the last
+/// one waits for the first.
+#[derive(Debug)]
+pub struct Cycle<TaskT>(pub Vec<TaskT>);
+
+impl<TaskT: Display> Display for Cycle<TaskT> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.iter().chain(self.0.first()).join(" → "))
+    }
+}
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Scheduled<T> {
     pub task: T,
     pub when: DateTime<Utc>,
+    /// How long the task is scheduled for at this time. This is only part of its duration when
+    /// the task was split up into chunks.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::duration")
+    )]
+    pub duration: Duration,
 }
 
 impl<TaskT: PartialEq> std::cmp::PartialOrd for Scheduled<TaskT> {
@@ -74,11 +589,19 @@ impl<TaskT: PartialEq> std::cmp::PartialOrd for Scheduled<TaskT> {
 }
 
 #[derive(Debug)]
-pub struct Schedule<TaskT>(pub Vec<Scheduled<TaskT>>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Schedule<TaskT> {
+    pub tasks: Vec<Scheduled<TaskT>>,
+    /// The fixed events that were planned around, in chronological order.
+    pub events: Vec<FixedEvent>,
+}
 
 impl<TaskT> Default for Schedule<TaskT> {
     fn default() -> Self {
-        Schedule(vec![])
+        Schedule {
+            tasks: vec![],
+            events: vec![],
+        }
     }
 }
 
@@ -89,57 +612,201 @@ impl<TaskT> Schedule<TaskT> {
     /// Args:
     ///     start: the moment when the first task can be scheduled
     ///     tasks: iterable of tasks to schedule
+    ///     events: fixed events which no task can overlap with
     ///     strategy: the scheduling algorithm to use
     ///     time_segment: the time segment to schedule the tasks within
     /// Returns when successful an instance of Schedule which contains all
-    /// tasks, each bound to a certain date and time; returns None when not all
-    /// tasks could be scheduled.
+    /// tasks, each bound to a certain date and time, and all events that
+    /// haven't finished yet; returns an error for the first task that
+    /// couldn't be scheduled.
     pub(crate) fn schedule(
         start: DateTime<Utc>,
         tasks_per_segment: impl IntoIterator<Item = (impl TimeSegment, impl IntoIterator<Item = TaskT>)>,
-        strategy: SchedulingStrategy,
+        events: Vec<FixedEvent>,
+        strategy: &dyn Strategy,
     ) -> Result<Schedule<TaskT>, Error<TaskT>>
     where
         TaskT: Task,
     {
-        tasks_per_segment
+        Schedule::schedule_best_effort(start, tasks_per_segment, events, strategy)
+            .and_then(all_or_nothing)
+    }
+
+    /// Like `schedule`, but instead of giving up when a task can't be scheduled, it schedules
+    /// all other tasks and returns the ones that didn't fit next to the schedule.
+    ///
+    /// Dependency cycles are still an error.
+    pub(crate) fn schedule_best_effort(
+        start: DateTime<Utc>,
+        tasks_per_segment: impl IntoIterator<Item = (impl TimeSegment, impl IntoIterator<Item = TaskT>)>,
+        events: Vec<FixedEvent>,
+        strategy: &dyn Strategy,
+    ) -> Result<BestEffortSchedule<TaskT>, Error<TaskT>>
+    where
+        TaskT: Task,
+    {
+        Schedule::schedule_keeping(start, tasks_per_segment, events, strategy, &HashMap::new())
+    }
+
+    /// Like `schedule_best_effort`, but it leaves the tasks where `previous`, the last accepted
+    /// schedule, has them as long as they still fit there. The strategy only plans the other
+    /// tasks around them, unless that leaves some of those without room: then the kept tasks
+    /// that might be in the way are planned anew as well. Next to the schedule, it returns the
+    /// tasks that moved.
+    pub(crate) fn schedule_stable(
+        start: DateTime<Utc>,
+        tasks_per_segment: impl IntoIterator<Item = (impl TimeSegment, impl IntoIterator<Item = TaskT>)>,
+        events: Vec<FixedEvent>,
+        strategy: &dyn Strategy,
+        previous: &Placements<TaskT>,
+    ) -> Result<StableSchedule<TaskT>, Error<TaskT>>
+    where
+        TaskT: Task,
+    {
+        let best_effort =
+            Schedule::schedule_keeping(start, tasks_per_segment, events, strategy, previous)?;
+        Ok(StableSchedule {
+            moves: moves(previous, &best_effort),
+            best_effort,
+        })
+    }
+
+    /// Schedules the tasks like `schedule_best_effort`, keeping the ones that still fit where
+    /// `previous` has them.
+    fn schedule_keeping(
+        start: DateTime<Utc>,
+        tasks_per_segment: impl IntoIterator<Item = (impl TimeSegment, impl IntoIterator<Item = TaskT>)>,
+        events: Vec<FixedEvent>,
+        strategy: &dyn Strategy,
+        previous: &Placements<TaskT>,
+    ) -> Result<BestEffortSchedule<TaskT>, Error<TaskT>>
+    where
+        TaskT: Task,
+    {
+        // Tasks that took as long as planned, but aren't done yet, can't be scheduled anymore.
+        let mut unschedulable = vec![];
+        let tasks_per_segment = tasks_per_segment
             .into_iter()
             .map(|(segment, tasks)| {
-                Schedule::schedule_within_segment(start, tasks, segment, strategy)
+                let (tasks, used_up): (Vec<_>, Vec<_>) = tasks
+                    .into_iter()
+                    .partition(|task| task.duration() > Duration::zero());
+                unschedulable.extend(used_up.into_iter().map(|task| Unschedulable {
+                    task,
+                    reason: UnschedulableReason::EstimateUsedUp,
+                    deadline_extension: None,
+                    pin_conflict: None,
+                }));
+                (segment, tasks)
             })
-            .fold(
-                Ok(Schedule::default()),
-                |acc_schedule, new_schedule| match (acc_schedule, new_schedule) {
-                    (Err(error), _) => Err(error),
-                    (_, Err(error)) => Err(error),
-                    (Ok(acc_schedule), Ok(new_schedule)) => Ok(Schedule(
-                        itertools::merge(acc_schedule.0, new_schedule.0).collect_vec(),
-                    )),
-                },
-            )
+            .collect_vec();
+        let mut dependencies =
+            Dependencies::new(start, tasks_per_segment.iter().flat_map(|(_, tasks)| tasks))?;
+        let order = dependencies.segment_order(
+            &tasks_per_segment
+                .iter()
+                .map(|(_, tasks)| tasks.as_slice())
+                .collect_vec(),
+        );
+        let mut schedule = Schedule::default();
+        let mut explanations = vec![];
+        for (_, (segment, tasks)) in tasks_per_segment
+            .into_iter()
+            .enumerate()
+            .sorted_by_key(|(i, _)| order.iter().position(|j| j == i))
+        {
+            let busy = events
+                .iter()
+                .filter(|event| event.blocks(&segment))
+                .map(|event| event.start..event.end)
+                .collect_vec();
+            let segment_schedule = Schedule::schedule_within_segment(
+                start,
+                tasks,
+                segment,
+                &busy,
+                &dependencies,
+                strategy,
+                previous,
+            )?;
+            dependencies.place(&segment_schedule.schedule.tasks);
+            schedule.tasks =
+                itertools::merge(schedule.tasks, segment_schedule.schedule.tasks).collect_vec();
+            unschedulable.extend(segment_schedule.unschedulable);
+            explanations.extend(segment_schedule.explanations);
+        }
+        explanations.sort_by_key(|explanation: &Explanation<TaskT>| {
+            explanation.steps.last().map(|step| step.start)
+        });
+        let schedule = Schedule {
+            events: events
+                .into_iter()
+                .filter(|event| event.end > start)
+                .sorted_by_key(|event| event.start)
+                .collect(),
+            ..schedule
+        };
+        Ok(BestEffortSchedule {
+            schedule,
+            unschedulable,
+            explanations,
+        })
     }
 
+    /// Schedules the tasks of a single time segment, keeping them clear of both the time outside
+    /// of the segment and the given `busy` ranges, and keeping them in the order imposed by
+    /// `dependencies`. The tasks that still fit where `previous` has them are kept there. The
+    /// tasks that don't fit are returned next to the schedule, and so are the explanations of the
+    /// ones that do.
+    ///
+    /// The returned schedule doesn't contain any events.
     fn schedule_within_segment(
         start: DateTime<Utc>,
         tasks: impl IntoIterator<Item = TaskT>,
         segment: impl TimeSegment,
-        strategy: SchedulingStrategy,
-    ) -> Result<Schedule<TaskT>, Error<TaskT>>
+        busy: &[Range<DateTime<Utc>>],
+        dependencies: &Dependencies<TaskT>,
+        strategy: &dyn Strategy,
+        previous: &Placements<TaskT>,
+    ) -> Result<BestEffortSchedule<TaskT>, Error<TaskT>>
     where
         TaskT: Task,
     {
         let tasks: Vec<Rc<TaskT>> = tasks.into_iter().map(Rc::new).collect();
         if tasks.is_empty() {
-            Ok(Schedule::default())
+            Ok(BestEffortSchedule {
+                schedule: Schedule::default(),
+                unschedulable: vec![],
+                explanations: vec![],
+            })
         } else {
             let mut tree: ScheduleTree<DateTime<Utc>, Item<TaskT>> = ScheduleTree::new();
             // Make sure things aren't scheduled before the algorithm is finished.
             let last_deadline = tasks
                 .iter()
-                .map(|task| task.deadline())
+                .map(|task| match task.pinned_at() {
+                    Some(at) => max(task.deadline(), at + task.duration()),
+                    None => task.deadline(),
+                })
                 .max()
                 .ok_or(Error::Internal("last deadline not found"))?;
-            let unscheduleables = segment.inverse().generate_ranges(start, last_deadline);
+            // Block the time after the last deadline (or pinned task) too, to find out where the
+            // tasks that don't fit would go.
+            let horizon = last_deadline + Duration::weeks(EXTENSION_HORIZON_WEEKS);
+            let unscheduleables = merge_ranges(
+                segment
+                    .inverse()
+                    .generate_ranges(start, horizon)
+                    .into_iter()
+                    .chain(busy.iter().filter_map(|range| {
+                        let range = max(range.start, start)..min(range.end, horizon);
+                        if range.start < range.end {
+                            Some(range)
+                        } else {
+                            None
+                        }
+                    })),
+            );
             for unscheduleable in unscheduleables {
                 tree.schedule_exact(
                     unscheduleable.start,
@@ -147,13 +814,38 @@ impl<TaskT> Schedule<TaskT> {
                     Item::Nothing,
                 );
             }
-            match strategy {
-                SchedulingStrategy::Importance => {
-                    tree.schedule_according_to_importance(start, tasks)
-                }
-                SchedulingStrategy::Urgency => tree.schedule_according_to_myrjam(start, tasks),
-            }?;
-            Ok(Schedule::from_tree(tree))
+            let mut planner = SegmentPlanner {
+                tree,
+                start,
+                horizon,
+                tasks,
+                dependencies,
+                trace: Trace::new(),
+                unschedulable: vec![],
+                error: None,
+            };
+            planner.pin();
+            let kept = planner.keep(previous);
+            strategy.schedule(&mut planner);
+            if !kept.is_empty() {
+                planner.make_room(kept, strategy);
+            }
+            planner.finish()
+        }
+    }
+
+    pub(crate) fn map_tasks<U>(self, mut f: impl FnMut(TaskT) -> U) -> Schedule<U> {
+        Schedule {
+            tasks: self
+                .tasks
+                .into_iter()
+                .map(|scheduled| Scheduled {
+                    task: f(scheduled.task),
+                    when: scheduled.when,
+                    duration: scheduled.duration,
+                })
+                .collect(),
+            events: self.events,
         }
     }
 
@@ -165,26 +857,93 @@ impl<TaskT> Schedule<TaskT> {
             .into_iter()
             .filter_map(|entry| match entry.data {
                 Item::Nothing => None,
-                Item::Task(task) => Some(Scheduled {
+                Item::Task(task, _) => Some(Scheduled {
                     task: (*task).clone(),
                     when: entry.start,
+                    duration: entry.end - entry.start,
                 }),
             })
             .collect();
-        Schedule(scheduled_tasks)
+        Schedule {
+            tasks: scheduled_tasks,
+            events: vec![],
+        }
     }
 }
 
+/// The free time within some range: the time of a time segment that nothing is planned in.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FreeSlots {
+    /// The free ranges that are long enough, in chronological order.
+    pub slots: Vec<Range<DateTime<Utc>>>,
+    /// How much free time there is altogether, including the ranges that are too short.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::duration")
+    )]
+    pub total: Duration,
+}
+
+/// Finds the free time within `range`: the time during `segment` that none of the `busy` ranges
+/// take up. Only the free ranges of at least `min_length` become slots.
+pub(crate) fn free_slots(
+    range: Range<DateTime<Utc>>,
+    min_length: Duration,
+    segment: impl TimeSegment,
+    busy: impl IntoIterator<Item = Range<DateTime<Utc>>>,
+) -> FreeSlots {
+    let mut tree = ScheduleTree::new();
+    let unavailable = merge_ranges(
+        segment
+            .inverse()
+            .generate_ranges(range.start, range.end)
+            .into_iter()
+            .chain(busy),
+    );
+    for (i, unavailable) in unavailable.into_iter().enumerate() {
+        tree.schedule_exact(unavailable.start, unavailable.end - unavailable.start, i);
+    }
+    let mut slots = vec![];
+    let mut start = range.start;
+    while let Some(slot) = tree.first_free_range(start..range.end, min_length) {
+        start = slot.end;
+        slots.push(slot);
+    }
+    FreeSlots {
+        slots,
+        total: tree.free_time(range),
+    }
+}
+
+/// Merges possibly overlapping ranges into a chronological list of disjoint ranges.
+fn merge_ranges(
+    ranges: impl IntoIterator<Item = Range<DateTime<Utc>>>,
+) -> Vec<Range<DateTime<Utc>>> {
+    let mut merged: Vec<Range<DateTime<Utc>>> = vec![];
+    for range in ranges.into_iter().sorted_by_key(|range| range.start) {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = max(last.end, range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
 #[derive(Debug, Hash, Clone)]
 enum Item<TaskT> {
-    Task(Rc<TaskT>),
+    /// A task, or one chunk of it when it's split up. Chunks are numbered chronologically and a
+    /// task that isn't split up only has chunk 0.
+    Task(Rc<TaskT>, usize),
     Nothing,
 }
 
 impl<TaskT: PartialEq> PartialEq for Item<TaskT> {
     fn eq(&self, other: &Item<TaskT>) -> bool {
         match (self, other) {
-            (Self::Task(task), Self::Task(other)) => task.eq(other),
+            (Self::Task(task, chunk), Self::Task(other, other_chunk)) => {
+                task.eq(other) && chunk == other_chunk
+            }
             _ => false,
         }
     }
@@ -196,159 +955,904 @@ impl<TaskT: PartialEq> PartialEq for Item<TaskT> {
 // e.g. all Nothings to be un-unscheduleable.
 impl<TaskT: PartialEq> Eq for Item<TaskT> {}
 
-trait Scheduler<TaskT: Task> {
-    fn schedule_according_to_importance(
-        &mut self,
-        start: DateTime<Utc>,
-        tasks: Vec<Rc<TaskT>>,
-    ) -> Result<(), Error<TaskT>>;
-    fn schedule_according_to_myrjam(
+impl<TaskT: Task> ScheduleTree<DateTime<Utc>, Item<TaskT>> {
+    /// Schedules `task` as close as possible before `max_end`, but not before `min_start`. When
+    /// the task can be split up and there's no gap big enough for all of it, it's scheduled in
+    /// chunks instead.
+    ///
+    /// Returns whether the scheduling succeeded.
+    fn schedule_task_close_before(
         &mut self,
-        start: DateTime<Utc>,
-        tasks: Vec<Rc<TaskT>>,
-    ) -> Result<(), Error<TaskT>>;
-}
+        task: &Rc<TaskT>,
+        min_start: DateTime<Utc>,
+        max_end: DateTime<Utc>,
+    ) -> bool {
+        if max_end < min_start + task.duration() {
+            return false;
+        }
+        self.schedule_close_before(
+            max_end,
+            task.duration(),
+            Some(min_start),
+            Item::Task(Rc::clone(task), 0),
+        ) || task.min_chunk_duration().is_some_and(|min_chunk| {
+            self.schedule_chunks_close_before(max_end, task.duration(), min_chunk, min_start, |i| {
+                Item::Task(Rc::clone(task), i)
+            })
+        })
+    }
 
-impl<TaskT: Task> Scheduler<TaskT> for ScheduleTree<DateTime<Utc>, Item<TaskT>> {
-    /// Schedules `tasks` according to importance while making sure all deadlines are met.
+    /// Schedules `task` as close as possible after `min_start`, without letting it end after
+    /// `max_end`. A task that can be split up, is split up to fill the gaps.
     ///
-    /// First, all tasks --- starting with the least important until the most important --- are
-    /// scheduled as close as possible to their deadline. Next, all tasks --- starting with the
-    /// most important until the least important --- are put as close to the present as possible.
-    /// For ties on importance, more urgent tasks are scheduled later in the first phase and sooner
-    /// in the second phase.
+    /// Returns whether the scheduling succeeded.
+    fn schedule_task_close_after(
+        &mut self,
+        task: &Rc<TaskT>,
+        min_start: DateTime<Utc>,
+        max_end: DateTime<Utc>,
+    ) -> bool {
+        if max_end < min_start + task.duration() {
+            return false;
+        }
+        match task.min_chunk_duration() {
+            Some(min_chunk) => self.schedule_chunks_close_after(
+                min_start,
+                task.duration(),
+                min_chunk,
+                max_end,
+                |i| Item::Task(Rc::clone(task), i),
+            ),
+            None => self.schedule_close_after(
+                min_start,
+                task.duration(),
+                Some(max_end),
+                Item::Task(Rc::clone(task), 0),
+            ),
+        }
+    }
+
+    /// Moves `task` as close after `min_start` as possible, without letting it end any later. A
+    /// task that can be split up, is split up to fill the gaps.
     ///
-    /// This algorithm has a terrible performance at the moment and it doesn't work right when the
-    /// lengths of the tasks aren't about the same, but it will do for now.
-    fn schedule_according_to_importance(
+    /// Returns whether the task moved.
+    fn pull_forward(
+        &mut self,
+        task: &Rc<TaskT>,
+        min_start: DateTime<Utc>,
+    ) -> Result<bool, Error<TaskT>> {
+        let chunks = self.chunks_of(task);
+        let end = chunks
+            .last()
+            .ok_or(Error::Internal("I couldn't find a task to reschedule"))?
+            .end;
+        for i in 0..chunks.len() {
+            self.unschedule(&Item::Task(Rc::clone(task), i))
+                .ok_or(Error::Internal("I couldn't unschedule a task"))?;
+        }
+        if !self.schedule_task_close_after(task, min_start, end) {
+            // Dividing the task differently didn't work out, so put it back where it was.
+            for (i, chunk) in chunks.iter().enumerate() {
+                self.schedule_exact(
+                    chunk.start,
+                    chunk.end - chunk.start,
+                    Item::Task(Rc::clone(task), i),
+                );
+            }
+            return Ok(false);
+        }
+        Ok(self.chunks_of(task) != chunks)
+    }
+
+    /// Describes why `task` couldn't be scheduled before its deadline. To tell how much later the
+    /// deadline would have to be, the task is tried out as close after `min_start` as possible,
+    /// but before `horizon`.
+    fn unschedulable(
         &mut self,
         start: DateTime<Utc>,
-        mut tasks: Vec<Rc<TaskT>>,
-    ) -> Result<(), Error<TaskT>> {
-        // Start by scheduling the least important tasks closest to the deadline, and so on.
-        tasks.sort_by_key(|task| {
-            (
-                task.importance(),
-                start.signed_duration_since(task.deadline()),
-            )
+        horizon: DateTime<Utc>,
+        task: &Rc<TaskT>,
+        min_start: DateTime<Utc>,
+    ) -> Unschedulable<TaskT> {
+        let reason = if task.deadline() < start {
+            UnschedulableReason::DeadlinePassed
+        } else if task.deadline() < earliest_start(start, &**task) + task.duration() {
+            UnschedulableReason::DeadlineTooSoon
+        } else {
+            UnschedulableReason::NotEnoughTime
+        };
+        let fits = self.schedule_task_close_after(task, min_start, horizon);
+        let chunks = self.chunks_of(task);
+        self.unschedule_task(task);
+        let deadline_extension = chunks
+            .last()
+            .filter(|_| fits)
+            .map(|chunk| chunk.end - task.deadline())
+            .filter(|extension| *extension > Duration::zero());
+        Unschedulable {
+            task: (**task).clone(),
+            reason,
+            deadline_extension,
+            pin_conflict: None,
+        }
+    }
+
+    /// Records in `trace` where `task` is after `phase`, and what keeps it from going further.
+    fn record(
+        &self,
+        trace: &mut Trace<TaskT>,
+        phase: Phase,
+        start: DateTime<Utc>,
+        task: &Rc<TaskT>,
+        dependencies: &Dependencies<TaskT>,
+    ) {
+        let range = match dependencies.when_scheduled(task, self) {
+            Some(range) => range,
+            None => return,
+        };
+        let (moved, constraints) = match phase {
+            Phase::ForwardPlacement => (
+                Duration::zero(),
+                self.constraints_before(start, range.start, task, dependencies),
+            ),
+            Phase::DeadlinePacking => (
+                Duration::zero(),
+                self.constraints_after(range.end, task, dependencies),
+            ),
+            Phase::PullForward => (
+                trace
+                    .last_step(task)
+                    .map_or_else(Duration::zero, |step| step.start - range.start),
+                self.constraints_before(start, range.start, task, dependencies),
+            ),
+            Phase::Kept | Phase::Pinned => (Duration::zero(), vec![]),
+        };
+        trace.steps.entry(Rc::clone(task)).or_default().push(Step {
+            phase,
+            start: range.start,
+            end: range.end,
+            moved,
+            constraints,
         });
-        for task in &tasks {
-            if task.deadline() < start + task.duration() {
-                return Err(Error::DeadlineMissed {
-                    task: (**task).clone(),
-                    tense: if task.deadline() < start {
-                        "missed"
-                    } else {
-                        "will miss"
-                    },
-                });
+    }
+
+    /// Returns what keeps `task`, which ends at `end`, from ending any later.
+    fn constraints_after(
+        &self,
+        end: DateTime<Utc>,
+        task: &Rc<TaskT>,
+        dependencies: &Dependencies<TaskT>,
+    ) -> Vec<Constraint<TaskT>> {
+        let mut constraints = vec![];
+        if end == task.deadline() {
+            constraints.push(Constraint::Deadline);
+        }
+        let dependents = dependencies
+            .dependents_of(task)
+            .filter(|dependent| {
+                // Dependents that aren't scheduled yet hold up their room all the same.
+                let latest_start = match dependencies.when_scheduled(dependent, self) {
+                    Some(scheduled) => Some(scheduled.start),
+                    None => dependencies
+                        .latest_ends
+                        .get(*dependent)
+                        .map(|&latest_end| latest_end - dependent.duration()),
+                };
+                latest_start == Some(end)
+            })
+            .collect_vec();
+        constraints.extend(dependents.iter().map(|dependent| Constraint::Dependent {
+            task: (***dependent).clone(),
+        }));
+        constraints.extend(
+            self.entry_starting_at(end).and_then(|entry| {
+                self.constraint_of(entry.start..entry.end, entry.data, &dependents)
+            }),
+        );
+        constraints
+    }
+
+    /// Returns what keeps `task`, which starts at `at`, from starting any earlier, given that
+    /// nothing can start before `start`.
+    fn constraints_before(
+        &self,
+        start: DateTime<Utc>,
+        at: DateTime<Utc>,
+        task: &Rc<TaskT>,
+        dependencies: &Dependencies<TaskT>,
+    ) -> Vec<Constraint<TaskT>> {
+        let mut constraints = vec![];
+        if at == start {
+            constraints.push(Constraint::Start);
+        }
+        if task.available_from() == Some(at) {
+            constraints.push(Constraint::AvailableFrom);
+        }
+        let prerequisites = dependencies
+            .prerequisites_of(task)
+            .filter(|prerequisite| {
+                dependencies
+                    .when_scheduled(prerequisite, self)
+                    .is_some_and(|scheduled| scheduled.end == at)
+            })
+            .collect_vec();
+        constraints.extend(
+            prerequisites
+                .iter()
+                .map(|prerequisite| Constraint::Prerequisite {
+                    task: (***prerequisite).clone(),
+                }),
+        );
+        constraints.extend(self.entry_ending_at(at).and_then(|entry| {
+            self.constraint_of(entry.start..entry.end, entry.data, &prerequisites)
+        }));
+        constraints
+    }
+
+    /// Describes the neighbouring entry `data` at `range` as a constraint, unless it's one of the
+    /// `known` tasks.
+    fn constraint_of(
+        &self,
+        range: Range<DateTime<Utc>>,
+        data: &Item<TaskT>,
+        known: &[&Rc<TaskT>],
+    ) -> Option<Constraint<TaskT>> {
+        match data {
+            Item::Task(other, _) if known.contains(&other) => None,
+            Item::Task(other, _) => Some(Constraint::Task {
+                task: (**other).clone(),
+            }),
+            Item::Nothing => Some(Constraint::Unavailable {
+                start: range.start,
+                end: range.end,
+            }),
+        }
+    }
+
+    /// Schedules the chunks of `task` at exactly the given times, or nothing at all when one of
+    /// them doesn't fit.
+    ///
+    /// Returns whether the scheduling succeeded.
+    fn schedule_task_exact(&mut self, task: &Rc<TaskT>, chunks: &[Range<DateTime<Utc>>]) -> bool {
+        for (i, chunk) in chunks.iter().enumerate() {
+            let scheduled = self.schedule_exact(
+                chunk.start,
+                chunk.end - chunk.start,
+                Item::Task(Rc::clone(task), i),
+            );
+            if !scheduled {
+                self.unschedule_task(task);
+                return false;
             }
-            if !self.schedule_close_before(
-                task.deadline(),
-                task.duration(),
-                Some(start),
-                Item::Task(Rc::clone(task)),
-            ) {
-                return Err(Error::NotEnoughTime {
-                    task: (**task).clone(),
-                });
+        }
+        true
+    }
+
+    /// Takes all chunks of `task` out of the schedule.
+    fn unschedule_task(&mut self, task: &Rc<TaskT>) {
+        for i in 0..self.chunks_of(task).len() {
+            self.unschedule(&Item::Task(Rc::clone(task), i));
+        }
+    }
+
+    fn is_scheduled(&self, task: &Rc<TaskT>) -> bool {
+        self.scheduled_range(&Item::Task(Rc::clone(task), 0))
+            .is_some()
+    }
+
+    /// Returns when the chunks of `task` are scheduled, in chronological order.
+    fn chunks_of(&self, task: &Rc<TaskT>) -> Vec<Range<DateTime<Utc>>> {
+        (0..)
+            .map(|i| self.scheduled_range(&Item::Task(Rc::clone(task), i)))
+            .take_while(Option::is_some)
+            .flatten()
+            .collect()
+    }
+}
+
+/// The `Planner` for the tasks of a single time segment.
+struct SegmentPlanner<'a, TaskT: Task + 'static> {
+    tree: ScheduleTree<DateTime<Utc>, Item<TaskT>>,
+    start: DateTime<Utc>,
+    /// Until when to look for room for the tasks that don't fit.
+    horizon: DateTime<Utc>,
+    tasks: Vec<Rc<TaskT>>,
+    dependencies: &'a Dependencies<TaskT>,
+    trace: Trace<TaskT>,
+    unschedulable: Vec<Unschedulable<TaskT>>,
+    /// The first internal error that occurred. The strategies can't handle those anyway, so it's
+    /// only returned when they're done.
+    error: Option<Error<TaskT>>,
+}
+
+impl<'a, TaskT: Task + 'static> SegmentPlanner<'a, TaskT> {
+    /// Schedules the tasks that are pinned to a time that hasn't passed right there, and leaves
+    /// only the other tasks to the strategy. The pinned tasks that can't be done at their time are
+    /// recorded as unschedulable, and left to the strategy as well.
+    fn pin(&mut self) {
+        let mut pinned = vec![];
+        for task in mem::take(&mut self.tasks) {
+            match task.pinned_at() {
+                Some(at) if self.start <= at => pinned.push((task, at)),
+                _ => self.tasks.push(task),
             }
         }
-        // Next, shift the most important tasks towards today, and so on, filling up the gaps.
-        // Keep repeating that, until nothing changes anymore (i.e. all gaps are filled).
-        let mut changed = !self.is_empty();
-        while changed {
-            changed = false;
-            for task in tasks.iter().rev() {
-                let scheduled_entry = self
-                    .unschedule(&Item::Task(task.clone()))
-                    .ok_or_else(|| Error::Internal("I couldn't unschedule a task"))?;
-                if !self.schedule_close_after(
-                    start,
-                    task.duration(),
-                    Some(scheduled_entry.end),
-                    scheduled_entry.data,
-                ) {
-                    return Err(Error::Internal("I couldn't reschedule a task"));
-                }
-                let new_start =
-                    self.when_scheduled(&Item::Task(task.clone()))
-                        .ok_or_else(|| {
-                            Error::Internal("I couldn't find a task that was just scheduled")
-                        })?;
-                if scheduled_entry.start != *new_start {
-                    changed = true;
-                    break;
+        let mut placed = vec![];
+        for (task, at) in pinned {
+            let range = at..at + task.duration();
+            if self
+                .tree
+                .schedule_task_exact(&task, slice::from_ref(&range))
+            {
+                placed.push((task, at));
+            } else {
+                let conflict = self
+                    .tree
+                    .iter()
+                    .find(|entry| entry.start < range.end && range.start < entry.end)
+                    .map_or(PinConflict::Unavailable, |entry| match entry.data {
+                        Item::Task(other, _) => PinConflict::Task((**other).clone()),
+                        Item::Nothing => PinConflict::Unavailable,
+                    });
+                self.conflict(task, conflict);
+            }
+        }
+        for (task, at) in placed {
+            let late_prerequisite = self
+                .dependencies
+                .prerequisites_of(&task)
+                .find(|prerequisite| {
+                    self.dependencies
+                        .when_scheduled(prerequisite, &self.tree)
+                        .is_some_and(|scheduled| at < scheduled.end)
+                })
+                .cloned();
+            match late_prerequisite {
+                Some(prerequisite) => {
+                    self.tree.unschedule_task(&task);
+                    self.conflict(task, PinConflict::Prerequisite((*prerequisite).clone()));
                 }
+                None => self.tree.record(
+                    &mut self.trace,
+                    Phase::Pinned,
+                    self.start,
+                    &task,
+                    self.dependencies,
+                ),
+            }
+        }
+    }
+
+    /// Records that `task` can't be done at the time it's pinned to because of `conflict`, and
+    /// leaves it to the strategy.
+    fn conflict(&mut self, task: Rc<TaskT>, conflict: PinConflict<TaskT>) {
+        self.unschedulable.push(Unschedulable {
+            task: (*task).clone(),
+            reason: UnschedulableReason::PinConflict,
+            deadline_extension: None,
+            pin_conflict: Some(conflict),
+        });
+        self.tasks.push(task);
+    }
+
+    /// Schedules the tasks that still fit where `previous` has them right there, and leaves only
+    /// the other tasks to the strategy.
+    ///
+    /// Returns the tasks that were kept.
+    fn keep(&mut self, previous: &Placements<TaskT>) -> Vec<Rc<TaskT>> {
+        let mut kept = vec![];
+        for task in &self.tasks {
+            let fits = previous
+                .get(task)
+                .is_some_and(|chunks| still_fits(self.start, &**task, chunks))
+                && self.tree.schedule_task_exact(task, &previous[task]);
+            if fits {
+                kept.push(Rc::clone(task));
+            }
+        }
+        // Tasks that depend on each other might not be in the right order anymore.
+        while let Some(i) = kept.iter().position(|task| {
+            let chunks = self.tree.chunks_of(task);
+            chunks[0].start
+                < self
+                    .dependencies
+                    .earliest_start(self.start, task, &self.tree)
+                || self.dependencies.latest_end(task, &self.tree) < chunks[chunks.len() - 1].end
+        }) {
+            self.tree.unschedule_task(&kept.swap_remove(i));
+        }
+        let kept_set: HashSet<_> = kept.iter().cloned().collect();
+        self.tasks.retain(|task| !kept_set.contains(task));
+        for task in &kept {
+            self.tree.record(
+                &mut self.trace,
+                Phase::Kept,
+                self.start,
+                task,
+                self.dependencies,
+            );
+        }
+        kept
+    }
+
+    /// When the strategy couldn't find room for some tasks before their deadlines, lets it plan
+    /// them again together with the `kept` tasks that start before those deadlines, as those
+    /// might be in the way.
+    fn make_room(&mut self, kept: Vec<Rc<TaskT>>, strategy: &dyn Strategy) {
+        self.give_up_on_unscheduled();
+        let latest_deadline = self
+            .unschedulable
+            .iter()
+            .filter(|unschedulable| unschedulable.reason == UnschedulableReason::NotEnoughTime)
+            .map(|unschedulable| unschedulable.task.deadline())
+            .max();
+        let in_the_way = kept
+            .into_iter()
+            .filter(|task| {
+                let chunks = self.tree.chunks_of(task);
+                latest_deadline.is_some_and(|deadline| chunks[0].start < deadline)
+            })
+            .collect_vec();
+        if in_the_way.is_empty() {
+            return;
+        }
+        for task in self.tasks.iter().chain(&in_the_way) {
+            self.tree.unschedule_task(task);
+            self.trace.steps.remove(task);
+        }
+        self.unschedulable
+            .retain(|unschedulable| unschedulable.reason == UnschedulableReason::PinConflict);
+        self.tasks.extend(in_the_way);
+        strategy.schedule(self);
+    }
+
+    fn give_up_on_unscheduled(&mut self) {
+        for task in 0..self.tasks.len() {
+            if !self.tree.is_scheduled(&self.tasks[task]) {
+                self.give_up(task);
+            }
+        }
+    }
+
+    /// Gives up on the tasks the strategy didn't schedule, and returns the schedule with the
+    /// explanations of the tasks in it.
+    fn finish(mut self) -> Result<BestEffortSchedule<TaskT>, Error<TaskT>> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.give_up_on_unscheduled();
+        // Tell why the tasks that were packed before their deadline, but never pulled forward,
+        // couldn't be.
+        for task in &self.tasks {
+            let only_packed = self
+                .trace
+                .last_step(task)
+                .is_some_and(|step| step.phase == Phase::DeadlinePacking);
+            if only_packed {
+                self.tree.record(
+                    &mut self.trace,
+                    Phase::PullForward,
+                    self.start,
+                    task,
+                    self.dependencies,
+                );
+            }
+        }
+        let schedule = Schedule::from_tree(self.tree);
+        Ok(BestEffortSchedule {
+            explanations: self.trace.into_explanations(&schedule),
+            schedule,
+            unschedulable: self.unschedulable,
+        })
+    }
+}
+
+impl<'a, TaskT: Task + 'static> Planner for SegmentPlanner<'a, TaskT> {
+    fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    fn deadline(&self, task: usize) -> DateTime<Utc> {
+        self.tasks[task].deadline()
+    }
+
+    fn duration(&self, task: usize) -> Duration {
+        self.tasks[task].duration()
+    }
+
+    fn importance(&self, task: usize) -> u32 {
+        self.tasks[task].importance()
+    }
+
+    fn min_chunk_duration(&self, task: usize) -> Option<Duration> {
+        self.tasks[task].min_chunk_duration()
+    }
+
+    fn earliest_start(&self, task: usize) -> DateTime<Utc> {
+        self.dependencies
+            .earliest_start(self.start, &self.tasks[task], &self.tree)
+    }
+
+    fn latest_end(&self, task: usize) -> DateTime<Utc> {
+        self.dependencies.latest_end(&self.tasks[task], &self.tree)
+    }
+
+    fn scheduled(&self, task: usize) -> Option<Range<DateTime<Utc>>> {
+        let chunks = self.tree.chunks_of(&self.tasks[task]);
+        Some(chunks.first()?.start..chunks.last()?.end)
+    }
+
+    fn free_range_at(&self, at: DateTime<Utc>) -> Option<Range<DateTime<Utc>>> {
+        self.tree.free_range_at(at, self.start..self.horizon)
+    }
+
+    fn schedule_close_before(
+        &mut self,
+        task: usize,
+        min_start: DateTime<Utc>,
+        max_end: DateTime<Utc>,
+    ) -> bool {
+        let task = &self.tasks[task];
+        let scheduled = !self.tree.is_scheduled(task)
+            && self
+                .tree
+                .schedule_task_close_before(task, min_start, max_end);
+        if scheduled {
+            self.tree.record(
+                &mut self.trace,
+                Phase::DeadlinePacking,
+                self.start,
+                task,
+                self.dependencies,
+            );
+        }
+        scheduled
+    }
+
+    fn schedule_close_after(
+        &mut self,
+        task: usize,
+        min_start: DateTime<Utc>,
+        max_end: DateTime<Utc>,
+    ) -> bool {
+        let task = &self.tasks[task];
+        let scheduled = !self.tree.is_scheduled(task)
+            && self
+                .tree
+                .schedule_task_close_after(task, min_start, max_end);
+        if scheduled {
+            self.tree.record(
+                &mut self.trace,
+                Phase::ForwardPlacement,
+                self.start,
+                task,
+                self.dependencies,
+            );
+        }
+        scheduled
+    }
+
+    fn pull_forward(&mut self, task: usize, min_start: DateTime<Utc>) -> bool {
+        let task = &self.tasks[task];
+        if !self.tree.is_scheduled(task) {
+            return false;
+        }
+        match self.tree.pull_forward(task, min_start) {
+            Ok(true) => {
+                self.tree.record(
+                    &mut self.trace,
+                    Phase::PullForward,
+                    self.start,
+                    task,
+                    self.dependencies,
+                );
+                true
+            }
+            Ok(false) => false,
+            Err(error) => {
+                self.error.get_or_insert(error);
+                false
             }
         }
-        Ok(())
     }
 
-    /// Schedules `tasks` according to deadline first and then according to importance.
-    ///
-    /// First, all tasks --- starting with the least important until the most important --- are
-    /// scheduled as close as possible to their deadline. Next, all tasks are put as close to the
-    /// present as possible, keeping the order from the first scheduling phase.
+    fn unschedule(&mut self, task: usize) {
+        let task = &self.tasks[task];
+        self.tree.unschedule_task(task);
+        self.trace.steps.remove(task);
+    }
+
+    fn give_up(&mut self, task: usize) {
+        let task = &self.tasks[task];
+        if self.unschedulable.iter().any(|unschedulable| {
+            unschedulable.task == **task && unschedulable.reason != UnschedulableReason::PinConflict
+        }) {
+            return;
+        }
+        // The strategy might give up on a task it already scheduled.
+        self.tree.unschedule_task(task);
+        self.trace.steps.remove(task);
+        let min_start = self
+            .dependencies
+            .earliest_start(self.start, task, &self.tree);
+        let unschedulable = self
+            .tree
+            .unschedulable(self.start, self.horizon, task, min_start);
+        self.unschedulable.push(unschedulable);
+    }
+}
+
+/// Returns whether `task` could still be scheduled in `chunks`, leaving aside the other tasks,
+/// given that nothing can start before `start`.
+fn still_fits<TaskT: Task>(
+    start: DateTime<Utc>,
+    task: &TaskT,
+    chunks: &[Range<DateTime<Utc>>],
+) -> bool {
+    let (first, last) = match (chunks.first(), chunks.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return false,
+    };
+    let chunks_are_long_enough = match task.min_chunk_duration() {
+        _ if chunks.len() == 1 => true,
+        Some(min_chunk) => chunks
+            .iter()
+            .all(|chunk| chunk.end - chunk.start >= min_chunk),
+        None => false,
+    };
+    let duration = chunks.iter().fold(Duration::zero(), |duration, chunk| {
+        duration + (chunk.end - chunk.start)
+    });
+    chunks_are_long_enough
+        && duration == task.duration()
+        && earliest_start(start, task) <= first.start
+        && last.end <= task.deadline()
+}
+
+/// Returns the earliest moment `task` can start, given that nothing can start before `start`.
+fn earliest_start<TaskT: Task>(start: DateTime<Utc>, task: &TaskT) -> DateTime<Utc> {
+    task.available_from()
+        .map_or(start, |available_from| max(start, available_from))
+}
+
+/// Which tasks have to be finished before which other tasks can be started, and what that means
+/// for when each task can be scheduled.
+struct Dependencies<TaskT> {
+    prerequisites: HashMap<Rc<TaskT>, Vec<Rc<TaskT>>>,
+    dependents: HashMap<Rc<TaskT>, Vec<Rc<TaskT>>>,
+    /// The earliest moments the tasks can start, leaving time for their prerequisites.
+    earliest_starts: HashMap<Rc<TaskT>, DateTime<Utc>>,
+    /// The latest moments the tasks can end, leaving time for the tasks that depend on them.
+    latest_ends: HashMap<Rc<TaskT>, DateTime<Utc>>,
+    /// Where the tasks of the time segments that were already scheduled ended up.
+    placed: HashMap<Rc<TaskT>, Range<DateTime<Utc>>>,
+}
+
+impl<TaskT: Hash + Eq> Default for Dependencies<TaskT> {
+    fn default() -> Self {
+        Dependencies {
+            prerequisites: HashMap::new(),
+            dependents: HashMap::new(),
+            earliest_starts: HashMap::new(),
+            latest_ends: HashMap::new(),
+            placed: HashMap::new(),
+        }
+    }
+}
+
+impl<TaskT: Task> Dependencies<TaskT> {
+    /// Finds the dependencies between `tasks`, none of which can start before `start`.
     ///
-    /// This algorithm is how Myrjam Van de Vijver does her personal scheduling. A benefit of doing
-    /// it this way, is that it is highly robust against contingencies like falling sick. A
-    /// disadvantage is that it gives more priority to urgent but less important tasks than to
-    /// important but less urgent tasks.
-    fn schedule_according_to_myrjam(
-        &mut self,
+    /// Returns an error naming the tasks involved if some of them depend on each other in a
+    /// circle.
+    fn new<'a>(
         start: DateTime<Utc>,
-        mut tasks: Vec<Rc<TaskT>>,
-    ) -> Result<(), Error<TaskT>> {
-        // Start by scheduling the least important tasks closest to the deadline, and so on.
-        tasks.sort_by_key(|task| task.importance());
-        for task in tasks {
-            if task.deadline() < start + task.duration() {
-                return Err(Error::DeadlineMissed {
-                    task: (*task).clone(),
-                    tense: if task.deadline() < start {
-                        "missed"
-                    } else {
-                        "will miss"
-                    },
-                });
+        tasks: impl IntoIterator<Item = &'a TaskT>,
+    ) -> Result<Dependencies<TaskT>, Error<TaskT>>
+    where
+        TaskT: 'a,
+    {
+        let tasks = tasks
+            .into_iter()
+            .unique()
+            .cloned()
+            .map(Rc::new)
+            .collect_vec();
+        let mut dependencies = Dependencies::default();
+        for task in &tasks {
+            for other in &tasks {
+                if task.depends_on(other) {
+                    dependencies
+                        .prerequisites
+                        .entry(Rc::clone(task))
+                        .or_insert_with(Vec::new)
+                        .push(Rc::clone(other));
+                    dependencies
+                        .dependents
+                        .entry(Rc::clone(other))
+                        .or_insert_with(Vec::new)
+                        .push(Rc::clone(task));
+                }
             }
-            if !self.schedule_close_before(
-                task.deadline(),
-                task.duration(),
-                Some(start),
-                Item::Task(Rc::clone(&task)),
-            ) {
-                return Err(Error::NotEnoughTime {
-                    task: (*task).clone(),
-                });
+        }
+        let order = dependencies.topological_order(&tasks)?;
+        for task in &order {
+            let earliest_start = dependencies
+                .prerequisites_of(task)
+                .map(|prerequisite| {
+                    dependencies.earliest_starts[prerequisite] + prerequisite.duration()
+                })
+                .fold(earliest_start(start, &**task), max);
+            dependencies
+                .earliest_starts
+                .insert(Rc::clone(task), earliest_start);
+        }
+        for task in order.iter().rev() {
+            let latest_end = dependencies
+                .dependents_of(task)
+                .map(|dependent| dependencies.latest_ends[dependent] - dependent.duration())
+                .fold(task.deadline(), min);
+            dependencies.latest_ends.insert(Rc::clone(task), latest_end);
+        }
+        Ok(dependencies)
+    }
+
+    fn prerequisites_of(&self, task: &TaskT) -> impl Iterator<Item = &Rc<TaskT>> {
+        self.prerequisites.get(task).into_iter().flatten()
+    }
+
+    fn dependents_of(&self, task: &TaskT) -> impl Iterator<Item = &Rc<TaskT>> {
+        self.dependents.get(task).into_iter().flatten()
+    }
+
+    /// Orders `tasks` so that every task comes after its prerequisites.
+    fn topological_order(&self, tasks: &[Rc<TaskT>]) -> Result<Vec<Rc<TaskT>>, Error<TaskT>> {
+        let mut waiting_for: HashMap<Rc<TaskT>, usize> = tasks
+            .iter()
+            .map(|task| (Rc::clone(task), self.prerequisites_of(task).count()))
+            .collect();
+        let mut ready = tasks
+            .iter()
+            .filter(|task| waiting_for[*task] == 0)
+            .cloned()
+            .collect_vec();
+        let mut order = Vec::with_capacity(tasks.len());
+        while let Some(task) = ready.pop() {
+            for dependent in self.dependents_of(&task) {
+                let count = waiting_for
+                    .get_mut(dependent)
+                    .ok_or(Error::Internal("dependent task not found"))?;
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(Rc::clone(dependent));
+                }
             }
+            order.push(task);
+        }
+        if order.len() == tasks.len() {
+            return Ok(order);
         }
-        // Next, shift the all tasks towards the present, filling up the gaps.
-        let entries = self
+        // Every task that is still waiting, waits for another one that is still waiting. Follow
+        // them until we come across the same task twice.
+        let mut path: Vec<&Rc<TaskT>> = vec![];
+        let mut current = tasks
+            .iter()
+            .find(|task| waiting_for[*task] > 0)
+            .ok_or(Error::Internal("waiting task not found"))?;
+        while !path.contains(&current) {
+            path.push(current);
+            current = self
+                .prerequisites_of(current)
+                .find(|prerequisite| waiting_for[*prerequisite] > 0)
+                .ok_or(Error::Internal("waiting prerequisite not found"))?;
+        }
+        let cycle_start = path.iter().position(|task| *task == current).unwrap_or(0);
+        Err(Error::DependencyCycle {
+            cycle: Cycle(
+                path[cycle_start..]
+                    .iter()
+                    .map(|task| (***task).clone())
+                    .collect(),
+            ),
+        })
+    }
+
+    /// Returns the order in which to schedule time segments with the given tasks, such that the
+    /// segments holding prerequisites come before the ones holding the tasks that wait for them,
+    /// as far as that's possible.
+    fn segment_order(&self, tasks_per_segment: &[&[TaskT]]) -> Vec<usize> {
+        let segment_of: HashMap<&TaskT, usize> = tasks_per_segment
             .iter()
-            .map(|entry| Entry {
-                start: entry.start,
-                end: entry.end,
-                data: (*entry.data).clone(),
+            .enumerate()
+            .flat_map(|(i, tasks)| tasks.iter().map(move |task| (task, i)))
+            .collect();
+        let waits_for: Vec<HashSet<usize>> = tasks_per_segment
+            .iter()
+            .enumerate()
+            .map(|(i, tasks)| {
+                tasks
+                    .iter()
+                    .flat_map(|task| self.prerequisites_of(task))
+                    .filter_map(|prerequisite| segment_of.get(&**prerequisite).cloned())
+                    .filter(|&j| j != i)
+                    .collect()
             })
-            .collect::<Vec<_>>();
-        for entry in entries {
-            if let Item::Task(ref task) = entry.data {
-                let scheduled_entry = self
-                    .unschedule(&entry.data)
-                    .ok_or_else(|| Error::Internal("I couldn't unschedule a task"))?;
-                if !self.schedule_close_after(
-                    start,
-                    task.duration(),
-                    Some(scheduled_entry.end),
-                    scheduled_entry.data,
-                ) {
-                    return Err(Error::Internal("I couldn't reschedule a task"));
-                }
+            .collect();
+        let mut order = vec![];
+        loop {
+            let mut left = (0..tasks_per_segment.len()).filter(|i| !order.contains(i));
+            // If all segments that are left wait for each other, just take the first one.
+            let next = left
+                .clone()
+                .find(|&i| waits_for[i].iter().all(|j| order.contains(j)))
+                .or_else(|| left.next());
+            match next {
+                Some(next) => order.push(next),
+                None => return order,
             }
         }
-        Ok(())
+    }
+
+    /// Remembers where the tasks of a time segment ended up, so that the tasks of the other time
+    /// segments can be planned around them.
+    fn place(&mut self, scheduled_tasks: &[Scheduled<TaskT>]) {
+        for scheduled in scheduled_tasks {
+            let end = scheduled.when + scheduled.duration;
+            self.placed
+                .entry(Rc::new(scheduled.task.clone()))
+                .and_modify(|range| *range = min(range.start, scheduled.when)..max(range.end, end))
+                .or_insert(scheduled.when..end);
+        }
+    }
+
+    fn when_scheduled(
+        &self,
+        task: &Rc<TaskT>,
+        tree: &ScheduleTree<DateTime<Utc>, Item<TaskT>>,
+    ) -> Option<Range<DateTime<Utc>>> {
+        self.placed.get(task).cloned().or_else(|| {
+            let chunks = tree.chunks_of(task);
+            Some(chunks.first()?.start..chunks.last()?.end)
+        })
+    }
+
+    /// Returns the earliest moment `task` can start, given that nothing can start before `start`
+    /// and that its prerequisites have to be finished first, wherever they are in `tree`.
+    fn earliest_start(
+        &self,
+        start: DateTime<Utc>,
+        task: &Rc<TaskT>,
+        tree: &ScheduleTree<DateTime<Utc>, Item<TaskT>>,
+    ) -> DateTime<Utc> {
+        let earliest_start = self
+            .earliest_starts
+            .get(task)
+            .map_or_else(|| earliest_start(start, &**task), |&when| max(start, when));
+        self.prerequisites_of(task)
+            .filter_map(|prerequisite| self.when_scheduled(prerequisite, tree))
+            .map(|scheduled| scheduled.end)
+            .fold(earliest_start, max)
+    }
+
+    /// Returns the latest moment `task` can end, leaving time for the tasks that depend on it.
+    fn latest_end(
+        &self,
+        task: &Rc<TaskT>,
+        tree: &ScheduleTree<DateTime<Utc>, Item<TaskT>>,
+    ) -> DateTime<Utc> {
+        let latest_end = self
+            .latest_ends
+            .get(task)
+            .copied()
+            .unwrap_or_else(|| task.deadline());
+        self.dependents_of(task)
+            .filter_map(|dependent| self.when_scheduled(dependent, tree))
+            .map(|scheduled| scheduled.start)
+            .fold(latest_end, min)
     }
 }
 
@@ -364,7 +1868,8 @@ mod tests {
     use chrono::Duration;
 
     use super::*;
-    use crate::time_segment::UnnamedTimeSegment;
+    use crate::time_segment::{NamedTimeSegment, UnnamedTimeSegment};
+    use crate::util::Lcg;
 
     #[derive(Debug, PartialEq, Eq, Clone, Hash)]
     struct Task {
@@ -372,6 +1877,10 @@ mod tests {
         pub deadline: DateTime<Utc>,
         pub duration: Duration,
         pub importance: u32,
+        pub available_from: Option<DateTime<Utc>>,
+        pub depends_on: Vec<String>,
+        pub min_chunk_duration: Option<Duration>,
+        pub pinned_at: Option<DateTime<Utc>>,
     }
 
     impl super::Task for Task {
@@ -386,6 +1895,22 @@ mod tests {
         fn importance(&self) -> u32 {
             self.importance
         }
+
+        fn available_from(&self) -> Option<DateTime<Utc>> {
+            self.available_from
+        }
+
+        fn depends_on(&self, other: &Task) -> bool {
+            self.depends_on.contains(&other.content)
+        }
+
+        fn min_chunk_duration(&self) -> Option<Duration> {
+            self.min_chunk_duration
+        }
+
+        fn pinned_at(&self) -> Option<DateTime<Utc>> {
+            self.pinned_at
+        }
     }
 
     impl Display for Task {
@@ -394,8 +1919,45 @@ mod tests {
         }
     }
 
+    /// A task that can be done any time before `deadline`, doesn't depend on anything and can't be
+    /// split up. Change the other fields with struct update syntax, like
+    /// `Task { importance: 9, ..task("write", deadline, Duration::hours(2)) }`.
+    fn task(content: &str, deadline: DateTime<Utc>, duration: Duration) -> Task {
+        Task {
+            content: content.to_string(),
+            deadline,
+            duration,
+            importance: 5,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
+        }
+    }
+
     type Result<T> = std::result::Result<T, Error<Task>>;
 
+    /// Schedules the tasks of a single time segment, failing if any of them doesn't fit.
+    fn strictly_within_segment(
+        start: DateTime<Utc>,
+        tasks: Vec<Task>,
+        segment: impl TimeSegment,
+        busy: &[Range<DateTime<Utc>>],
+        dependencies: &Dependencies<Task>,
+        strategy: &dyn Strategy,
+    ) -> Result<Schedule<Task>> {
+        Schedule::schedule_within_segment(
+            start,
+            tasks,
+            segment,
+            busy,
+            dependencies,
+            strategy,
+            &HashMap::new(),
+        )
+        .and_then(all_or_nothing)
+    }
+
     fn anytime() -> impl TimeSegment {
         let start = Utc::now();
         let period = Duration::weeks(1);
@@ -423,7 +1985,8 @@ mod tests {
                     /// Schedules the given tasks in a time segment without
                     /// gaps.
                     fn schedule(tasks: Vec<Task>, start: DateTime<Utc>) -> Result<Schedule<Task>> {
-                        Schedule::schedule_within_segment(start, tasks, anytime(), $strategy)
+                        let dependencies = Dependencies::new(start, &tasks)?;
+                        strictly_within_segment(start, tasks, anytime(), &[], &dependencies, $strategy)
                     }
 
                     #[test]
@@ -431,12 +1994,12 @@ mod tests {
                         let start = Utc::now();
                         for tasks in vec![taskset_of_myrjam(), taskset_just_in_time(start)] {
                             let schedule = schedule(tasks.clone(), start).unwrap();
-                            assert_eq!(tasks.len(), schedule.0.len());
-                            for scheduled_task in schedule.0.iter() {
+                            assert_eq!(tasks.len(), schedule.tasks.len());
+                            for scheduled_task in schedule.tasks.iter() {
                                 assert!(tasks.contains(&scheduled_task.task));
                             }
                             for task in tasks {
-                                assert!(schedule.0.iter()
+                                assert!(schedule.tasks.iter()
                                         .any(|scheduled_task| scheduled_task.task == task));
                             }
                         }
@@ -447,7 +2010,7 @@ mod tests {
                         let start = Utc::now();
                         for tasks in vec![taskset_of_myrjam(), taskset_just_in_time(start)] {
                             let schedule = schedule(tasks, start).unwrap();
-                            for scheduled_task in schedule.0.iter() {
+                            for scheduled_task in schedule.tasks.iter() {
                                 assert!(scheduled_task.when <= scheduled_task.task.deadline);
                             }
                         }
@@ -458,56 +2021,17 @@ mod tests {
                         let start = Utc::now();
                         let tasks = taskset_just_in_time(start);
                         let schedule = schedule(tasks.clone(), start).unwrap();
-                        assert_eq!(schedule.0[0].task, tasks[0]);
-                        assert_eq!(schedule.0[1].task, tasks[1]);
-                        assert_eq!(schedule.0[0].when, start);
-                        assert_eq!(schedule.0[1].when, start + Duration::days(23 * 365));
-                    }
-
-                    #[test]
-                    fn schedule_sets_of_two() {
-                        let start = Utc::now();
-                        let mut tasks = vec![Task {
-                            content: "find meaning to life".to_string(),
-                            deadline: start + Duration::hours(1),
-                            duration: Duration::hours(1),
-                            importance: 6,
-                        },
-                        Task {
-                            content: "stop giving a fuck".to_string(),
-                            deadline: start + Duration::hours(3),
-                            duration: Duration::hours(2),
-                            importance: 5,
-                        }];
-                        // Normal scheduling
-                        {
-                            let schedule = schedule(tasks.clone(), start).unwrap();
-                            assert_eq!(schedule.0[0].task, tasks[0]);
-                            assert_eq!(schedule.0[1].task, tasks[1]);
-                        }
-
-                        // Reversing the importance should maintain the scheduled order, because it's the only way
-                        // to meet the deadlines.
-                        tasks[0].importance = 5;
-                        tasks[1].importance = 6;
-                        {
-                            let schedule = schedule(tasks.clone(), start).unwrap();
-                            assert_eq!(schedule.0[0].task, tasks[0]);
-                            assert_eq!(schedule.0[1].task, tasks[1]);
-                        }
-
-                        // Leveling the deadlines should make the more important task be scheduled first again.
-                        tasks[0].deadline = start + Duration::hours(3);
-                        let schedule = schedule(tasks.clone(), start).unwrap();
-                        assert_eq!(schedule.0[0].task, tasks[1]);
-                        assert_eq!(schedule.0[1].task, tasks[0]);
+                        assert_eq!(schedule.tasks[0].task, tasks[0]);
+                        assert_eq!(schedule.tasks[1].task, tasks[1]);
+                        assert_eq!(schedule.tasks[0].when, start);
+                        assert_eq!(schedule.tasks[1].when, start + Duration::days(23 * 365));
                     }
 
                     #[test]
                     fn no_schedule() {
                         let tasks = vec![];
                         let schedule = schedule(tasks, Utc::now()).unwrap();
-                        assert!(schedule.0.is_empty());
+                        assert!(schedule.tasks.is_empty());
                     }
 
                     #[test]
@@ -534,6 +2058,64 @@ mod tests {
                                         Err(Error::NotEnoughTime { .. }));
                     }
 
+                    #[test]
+                    fn best_effort_leaves_out_what_doesnt_fit() {
+                        let start = Utc::now();
+                        let segment = UnnamedTimeSegment {
+                            ranges: vec![start..start + Duration::weeks(1)],
+                            start,
+                            period: Duration::weeks(1),
+                        };
+                        let task = |content: &str, deadline, hours, importance| Task {
+                            importance,
+                            ..task(content, deadline, Duration::hours(hours))
+                        };
+                        let tasks = vec![
+                            task("write a novel", start + Duration::hours(2), 2, 5),
+                            task("read a novel", start + Duration::hours(2), 1, 3),
+                            task("buy a pen", start - Duration::hours(1), 1, 1),
+                        ];
+                        let BestEffortSchedule { schedule, unschedulable, .. } = Schedule::schedule_within_segment(
+                            start, tasks.clone(), segment, &[], &Dependencies::default(), $strategy, &HashMap::new()).unwrap();
+                        // Only one of the novels fits, and the pen can't be bought in the past.
+                        assert_eq!(schedule.tasks.len(), 1);
+                        assert_eq!(schedule.tasks[0].when, start);
+                        assert_eq!(unschedulable.len(), 2);
+                        for unschedulable in &unschedulable {
+                            assert_ne!(unschedulable.task, schedule.tasks[0].task);
+                            assert!(unschedulable.deadline_extension.is_some());
+                            let reason = if unschedulable.task == tasks[2] {
+                                UnschedulableReason::DeadlinePassed
+                            } else {
+                                UnschedulableReason::NotEnoughTime
+                            };
+                            assert_eq!(unschedulable.reason, reason);
+                        }
+                    }
+
+                    #[test]
+                    fn explains_every_scheduled_task() {
+                        let start = Utc::now();
+                        let segment = UnnamedTimeSegment {
+                            ranges: vec![start..start + Duration::weeks(1)],
+                            start,
+                            period: Duration::weeks(1),
+                        };
+                        let mut tasks = taskset_of_myrjam();
+                        tasks[3].depends_on = vec![tasks[1].content.clone()];
+                        let busy = [start..start + Duration::minutes(30)];
+                        let dependencies = Dependencies::new(start, &tasks).unwrap();
+                        let BestEffortSchedule { schedule, explanations, .. } = Schedule::schedule_within_segment(
+                            start, tasks, segment, &busy, &dependencies, $strategy, &HashMap::new()).unwrap();
+                        assert_eq!(explanations.len(), schedule.tasks.len());
+                        for (explanation, scheduled) in explanations.iter().zip(&schedule.tasks) {
+                            assert_eq!(explanation.task, scheduled.task);
+                            let last = explanation.steps.last().unwrap();
+                            assert_eq!(last.start, scheduled.when);
+                            assert_eq!(last.end, scheduled.when + scheduled.duration);
+                        }
+                    }
+
                     #[test]
                     fn schedules_within_the_time_segment() {
                         let now = Utc::now();
@@ -543,24 +2125,40 @@ mod tests {
                                 deadline: now + Duration::days(2),
                                 duration: Duration::minutes(20),
                                 importance: 4,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
                             },
                             Task {
                                 content: "important-quick".to_string(),
                                 deadline: now + Duration::days(2),
                                 duration: Duration::minutes(20),
                                 importance: 9,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
                             },
                             Task {
                                 content: "urgent-long".to_string(),
                                 deadline: now + Duration::days(4),
                                 duration: Duration::hours(2),
                                 importance: 4,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
                             },
                             Task {
                                 content: "important-long".to_string(),
                                 deadline: now + Duration::days(4),
                                 duration: Duration::hours(2),
                                 importance: 9,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
                             },
                         ];
                         let segment = UnnamedTimeSegment {
@@ -568,8 +2166,8 @@ mod tests {
                             start: now,
                             period: Duration::days(1),
                         };
-                        let schedule = Schedule::schedule_within_segment(now, tasks, segment, $strategy);
-                        assert_matches!(schedule, Ok(Schedule(scheduled_tasks)) => {
+                        let schedule = strictly_within_segment(now, tasks, segment, &[], &Dependencies::default(), $strategy);
+                        assert_matches!(schedule, Ok(Schedule { tasks: scheduled_tasks, .. }) => {
                             for scheduled_task in scheduled_tasks {
                                 let start = scheduled_task.when;
                                 let end = scheduled_task.when + scheduled_task.task.duration;
@@ -594,62 +2192,1317 @@ mod tests {
                             start: now,
                             period: Duration::days(1),
                         };
-
-                        // Trying to schedule tasks longer than two hours fails
+
+                        // Trying to schedule tasks longer than two hours fails
+                        let tasks = vec![
+                            Task {
+                                content: "too-long".to_string(),
+                                deadline: now + Duration::days(4),
+                                duration: Duration::hours(2) + Duration::seconds(1),
+                                importance: 10,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
+                            },
+                        ];
+                        let schedule = strictly_within_segment(now, tasks, segment.clone(), &[], &Dependencies::default(), $strategy);
+                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
+
+                        // Trying to schedule more tasks than possible to fit in
+                        // to the segment, fails as well
+                        let tasks = vec![
+                            Task {
+                                content: "task1".to_string(),
+                                deadline: now + Duration::hours(36) - Duration::seconds(1),
+                                duration: Duration::hours(1),
+                                importance: 5,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
+                            },
+                            Task {
+                                content: "task2".to_string(),
+                                deadline: now + Duration::hours(36) - Duration::seconds(1),
+                                duration: Duration::hours(1),
+                                importance: 5,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
+                            },
+                            Task {
+                                content: "task3".to_string(),
+                                deadline: now + Duration::hours(36) - Duration::seconds(1),
+                                duration: Duration::hours(2),
+                                importance: 5,
+                                available_from: None,
+                                depends_on: vec![],
+                                min_chunk_duration: None,
+                                pinned_at: None,
+                            },
+                        ];
+                        let schedule = strictly_within_segment(now, tasks, segment, &[], &Dependencies::default(), $strategy);
+                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
+                    }
+
+                    #[test]
+                    fn plans_around_busy_time() {
+                        let now = Utc::now();
+                        let tasks = vec![
+                            task("short", now + Duration::hours(6), Duration::hours(1)),
+                            Task {
+                                importance: 6,
+                                ..task("long", now + Duration::hours(6), Duration::hours(2))
+                            },
+                        ];
+                        // Two overlapping meetings, leaving room for both tasks on either side
+                        let busy = [
+                            now + Duration::hours(1)..now + Duration::minutes(90),
+                            now + Duration::minutes(80)..now + Duration::hours(3),
+                        ];
+                        let schedule = strictly_within_segment(now, tasks.clone(), anytime(), &busy, &Dependencies::default(), $strategy).unwrap();
+                        assert_eq!(schedule.tasks[0].task, tasks[0]);
+                        assert_eq!(schedule.tasks[0].when, now);
+                        assert_eq!(schedule.tasks[1].task, tasks[1]);
+                        assert_eq!(schedule.tasks[1].when, now + Duration::hours(3));
+
+                        // The long task doesn't fit anywhere once the meetings take longer
+                        let busy = [now + Duration::hours(1)..now + Duration::hours(4) + Duration::minutes(1)];
+                        let schedule = strictly_within_segment(now, tasks, anytime(), &busy, &Dependencies::default(), $strategy);
+                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
+                    }
+
+                    #[test]
+                    fn respects_available_from() {
+                        let now = Utc::now();
+                        let tasks = vec![
+                            Task {
+                                importance: 9,
+                                available_from: Some(now + Duration::days(7)),
+                                ..task("read the letter", now + Duration::days(10), Duration::hours(1))
+                            },
+                            Task {
+                                importance: 1,
+                                available_from: Some(now - Duration::days(7)),
+                                ..task("anything else", now + Duration::days(10), Duration::hours(1))
+                            },
+                        ];
+                        let scheduled_tasks = schedule(tasks.clone(), now).unwrap().tasks;
+                        assert_eq!(scheduled_tasks[0].task, tasks[1]);
+                        assert_eq!(scheduled_tasks[0].when, now);
+                        assert_eq!(scheduled_tasks[1].task, tasks[0]);
+                        assert_eq!(scheduled_tasks[1].when, now + Duration::days(7));
+
+                        // It can't fit if it's only available after the deadline
+                        let mut tasks = tasks;
+                        tasks[0].available_from = Some(tasks[0].deadline - Duration::minutes(59));
+                        assert_matches!(schedule(tasks, now),
+                                        Err(Error::DeadlineMissed { tense, .. })
+                                        if tense == "will miss");
+                    }
+
+                    #[test]
+                    fn respects_dependencies() {
+                        let now = Utc::now();
+                        let tasks = vec![
+                            Task {
+                                importance: 10,
+                                depends_on: vec!["proofread".to_string()],
+                                ..task("publish", now + Duration::hours(3), Duration::hours(1))
+                            },
+                            Task {
+                                importance: 1,
+                                ..task("write", now + Duration::hours(6), Duration::hours(1))
+                            },
+                            Task {
+                                importance: 1,
+                                depends_on: vec!["write".to_string()],
+                                ..task("proofread", now + Duration::hours(6), Duration::hours(1))
+                            },
+                        ];
+                        let scheduled_tasks = schedule(tasks.clone(), now).unwrap().tasks;
+                        assert_eq!(scheduled_tasks[0].task, tasks[1]);
+                        assert_eq!(scheduled_tasks[0].when, now);
+                        assert_eq!(scheduled_tasks[1].task, tasks[2]);
+                        assert_eq!(scheduled_tasks[1].when, now + Duration::hours(1));
+                        assert_eq!(scheduled_tasks[2].task, tasks[0]);
+                        assert_eq!(scheduled_tasks[2].when, now + Duration::hours(2));
+
+                        // There's no room for the prerequisites if the deadline is too close
+                        let mut tasks = tasks;
+                        tasks[0].deadline = now + Duration::hours(2) + Duration::minutes(59);
+                        assert_matches!(schedule(tasks, now), Err(Error::NotEnoughTime { .. }));
+                    }
+
+                    #[test]
+                    fn splits_tasks_over_gaps() {
+                        let now = Utc::now();
+                        let tasks = vec![Task {
+                            min_chunk_duration: Some(Duration::minutes(30)),
+                            ..task("write thesis", now + Duration::hours(5), Duration::hours(3))
+                        }];
+                        let busy = [
+                            now + Duration::hours(1)..now + Duration::hours(2),
+                            now + Duration::hours(3)..now + Duration::hours(4),
+                        ];
+                        let mut tasks = tasks;
+                        // Whether it's split up right away or only when it's pulled forward
+                        for deadline in &[Duration::hours(5), Duration::hours(10)] {
+                            tasks[0].deadline = now + *deadline;
+                            let schedule = strictly_within_segment(now, tasks.clone(), anytime(), &busy, &Dependencies::default(), $strategy).unwrap();
+                            assert_eq!(schedule.tasks.len(), 3);
+                            for (scheduled, hours) in schedule.tasks.iter().zip(&[0, 2, 4]) {
+                                assert_eq!(scheduled.task, tasks[0]);
+                                assert_eq!(scheduled.when, now + Duration::hours(*hours));
+                                assert_eq!(scheduled.duration, Duration::hours(1));
+                            }
+                        }
+
+                        // The gaps are too small for chunks of at least two hours
+                        tasks[0].deadline = now + Duration::hours(5);
+                        tasks[0].min_chunk_duration = Some(Duration::hours(2));
+                        let schedule = strictly_within_segment(now, tasks, anytime(), &busy, &Dependencies::default(), $strategy);
+                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
+                    }
+
+                    #[test]
+                    fn can_handle_never_time_segment() {
+                        let tasks = taskset_of_myrjam();
+                        let schedule = strictly_within_segment(Utc::now(), tasks, never(), &[], &Dependencies::default(), $strategy);
+                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
+                        let tasks: Vec<Task> = vec![];
+                        let schedule = strictly_within_segment(Utc::now(), tasks, never(), &[], &Dependencies::default(), $strategy);
+                        assert_matches!(schedule, Ok(Schedule { tasks, .. }) if tasks.is_empty());
+                    }
+                }
+             )*
+        }
+    }
+
+    test_generic_properties! {
+        importance: &ImportanceStrategy,
+        urgency: &UrgencyStrategy,
+        edf: &EarliestDeadlineFirstStrategy,
+        wspt: &WeightedShortestJobFirstStrategy,
+        optimal: &OptimalStrategy::default(),
+    }
+
+    /// Properties of the strategies that first pack the tasks before their deadlines and then pull
+    /// them forward again.
+    macro_rules! test_two_phase_properties {
+        ($($strategy_name:ident: $strategy:expr,)*) => {
+            $(
+                mod $strategy_name {
+                    use super::*;
+
+                    /// Schedules the given tasks in a time segment without
+                    /// gaps, when they fit.
+                    fn schedule(tasks: Vec<Task>, start: DateTime<Utc>) -> Schedule<Task> {
+                        let dependencies = Dependencies::new(start, &tasks).unwrap();
+                        strictly_within_segment(start, tasks, anytime(), &[], &dependencies, $strategy).unwrap()
+                    }
+
+                    #[test]
+                    fn schedule_sets_of_two() {
+                        let start = Utc::now();
+                        let mut tasks = vec![Task {
+                            content: "find meaning to life".to_string(),
+                            deadline: start + Duration::hours(1),
+                            duration: Duration::hours(1),
+                            importance: 6,
+                            available_from: None,
+                            depends_on: vec![],
+                            min_chunk_duration: None,
+                            pinned_at: None,
+                        },
+                        Task {
+                            content: "stop giving a fuck".to_string(),
+                            deadline: start + Duration::hours(3),
+                            duration: Duration::hours(2),
+                            importance: 5,
+                            available_from: None,
+                            depends_on: vec![],
+                            min_chunk_duration: None,
+                            pinned_at: None,
+                        }];
+                        // Normal scheduling
+                        {
+                            let schedule = schedule(tasks.clone(), start);
+                            assert_eq!(schedule.tasks[0].task, tasks[0]);
+                            assert_eq!(schedule.tasks[1].task, tasks[1]);
+                        }
+
+                        // Reversing the importance should maintain the scheduled order, because it's the only way
+                        // to meet the deadlines.
+                        tasks[0].importance = 5;
+                        tasks[1].importance = 6;
+                        {
+                            let schedule = schedule(tasks.clone(), start);
+                            assert_eq!(schedule.tasks[0].task, tasks[0]);
+                            assert_eq!(schedule.tasks[1].task, tasks[1]);
+                        }
+
+                        // Leveling the deadlines should make the more important task be scheduled first again.
+                        tasks[0].deadline = start + Duration::hours(3);
+                        let schedule = schedule(tasks.clone(), start);
+                        assert_eq!(schedule.tasks[0].task, tasks[1]);
+                        assert_eq!(schedule.tasks[1].task, tasks[0]);
+                    }
+
+                    #[test]
+                    fn best_effort_schedules_what_fits() {
+                        let start = Utc::now();
+                        let segment = UnnamedTimeSegment {
+                            ranges: vec![start..start + Duration::weeks(1)],
+                            start,
+                            period: Duration::weeks(1),
+                        };
+                        let task = |content: &str, deadline, hours, importance| Task {
+                            importance,
+                            ..task(content, deadline, Duration::hours(hours))
+                        };
                         let tasks = vec![
-                            Task {
-                                content: "too-long".to_string(),
-                                deadline: now + Duration::days(4),
-                                duration: Duration::hours(2) + Duration::seconds(1),
-                                importance: 10,
-                            },
+                            task("write a novel", start + Duration::hours(2), 2, 5),
+                            task("read a novel", start + Duration::hours(2), 1, 3),
+                            task("buy a pen", start - Duration::hours(1), 1, 1),
                         ];
-                        let schedule = Schedule::schedule_within_segment(now, tasks, segment.clone(), $strategy);
-                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
-
-                        // Trying to schedule more tasks than possible to fit in
-                        // to the segment, fails as well
-                        let tasks = vec![
-                            Task {
-                                content: "task1".to_string(),
-                                deadline: now + Duration::hours(36) - Duration::seconds(1),
-                                duration: Duration::hours(1),
-                                importance: 5,
-                            },
-                            Task {
-                                content: "task2".to_string(),
-                                deadline: now + Duration::hours(36) - Duration::seconds(1),
-                                duration: Duration::hours(1),
-                                importance: 5,
+                        let BestEffortSchedule { schedule, unschedulable, .. } = Schedule::schedule_within_segment(
+                            start, tasks.clone(), segment, &[], &Dependencies::default(), $strategy, &HashMap::new()).unwrap();
+                        // The least important tasks are left out
+                        assert_eq!(schedule.tasks.len(), 1);
+                        assert_eq!(schedule.tasks[0].task, tasks[0]);
+                        assert_eq!(schedule.tasks[0].when, start);
+                        assert_eq!(unschedulable, vec![
+                            Unschedulable {
+                                task: tasks[2].clone(),
+                                reason: UnschedulableReason::DeadlinePassed,
+                                deadline_extension: Some(Duration::hours(2)),
+                                pin_conflict: None,
                             },
-                            Task {
-                                content: "task3".to_string(),
-                                deadline: now + Duration::hours(36) - Duration::seconds(1),
-                                duration: Duration::hours(2),
-                                importance: 5,
+                            Unschedulable {
+                                task: tasks[1].clone(),
+                                reason: UnschedulableReason::NotEnoughTime,
+                                deadline_extension: Some(Duration::hours(1)),
+                                pin_conflict: None,
                             },
-                        ];
-                        let schedule = Schedule::schedule_within_segment(now, tasks, segment, $strategy);
-                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
+                        ]);
                     }
 
                     #[test]
-                    fn can_handle_never_time_segment() {
-                        let tasks = taskset_of_myrjam();
-                        let schedule = Schedule::schedule_within_segment(Utc::now(), tasks, never(), $strategy);
-                        assert_matches!(schedule, Err(Error::NotEnoughTime { .. }));
-                        let tasks: Vec<Task> = vec![];
-                        let schedule = Schedule::schedule_within_segment(Utc::now(), tasks, never(), $strategy);
-                        assert_matches!(schedule, Ok(Schedule(tasks)) if tasks.is_empty());
+                    fn explains_where_tasks_went() {
+                        let start = Utc::now();
+                        let segment = UnnamedTimeSegment {
+                            ranges: vec![start..start + Duration::weeks(1)],
+                            start,
+                            period: Duration::weeks(1),
+                        };
+                        let task = |content: &str, minutes, depends_on: &[&str]| Task {
+                            depends_on: depends_on.iter().map(|content| content.to_string()).collect(),
+                            ..task(content, start + Duration::hours(4), Duration::minutes(minutes))
+                        };
+                        let tasks = vec![
+                            task("write a letter", 60, &[]),
+                            task("post the letter", 30, &["write a letter"]),
+                        ];
+                        let busy = [start..start + Duration::minutes(30)];
+                        let dependencies = Dependencies::new(start, &tasks).unwrap();
+                        let explanations = Schedule::schedule_within_segment(
+                            start, tasks.clone(), segment, &busy, &dependencies, $strategy, &HashMap::new()).unwrap().explanations;
+                        let at = |minutes| start + Duration::minutes(minutes);
+                        assert_eq!(explanations.len(), 2);
+                        // Packed right before the letter is posted, then pulled forward until the
+                        // busy time.
+                        assert_eq!(explanations[0].task, tasks[0]);
+                        assert_eq!(explanations[0].steps.len(), 2);
+                        assert_eq!(explanations[0].steps[0], Step {
+                            phase: Phase::DeadlinePacking,
+                            start: at(150),
+                            end: at(210),
+                            moved: Duration::zero(),
+                            constraints: vec![Constraint::Dependent { task: tasks[1].clone() }],
+                        });
+                        assert_eq!(explanations[0].steps[1], Step {
+                            phase: Phase::PullForward,
+                            start: at(30),
+                            end: at(90),
+                            moved: Duration::hours(2),
+                            constraints: vec![Constraint::Unavailable { start, end: at(30) }],
+                        });
+                        // Packed before the deadline, then pulled forward until the letter is
+                        // written.
+                        assert_eq!(explanations[1].task, tasks[1]);
+                        assert_eq!(explanations[1].steps.first(), Some(&Step {
+                            phase: Phase::DeadlinePacking,
+                            start: at(210),
+                            end: at(240),
+                            moved: Duration::zero(),
+                            constraints: vec![Constraint::Deadline],
+                        }));
+                        assert_eq!(explanations[1].steps.last(), Some(&Step {
+                            phase: Phase::PullForward,
+                            start: at(90),
+                            end: at(120),
+                            moved: Duration::hours(2),
+                            constraints: vec![Constraint::Prerequisite { task: tasks[0].clone() }],
+                        }));
                     }
                 }
              )*
         }
     }
 
-    test_generic_properties! {
-        importance: SchedulingStrategy::Importance,
-        urgency: SchedulingStrategy::Urgency,
+    mod two_phase {
+        use super::*;
+
+        test_two_phase_properties! {
+            importance: &ImportanceStrategy,
+            urgency: &UrgencyStrategy,
+        }
+    }
+
+    /// Puts the tasks right before their deadlines in the order they're given, except for the
+    /// ones that are said to be less important than they are.
+    #[derive(Debug)]
+    struct JustInTime;
+
+    impl Strategy for JustInTime {
+        fn name(&self) -> &str {
+            "just in time"
+        }
+
+        fn schedule(&self, planner: &mut dyn Planner) {
+            for task in 0..planner.task_count() {
+                if planner.importance(task) > 1 {
+                    let min_start = planner.earliest_start(task);
+                    let max_end = planner.latest_end(task);
+                    planner.schedule_close_before(task, min_start, max_end);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn schedules_with_custom_strategies() {
+        let mut strategies = crate::configuration::Strategies::default();
+        strategies.register(Box::new(JustInTime));
+        assert_eq!(
+            strategies.names().collect_vec(),
+            [
+                "importance",
+                "urgency",
+                "edf",
+                "wspt",
+                "optimal",
+                "just in time"
+            ]
+        );
+        let now = Utc::now();
+        let task = |content: &str, hours, importance| Task {
+            importance,
+            ..task(content, now + Duration::hours(hours), Duration::hours(1))
+        };
+        let tasks = vec![
+            task("water the plants", 3, 5),
+            task("feed the cat", 5, 5),
+            task("dust the shelves", 5, 1),
+        ];
+        let best_effort = Schedule::schedule_best_effort(
+            now,
+            vec![(anytime(), tasks.clone())],
+            vec![],
+            strategies.get("just in time").unwrap(),
+        )
+        .unwrap();
+        let scheduled = &best_effort.schedule.tasks;
+        assert_eq!(scheduled.len(), 2);
+        assert_eq!(scheduled[0].task, tasks[0]);
+        assert_eq!(scheduled[0].when, now + Duration::hours(2));
+        assert_eq!(scheduled[1].task, tasks[1]);
+        assert_eq!(scheduled[1].when, now + Duration::hours(4));
+        // The task the strategy left alone doesn't fit
+        assert_eq!(best_effort.unschedulable.len(), 1);
+        assert_eq!(best_effort.unschedulable[0].task, tasks[2]);
+        assert_eq!(
+            best_effort.unschedulable[0].reason,
+            UnschedulableReason::NotEnoughTime
+        );
+    }
+
+    #[test]
+    fn earliest_deadline_first_ignores_importance() {
+        let now = Utc::now();
+        let task = |content: &str, hours, importance| Task {
+            importance,
+            ..task(content, now + Duration::hours(hours), Duration::hours(1))
+        };
+        let tasks = vec![
+            task("file the taxes", 10, 9),
+            task("water the plants", 5, 1),
+        ];
+        let schedule = Schedule::schedule(
+            now,
+            vec![(anytime(), tasks.clone())],
+            vec![],
+            &EarliestDeadlineFirstStrategy,
+        )
+        .unwrap();
+        assert_eq!(schedule.tasks[0].task, tasks[1]);
+        assert_eq!(schedule.tasks[0].when, now);
+        assert_eq!(schedule.tasks[1].task, tasks[0]);
+        assert_eq!(schedule.tasks[1].when, now + Duration::hours(1));
+    }
+
+    #[test]
+    fn weighted_shortest_job_first_keeps_deadlines_feasible() {
+        let now = Utc::now();
+        let task = |content: &str, deadline, hours, importance| Task {
+            importance,
+            ..task(
+                content,
+                now + Duration::hours(deadline),
+                Duration::hours(hours),
+            )
+        };
+        let tasks = vec![
+            task("answer the letter", 10, 1, 6),
+            task("paint the fence", 10, 3, 6),
+            task("put out the bins", 2, 1, 1),
+        ];
+        let schedule = Schedule::schedule(
+            now,
+            vec![(anytime(), tasks.clone())],
+            vec![],
+            &WeightedShortestJobFirstStrategy,
+        )
+        .unwrap();
+        // The fence is worth more per hour than the bins, but doing it second would make the bins
+        // miss their deadline.
+        let order = schedule
+            .tasks
+            .iter()
+            .map(|scheduled| (scheduled.task.content.as_str(), scheduled.when))
+            .collect_vec();
+        assert_eq!(
+            order,
+            [
+                ("answer the letter", now),
+                ("put out the bins", now + Duration::hours(1)),
+                ("paint the fence", now + Duration::hours(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn optimal_minimises_weighted_completion_time() {
+        let now = Utc::now();
+        let task = |content: &str, hours, importance| Task {
+            importance,
+            ..task(content, now + Duration::hours(10), Duration::hours(hours))
+        };
+        let tasks = vec![
+            task("paint the fence", 3, 6),
+            task("answer the letter", 1, 5),
+        ];
+        let schedule = |strategy: &dyn Strategy| {
+            Schedule::schedule(now, vec![(anytime(), tasks.clone())], vec![], strategy).unwrap()
+        };
+        // The importance strategy does the most important task first, even though doing the
+        // short one first gets more done sooner.
+        let heuristic = schedule(&ImportanceStrategy);
+        assert_eq!(heuristic.tasks[0].task, tasks[0]);
+        let optimal = schedule(&OptimalStrategy::default());
+        assert_eq!(optimal.tasks[0].task, tasks[1]);
+        assert_eq!(optimal.tasks[0].when, now);
+        assert_eq!(optimal.tasks[1].task, tasks[0]);
+        assert_eq!(optimal.tasks[1].when, now + Duration::hours(1));
+    }
+
+    #[test]
+    fn optimal_falls_back_to_importance_when_out_of_time() {
+        let tasks = taskset_of_myrjam();
+        let schedule = |strategy: &dyn Strategy| {
+            Schedule::schedule(
+                Utc::now(),
+                vec![(anytime(), tasks.clone())],
+                vec![],
+                strategy,
+            )
+            .unwrap()
+        };
+        let out_of_time = OptimalStrategy {
+            time_limit: std::time::Duration::from_secs(0),
+        };
+        let order = |schedule: Schedule<Task>| {
+            schedule
+                .tasks
+                .into_iter()
+                .map(|scheduled| scheduled.task.content)
+                .collect_vec()
+        };
+        assert_eq!(
+            order(schedule(&out_of_time)),
+            order(schedule(&ImportanceStrategy))
+        );
+    }
+
+    /// How bad a best effort schedule is: the importance it leaves out, and the sum of each task's
+    /// importance times the hours from `start` until it's done.
+    fn score(start: DateTime<Utc>, best_effort: &BestEffortSchedule<Task>) -> (u64, f64) {
+        let left_out = best_effort
+            .unschedulable
+            .iter()
+            .map(|unschedulable| u64::from(unschedulable.task.importance))
+            .sum();
+        let mut ends = HashMap::new();
+        for scheduled in &best_effort.schedule.tasks {
+            let end = scheduled.when + scheduled.duration;
+            let task_end = ends.entry(&scheduled.task).or_insert(end);
+            *task_end = max(*task_end, end);
+        }
+        let weighted_completion = ends
+            .into_iter()
+            .map(|(task, end)| {
+                f64::from(task.importance) * (end - start).num_minutes() as f64 / 60.0
+            })
+            .sum();
+        (left_out, weighted_completion)
+    }
+
+    /// How a heuristic strategy did compared to `OptimalStrategy`.
+    #[derive(Debug, Default)]
+    struct Comparison {
+        /// In how many rounds it was optimal.
+        optimal: usize,
+        /// In how many rounds it left out more importance than needed.
+        left_out: usize,
+        /// In the other rounds, the relative extra weighted completion time.
+        extra: Vec<f64>,
+    }
+
+    /// Schedules `rounds` made up sets of up to `max_tasks` tasks during office hours, with the
+    /// heuristic strategies and with `OptimalStrategy`, and compares how each heuristic did to the
+    /// optimal schedule, in the order importance, urgency, EDF and WSPT.
+    fn compare_with_optimal(rounds: usize, max_tasks: u64) -> Vec<Comparison> {
+        let heuristics: [&dyn Strategy; 4] = [
+            &ImportanceStrategy,
+            &UrgencyStrategy,
+            &EarliestDeadlineFirstStrategy,
+            &WeightedShortestJobFirstStrategy,
+        ];
+        let optimal = OptimalStrategy {
+            time_limit: std::time::Duration::from_secs(600),
+        };
+        let mut random = Lcg(42);
+        let mut comparisons = heuristics
+            .iter()
+            .map(|_| Comparison::default())
+            .collect_vec();
+        for _ in 0..rounds {
+            let start = Utc::now();
+            let office_hours = UnnamedTimeSegment {
+                ranges: vec![start..start + Duration::hours(8)],
+                start,
+                period: Duration::days(1),
+            };
+            let tasks = (0..2 + random.below(max_tasks - 1))
+                .map(|i| {
+                    let deadline = start + Duration::hours(2 + random.below(40) as i64);
+                    let duration = Duration::minutes(30 * (1 + random.below(8)) as i64);
+                    Task {
+                        importance: 1 + random.below(10) as u32,
+                        ..task(&format!("task {}", i), deadline, duration)
+                    }
+                })
+                .collect_vec();
+            let score = |strategy: &dyn Strategy| {
+                let segments = vec![(office_hours.clone(), tasks.clone())];
+                let best_effort =
+                    Schedule::schedule_best_effort(start, segments, vec![], strategy).unwrap();
+                score(start, &best_effort)
+            };
+            let best = score(&optimal);
+            for (heuristic, comparison) in heuristics.iter().zip(&mut comparisons) {
+                let found = score(*heuristic);
+                assert!(
+                    best.0 < found.0 || (best.0 == found.0 && best.1 <= found.1),
+                    "{} did better than optimal on {:?}",
+                    heuristic.name(),
+                    tasks
+                );
+                if found == best {
+                    comparison.optimal += 1;
+                } else if found.0 > best.0 {
+                    comparison.left_out += 1;
+                } else {
+                    comparison.extra.push(found.1 / best.1 - 1.0);
+                }
+            }
+        }
+        comparisons
+    }
+
+    #[test]
+    fn heuristics_are_never_better_than_optimal() {
+        compare_with_optimal(20, 6);
+    }
+
+    /// Checks that WSPT is the heuristic that comes closest to optimal. It takes a while, so run
+    /// it with `cargo test --release heuristics_compared -- --ignored`.
+    #[test]
+    #[ignore]
+    fn heuristics_compared_with_optimal() {
+        let rounds = 200;
+        let comparisons = compare_with_optimal(rounds, 9);
+        let wspt = &comparisons[3];
+        assert!(
+            wspt.optimal >= rounds * 3 / 4,
+            "WSPT is optimal in only {} of {} rounds",
+            wspt.optimal,
+            rounds
+        );
+        for comparison in &comparisons[..3] {
+            assert!(comparison.optimal < wspt.optimal, "{:?}", comparison);
+            assert!(comparison.left_out > wspt.left_out, "{:?}", comparison);
+        }
+        for comparison in &comparisons {
+            assert_eq!(
+                comparison.optimal + comparison.left_out + comparison.extra.len(),
+                rounds
+            );
+            assert!(comparison.extra.iter().all(|&extra| extra > 0.0));
+        }
+    }
+
+    #[test]
+    fn rejects_dependency_cycles() {
+        let now = Utc::now();
+        let task = |content: &str, depends_on: &str| Task {
+            depends_on: vec![depends_on.to_string()],
+            ..task(content, now + Duration::days(1), Duration::hours(1))
+        };
+        let tasks = vec![
+            task("paint", "sand"),
+            task("sand", "paint"),
+            task("sweep", "sand"),
+        ];
+        let schedule =
+            Schedule::schedule(now, vec![(anytime(), tasks)], vec![], &ImportanceStrategy);
+        match schedule {
+            Err(error @ Error::DependencyCycle { .. }) => {
+                let message = error.to_string();
+                assert!(
+                    message.contains("paint → sand → paint")
+                        || message.contains("sand → paint → sand")
+                );
+            }
+            result => panic!("expected a dependency cycle, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn respects_dependencies_across_time_segments() {
+        let now = Utc::now();
+        let tasks_per_segment = vec![
+            (
+                anytime(),
+                vec![Task {
+                    importance: 10,
+                    depends_on: vec!["write the report".to_string()],
+                    ..task(
+                        "send the report",
+                        now + Duration::hours(3),
+                        Duration::hours(1),
+                    )
+                }],
+            ),
+            (
+                anytime(),
+                vec![Task {
+                    importance: 1,
+                    ..task(
+                        "write the report",
+                        now + Duration::hours(6),
+                        Duration::hours(2),
+                    )
+                }],
+            ),
+        ];
+        let strategies: [&dyn Strategy; 2] = [&ImportanceStrategy, &UrgencyStrategy];
+        for strategy in &strategies {
+            let schedule =
+                Schedule::schedule(now, tasks_per_segment.clone(), vec![], *strategy).unwrap();
+            assert_eq!(schedule.tasks[0].task.content, "write the report");
+            assert_eq!(schedule.tasks[0].when, now);
+            assert_eq!(schedule.tasks[1].task.content, "send the report");
+            assert_eq!(schedule.tasks[1].when, now + Duration::hours(2));
+        }
+    }
+
+    #[test]
+    fn events_of_a_time_segment_only_block_that_segment() {
+        let now = Utc::now();
+        let segment = |id| NamedTimeSegment {
+            id,
+            name: format!("segment {}", id),
+            ranges: vec![now..now + Duration::weeks(1)],
+            start: now,
+            period: Duration::weeks(1),
+            hue: 0,
+        };
+        let event = |id, time_segment_id| FixedEvent {
+            id,
+            title: format!("event {}", id),
+            start: now,
+            end: now + Duration::hours(1),
+            time_segment_id,
+            ical_uid: None,
+        };
+        let tasks_per_segment = vec![
+            (
+                segment(0),
+                vec![task(
+                    "mow the lawn",
+                    now + Duration::hours(4),
+                    Duration::hours(1),
+                )],
+            ),
+            (
+                segment(1),
+                vec![task(
+                    "write the report",
+                    now + Duration::hours(4),
+                    Duration::hours(1),
+                )],
+            ),
+        ];
+        let strategy = ImportanceStrategy;
+        let schedule = Schedule::schedule(
+            now,
+            tasks_per_segment.clone(),
+            vec![event(1, Some(1))],
+            &strategy,
+        )
+        .unwrap();
+        let when = |content: &str| {
+            schedule
+                .tasks
+                .iter()
+                .find(|scheduled| scheduled.task.content == content)
+                .unwrap()
+                .when
+        };
+        assert_eq!(when("mow the lawn"), now);
+        assert!(now + Duration::hours(1) <= when("write the report"));
+        assert_eq!(schedule.events, [event(1, Some(1))]);
+
+        // Events without a time segment block every segment
+        let schedule =
+            Schedule::schedule(now, tasks_per_segment, vec![event(2, None)], &strategy).unwrap();
+        assert!(schedule
+            .tasks
+            .iter()
+            .all(|scheduled| now + Duration::hours(1) <= scheduled.when));
+    }
+
+    /// Returns when the chunks of the tasks in `schedule` are scheduled.
+    fn placements(schedule: &Schedule<Task>) -> Placements<Task> {
+        let mut placements = Placements::new();
+        for scheduled in &schedule.tasks {
+            placements
+                .entry(scheduled.task.clone())
+                .or_insert_with(Vec::new)
+                .push(scheduled.when..scheduled.when + scheduled.duration);
+        }
+        placements
+    }
+
+    #[test]
+    fn stable_schedule_keeps_tasks_where_they_were() {
+        let now = Utc::now();
+        let task = |content: &str, hours, importance| Task {
+            importance,
+            ..task(content, now + Duration::hours(hours), Duration::hours(1))
+        };
+        let tasks = vec![task("mow the lawn", 10, 3), task("wash the car", 10, 5)];
+        let previous = Schedule::schedule(
+            now,
+            vec![(anytime(), tasks.clone())],
+            vec![],
+            &ImportanceStrategy,
+        )
+        .unwrap();
+        let urgent = task("call the plumber", 3, 10);
+        let stable = Schedule::schedule_stable(
+            now,
+            vec![(
+                anytime(),
+                vec![urgent.clone(), tasks[0].clone(), tasks[1].clone()],
+            )],
+            vec![],
+            &ImportanceStrategy,
+            &placements(&previous),
+        )
+        .unwrap();
+        assert!(stable.best_effort.unschedulable.is_empty());
+        // The new task goes around the ones that were already planned
+        let schedule = &stable.best_effort.schedule;
+        assert_eq!(schedule.tasks.len(), 3);
+        assert_eq!(schedule.tasks[..2], previous.tasks[..]);
+        assert_eq!(schedule.tasks[2].task, urgent);
+        assert_eq!(schedule.tasks[2].when, now + Duration::hours(2));
+        assert_eq!(
+            stable.moves,
+            [Move {
+                task: urgent,
+                from: vec![],
+                to: vec![now + Duration::hours(2)..now + Duration::hours(3)],
+            }]
+        );
+        for explanation in &stable.best_effort.explanations {
+            if explanation.task != schedule.tasks[2].task {
+                assert_eq!(explanation.steps.len(), 1);
+                assert_eq!(explanation.steps[0].phase, Phase::Kept);
+            }
+        }
+    }
+
+    #[test]
+    fn stable_schedule_only_moves_tasks_that_are_in_the_way() {
+        let now = Utc::now();
+        let at = |hours| now + Duration::hours(hours);
+        let task = |content: &str, hours| task(content, at(hours), Duration::hours(1));
+        let tasks = [task("mow the lawn", 10), task("wash the car", 10)];
+        let previous = vec![
+            (tasks[0].clone(), vec![at(0)..at(1)]),
+            (tasks[1].clone(), vec![at(5)..at(6)]),
+        ]
+        .into_iter()
+        .collect();
+        let urgent = task("call the plumber", 1);
+        let strategies: [&dyn Strategy; 2] = [&ImportanceStrategy, &UrgencyStrategy];
+        for strategy in &strategies {
+            let stable = Schedule::schedule_stable(
+                now,
+                vec![(
+                    anytime(),
+                    vec![urgent.clone(), tasks[0].clone(), tasks[1].clone()],
+                )],
+                vec![],
+                *strategy,
+                &previous,
+            )
+            .unwrap();
+            assert!(stable.best_effort.unschedulable.is_empty());
+            let schedule = &stable.best_effort.schedule;
+            assert_eq!(schedule.tasks.len(), 3);
+            assert_eq!(schedule.tasks[0].task, urgent);
+            assert_eq!(schedule.tasks[0].when, at(0));
+            assert_eq!(schedule.tasks[1].task, tasks[0]);
+            // The task that isn't in the way stays where it was
+            assert_eq!(schedule.tasks[2].task, tasks[1]);
+            assert_eq!(schedule.tasks[2].when, at(5));
+            assert_eq!(
+                stable.moves,
+                [
+                    Move {
+                        task: urgent.clone(),
+                        from: vec![],
+                        to: vec![at(0)..at(1)],
+                    },
+                    Move {
+                        task: tasks[0].clone(),
+                        from: vec![at(0)..at(1)],
+                        to: vec![
+                            schedule.tasks[1].when..schedule.tasks[1].when + Duration::hours(1)
+                        ],
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn stable_schedule_moves_tasks_that_dont_fit_anymore() {
+        let now = Utc::now();
+        let at = |hours| now + Duration::hours(hours);
+        let task = |content: &str, hours, min_chunk_duration| Task {
+            min_chunk_duration,
+            ..task(content, at(10), Duration::hours(hours))
+        };
+        let tasks = vec![
+            task("mow the lawn", 1, None),
+            task("wash the car", 2, None),
+            task("paint the fence", 2, Some(Duration::hours(1))),
+            task("clean the gutters", 1, None),
+        ];
+        let previous = vec![
+            // There's a meeting now
+            (tasks[0].clone(), vec![at(4)..at(5)]),
+            // It takes longer now
+            (tasks[1].clone(), vec![at(6)..at(7)]),
+            // Split up, but still fine
+            (tasks[2].clone(), vec![at(1)..at(2), at(8)..at(9)]),
+            // It's in the past now
+            (tasks[3].clone(), vec![at(-2)..at(-1)]),
+        ]
+        .into_iter()
+        .collect();
+        let meeting = FixedEvent {
+            id: 1,
+            title: "meeting".to_string(),
+            start: at(4),
+            end: at(5),
+            time_segment_id: None,
+            ical_uid: None,
+        };
+        let stable = Schedule::schedule_stable(
+            now,
+            vec![(anytime(), tasks.clone())],
+            vec![meeting],
+            &ImportanceStrategy,
+            &previous,
+        )
+        .unwrap();
+        assert!(stable.best_effort.unschedulable.is_empty());
+        let kept = placements(&stable.best_effort.schedule);
+        assert_eq!(kept[&tasks[2]], previous[&tasks[2]]);
+        let moved = stable.moves.iter().map(|moved| &moved.task).collect_vec();
+        assert_eq!(moved.len(), 3);
+        for task in &[&tasks[0], &tasks[1], &tasks[3]] {
+            assert!(moved.contains(task));
+        }
+    }
+
+    #[test]
+    fn pinned_tasks_are_scheduled_where_they_are_pinned() {
+        let now = Utc::now();
+        let at = |hours| now + Duration::hours(hours);
+        let task = |content: &str, pinned_at| Task {
+            pinned_at,
+            ..task(content, at(10), Duration::hours(2))
+        };
+        let tasks = vec![
+            task("call the plumber", Some(at(1))),
+            task("mow the lawn", None),
+            task("wash the car", None),
+            // Pins that have passed don't count anymore
+            task("fix the roof", Some(at(-1))),
+        ];
+        let strategies: [&dyn Strategy; 4] = [
+            &ImportanceStrategy,
+            &UrgencyStrategy,
+            &EarliestDeadlineFirstStrategy,
+            &OptimalStrategy::default(),
+        ];
+        for strategy in &strategies {
+            let best_effort = Schedule::schedule_best_effort(
+                now,
+                vec![(anytime(), tasks.clone())],
+                vec![],
+                *strategy,
+            )
+            .unwrap();
+            assert!(best_effort.unschedulable.is_empty());
+            let scheduled = &best_effort.schedule.tasks;
+            assert_eq!(scheduled.len(), 4);
+            let plumber = scheduled
+                .iter()
+                .find(|scheduled| scheduled.task == tasks[0])
+                .unwrap();
+            assert_eq!(plumber.when, at(1));
+            for scheduled in scheduled {
+                if scheduled.task != tasks[0] {
+                    assert!(
+                        scheduled.when + scheduled.duration <= at(1) || at(3) <= scheduled.when
+                    );
+                }
+            }
+            let explanation = best_effort
+                .explanations
+                .iter()
+                .find(|explanation| explanation.task == tasks[0])
+                .unwrap();
+            assert_eq!(explanation.steps.len(), 1);
+            assert_eq!(explanation.steps[0].phase, Phase::Pinned);
+        }
+    }
+
+    #[test]
+    fn pins_that_conflict_are_errors() {
+        let now = Utc::now();
+        let at = |hours| now + Duration::hours(hours);
+        let task = |content: &str, pinned_at, depends_on: &[&str]| Task {
+            depends_on: depends_on
+                .iter()
+                .map(|content| content.to_string())
+                .collect(),
+            pinned_at: Some(at(pinned_at)),
+            ..task(content, at(10), Duration::hours(2))
+        };
+        fn schedule(
+            now: DateTime<Utc>,
+            tasks: Vec<Task>,
+            events: Vec<FixedEvent>,
+        ) -> Result<Schedule<Task>> {
+            Schedule::schedule(now, vec![(anytime(), tasks)], events, &ImportanceStrategy)
+        }
+        let meeting = FixedEvent {
+            id: 1,
+            title: "meeting".to_string(),
+            start: at(2),
+            end: at(3),
+            time_segment_id: None,
+            ical_uid: None,
+        };
+        assert_matches!(
+            schedule(now, vec![task("call the plumber", 1, &[])], vec![meeting]),
+            Err(Error::PinConflict { conflict, .. }) if *conflict == PinConflict::Unavailable
+        );
+        let tasks = vec![
+            task("call the plumber", 1, &[]),
+            task("call the electrician", 2, &[]),
+        ];
+        match schedule(now, tasks.clone(), vec![]) {
+            Err(Error::PinConflict { task, conflict }) => assert_eq!(
+                (*task, *conflict),
+                (tasks[1].clone(), PinConflict::Task(tasks[0].clone()))
+            ),
+            result => panic!("expected a pin conflict, got {:?}", result),
+        }
+        let tasks = vec![
+            task("paint the fence", 1, &["sand the fence"]),
+            task("sand the fence", 3, &[]),
+        ];
+        match schedule(now, tasks.clone(), vec![]) {
+            Err(Error::PinConflict { task, conflict }) => assert_eq!(
+                (*task, *conflict),
+                (
+                    tasks[0].clone(),
+                    PinConflict::Prerequisite(tasks[1].clone())
+                )
+            ),
+            result => panic!("expected a pin conflict, got {:?}", result),
+        }
+        assert_matches!(
+            Schedule::schedule(
+                now,
+                vec![(never(), vec![task("call the plumber", 1, &[])])],
+                vec![],
+                &ImportanceStrategy,
+            ),
+            Err(Error::PinConflict { conflict, .. }) if *conflict == PinConflict::Unavailable
+        );
+    }
+
+    #[test]
+    fn pins_that_conflict_are_left_to_the_strategy_on_a_best_effort() {
+        let now = Utc::now();
+        let at = |hours| now + Duration::hours(hours);
+        let task = |content: &str, pinned_at, depends_on: &[&str]| Task {
+            depends_on: depends_on
+                .iter()
+                .map(|content| content.to_string())
+                .collect(),
+            pinned_at: Some(at(pinned_at)),
+            ..task(content, at(10), Duration::hours(2))
+        };
+        let schedule = |tasks: &[Task], events| {
+            Schedule::schedule_best_effort(
+                now,
+                vec![(anytime(), tasks.to_vec())],
+                events,
+                &ImportanceStrategy,
+            )
+            .unwrap()
+        };
+        let meeting = FixedEvent {
+            id: 1,
+            title: "meeting".to_string(),
+            start: at(2),
+            end: at(3),
+            time_segment_id: None,
+            ical_uid: None,
+        };
+        let tasks = vec![task("call the plumber", 1, &[])];
+        let best_effort = schedule(&tasks, vec![meeting]);
+        assert_eq!(
+            best_effort.unschedulable,
+            vec![Unschedulable {
+                task: tasks[0].clone(),
+                reason: UnschedulableReason::PinConflict,
+                deadline_extension: None,
+                pin_conflict: Some(PinConflict::Unavailable),
+            }]
+        );
+        let scheduled = &best_effort.schedule.tasks;
+        assert_eq!(scheduled.len(), 1);
+        assert!(scheduled[0].when + scheduled[0].duration <= at(2) || at(3) <= scheduled[0].when);
+
+        let tasks = vec![
+            task("paint the fence", 1, &["sand the fence"]),
+            task("sand the fence", 3, &[]),
+        ];
+        let best_effort = schedule(&tasks, vec![]);
+        assert_eq!(best_effort.unschedulable.len(), 1);
+        assert_eq!(best_effort.unschedulable[0].task, tasks[0]);
+        assert_eq!(
+            best_effort.unschedulable[0].pin_conflict,
+            Some(PinConflict::Prerequisite(tasks[1].clone()))
+        );
+        let scheduled = &best_effort.schedule.tasks;
+        assert_eq!(scheduled.len(), 2);
+        assert_eq!(scheduled[0].task, tasks[1]);
+        assert_eq!(scheduled[0].when, at(3));
+        assert_eq!(scheduled[1].task, tasks[0]);
+        assert!(at(5) <= scheduled[1].when);
+    }
+
+    #[test]
+    fn tasks_that_used_up_their_estimate_are_unschedulable() {
+        let now = Utc::now();
+        let occurrence = |id, tracked| Occurrence {
+            task: crate::Task {
+                id,
+                content: format!("task {}", id),
+                deadline: now + Duration::days(1),
+                available_from: None,
+                duration: Duration::hours(2),
+                importance: 5,
+                time_segment_id: 0,
+                recurrence: None,
+                min_chunk_duration: None,
+                depends_on: vec![],
+                completed_at: None,
+                ical_uid: None,
+                pin: None,
+                blocked: None,
+            },
+            not_before: None,
+            tracked,
+            correction: Duration::zero(),
+        };
+        let occurrences = vec![
+            occurrence(1, Duration::hours(1)),
+            occurrence(2, Duration::hours(2)),
+            occurrence(3, Duration::hours(3)),
+        ];
+        let best_effort = Schedule::schedule_best_effort(
+            now,
+            vec![(anytime(), occurrences.clone())],
+            vec![],
+            &ImportanceStrategy,
+        )
+        .unwrap();
+        let scheduled = &best_effort.schedule.tasks;
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].task, occurrences[0]);
+        assert_eq!(scheduled[0].duration, Duration::hours(1));
+        let used_up = |occurrence: &Occurrence| Unschedulable {
+            task: occurrence.clone(),
+            reason: UnschedulableReason::EstimateUsedUp,
+            deadline_extension: None,
+            pin_conflict: None,
+        };
+        assert_eq!(
+            best_effort.unschedulable,
+            vec![used_up(&occurrences[1]), used_up(&occurrences[2])]
+        );
+        assert_matches!(
+            Schedule::schedule(
+                now,
+                vec![(anytime(), occurrences.clone())],
+                vec![],
+                &ImportanceStrategy,
+            ),
+            Err(Error::EstimateUsedUp { task }) if *task == occurrences[1]
+        );
+    }
+
+    #[test]
+    fn schedule_returns_upcoming_events() {
+        let now = Utc::now();
+        let event = |id, start, end| FixedEvent {
+            id,
+            title: format!("meeting {}", id),
+            start: now + start,
+            end: now + end,
+            time_segment_id: None,
+            ical_uid: None,
+        };
+        let events = vec![
+            event(1, Duration::hours(5), Duration::hours(6)),
+            event(2, -Duration::hours(2), -Duration::hours(1)),
+            event(3, Duration::hours(1), Duration::hours(2)),
+        ];
+        let tasks = vec![task(
+            "prepare slides",
+            now + Duration::hours(2),
+            Duration::hours(1),
+        )];
+        let schedule = Schedule::schedule(
+            now,
+            vec![(anytime(), tasks.clone())],
+            events.clone(),
+            &ImportanceStrategy,
+        )
+        .unwrap();
+        assert_eq!(schedule.tasks.len(), 1);
+        assert_eq!(schedule.tasks[0].when, now);
+        // The finished event is left out and the others are chronological
+        assert_eq!(schedule.events, [events[2].clone(), events[0].clone()]);
+    }
+
+    #[test]
+    fn free_slots_are_long_enough_free_ranges_in_the_segment() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap();
+        let at = |day, hours, minutes| {
+            start + Duration::days(day) + Duration::hours(hours) + Duration::minutes(minutes)
+        };
+        let office_hours = UnnamedTimeSegment {
+            ranges: vec![at(0, 9, 0)..at(0, 17, 0)],
+            start,
+            period: Duration::days(1),
+        };
+        let busy = vec![
+            at(0, 10, 0)..at(0, 11, 0),
+            at(0, 11, 30)..at(0, 17, 0),
+            at(1, 9, 0)..at(1, 15, 0),
+            at(1, 14, 0)..at(1, 16, 0),
+        ];
+        let free = free_slots(
+            start..at(2, 0, 0),
+            Duration::hours(1),
+            office_hours.clone(),
+            busy.clone(),
+        );
+        assert_eq!(
+            free,
+            FreeSlots {
+                slots: vec![at(0, 9, 0)..at(0, 10, 0), at(1, 16, 0)..at(1, 17, 0)],
+                total: Duration::minutes(150),
+            }
+        );
+
+        // Only the time within the range counts
+        let free = free_slots(
+            at(0, 9, 30)..at(1, 16, 30),
+            Duration::minutes(30),
+            office_hours,
+            busy,
+        );
+        assert_eq!(
+            free,
+            FreeSlots {
+                slots: vec![
+                    at(0, 9, 30)..at(0, 10, 0),
+                    at(0, 11, 0)..at(0, 11, 30),
+                    at(1, 16, 0)..at(1, 16, 30),
+                ],
+                total: Duration::minutes(90),
+            }
+        );
     }
 
     // Note that some of these task sets are not representative at all, since tasks should be small
@@ -663,36 +3516,60 @@ mod tests {
             deadline: now + Duration::days(6 * 365),
             duration: Duration::hours(1000),
             importance: 10,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         let task2 = Task {
             content: "make onion soup".to_string(),
             deadline: now + Duration::hours(2),
             duration: Duration::hours(1),
             importance: 3,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         let task3 = Task {
             content: "publish Commander Mango 3".to_string(),
             deadline: now + Duration::days(365 / 2),
             duration: Duration::hours(50),
             importance: 6,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         let task4 = Task {
             content: "sculpt".to_string(),
             deadline: now + Duration::days(30),
             duration: Duration::hours(10),
             importance: 4,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         let task5 = Task {
             content: "organise birthday present".to_string(),
             deadline: now + Duration::days(30),
             duration: Duration::hours(5),
             importance: 10,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         let task6 = Task {
             content: "make dentist appointment".to_string(),
             deadline: now + Duration::days(7),
             duration: Duration::minutes(10),
             importance: 5,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         vec![task1, task2, task3, task4, task5, task6]
     }
@@ -703,12 +3580,20 @@ mod tests {
             deadline: now + Duration::days(23 * 365),
             duration: Duration::days(23 * 365),
             importance: 5,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         let task2 = Task {
             content: "work till you die".to_string(),
             deadline: now + Duration::days(65 * 365),
             duration: Duration::days(42 * 365),
             importance: 6,
+            available_from: None,
+            depends_on: vec![],
+            min_chunk_duration: None,
+            pinned_at: None,
         };
         vec![task1, task2]
     }
@@ -717,74 +3602,78 @@ mod tests {
     fn schedule_for_myrjam() {
         let tasks = taskset_of_myrjam();
         let start = Utc::now();
-        let schedule = Schedule::schedule_within_segment(
+        let schedule = strictly_within_segment(
             start,
             tasks.clone(),
             anytime(),
-            SchedulingStrategy::Urgency,
+            &[],
+            &Dependencies::default(),
+            &UrgencyStrategy,
         )
         .unwrap();
         let mut expected_when = start;
         // 1. Make onion soup, 1h, 3, in 2 hours
-        assert_eq!(schedule.0[0].task, tasks[1]);
-        assert_eq!(schedule.0[0].when, expected_when);
+        assert_eq!(schedule.tasks[0].task, tasks[1]);
+        assert_eq!(schedule.tasks[0].when, expected_when);
         expected_when = expected_when + Duration::hours(1);
         // 5. Make dentist appointment, 10m, 5, in 7 days
-        assert_eq!(schedule.0[1].task, tasks[5]);
-        assert_eq!(schedule.0[1].when, expected_when);
+        assert_eq!(schedule.tasks[1].task, tasks[5]);
+        assert_eq!(schedule.tasks[1].when, expected_when);
         expected_when = expected_when + Duration::minutes(10);
         // 4. Organise birthday present, 5h, 10, in 30 days
-        assert_eq!(schedule.0[2].task, tasks[4]);
-        assert_eq!(schedule.0[2].when, expected_when);
+        assert_eq!(schedule.tasks[2].task, tasks[4]);
+        assert_eq!(schedule.tasks[2].when, expected_when);
         expected_when = expected_when + Duration::hours(5);
         // 3. Sculpt, 10h, 4, in 30 days
-        assert_eq!(schedule.0[3].task, tasks[3]);
-        assert_eq!(schedule.0[3].when, expected_when);
+        assert_eq!(schedule.tasks[3].task, tasks[3]);
+        assert_eq!(schedule.tasks[3].when, expected_when);
         expected_when = expected_when + Duration::hours(10);
         // 2. Public Commander Mango 3, 50h, 6, in 6 months
-        assert_eq!(schedule.0[4].task, tasks[2]);
-        assert_eq!(schedule.0[4].when, expected_when);
+        assert_eq!(schedule.tasks[4].task, tasks[2]);
+        assert_eq!(schedule.tasks[4].when, expected_when);
         expected_when = expected_when + Duration::hours(50);
         // 0. Take over world, 1000h, 10, in 10 years
-        assert_eq!(schedule.0[5].task, tasks[0]);
-        assert_eq!(schedule.0[5].when, expected_when);
+        assert_eq!(schedule.tasks[5].task, tasks[0]);
+        assert_eq!(schedule.tasks[5].when, expected_when);
     }
 
     #[test]
     fn schedule_myrjams_schedule_by_importance() {
         let tasks = taskset_of_myrjam();
         let start = Utc::now();
-        let schedule = Schedule::schedule_within_segment(
+        let schedule = strictly_within_segment(
             start,
             tasks.clone(),
             anytime(),
-            SchedulingStrategy::Importance,
+            &[],
+            &Dependencies::default(),
+            &ImportanceStrategy,
         )
         .unwrap();
         let mut expected_when = start;
         // 5. Make dentist appointment, 10m, 5, in 7 days
-        assert_eq!(schedule.0[0].task, tasks[5]);
-        assert_eq!(schedule.0[0].when, expected_when);
+        assert_eq!(schedule.tasks[0].task, tasks[5]);
+        assert_eq!(schedule.tasks[0].when, expected_when);
         expected_when = expected_when + Duration::minutes(10);
         // 1. Make onion soup, 1h, 3, in 2 hours
-        assert_eq!(schedule.0[1].task, tasks[1]);
-        assert_eq!(schedule.0[1].when, expected_when);
+        assert_eq!(schedule.tasks[1].task, tasks[1]);
+        assert_eq!(schedule.tasks[1].when, expected_when);
         expected_when = expected_when + Duration::hours(1);
         // 4. Organise birthday present, 5h, 10, in 30 days
-        assert_eq!(schedule.0[2].task, tasks[4]);
-        assert_eq!(schedule.0[2].when, expected_when);
+        assert_eq!(schedule.tasks[2].task, tasks[4]);
+        assert_eq!(schedule.tasks[2].when, expected_when);
         expected_when = expected_when + Duration::hours(5);
         // 2. Public Commander Mango 3, 50h, 6, in 6 months
-        assert_eq!(schedule.0[3].task, tasks[2]);
-        assert_eq!(schedule.0[3].when, expected_when);
+        assert_eq!(schedule.tasks[3].task, tasks[2]);
+        assert_eq!(schedule.tasks[3].when, expected_when);
         expected_when = expected_when + Duration::hours(50);
         // 3. Sculpt, 10h, 4, in 30 days
-        assert_eq!(schedule.0[4].task, tasks[3]);
-        assert_eq!(schedule.0[4].when, expected_when);
+        assert_eq!(schedule.tasks[4].task, tasks[3]);
+        assert_eq!(schedule.tasks[4].when, expected_when);
         expected_when = expected_when + Duration::hours(10);
         // 0. Take over world, 1000h, 10, in 10 years
-        assert_eq!(schedule.0[5].task, tasks[0]);
-        assert_eq!(schedule.0[5].when, expected_when);
+        assert_eq!(schedule.tasks[5].task, tasks[0]);
+        assert_eq!(schedule.tasks[5].when, expected_when);
     }
 
     fn taskset_of_gandalf() -> Vec<Task> {
@@ -795,54 +3684,90 @@ mod tests {
                 deadline: now + Duration::days(12) + Duration::hours(15),
                 duration: Duration::days(2),
                 importance: 9,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Ask advice from Saruman".to_string(),
                 deadline: now + Duration::days(8) + Duration::hours(15),
                 duration: Duration::days(3),
                 importance: 4,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Visit Bilbo in Rivendel".to_string(),
                 deadline: now + Duration::days(13) + Duration::hours(15),
                 duration: Duration::days(2),
                 importance: 2,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Make some firework for the hobbits".to_string(),
                 deadline: now + Duration::hours(33),
                 duration: Duration::hours(3),
                 importance: 3,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Get riders of Rohan to help Gondor".to_string(),
                 deadline: now + Duration::days(21) + Duration::hours(15),
                 duration: Duration::days(7),
                 importance: 7,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Find some good pipe-weed".to_string(),
                 deadline: now + Duration::days(2) + Duration::hours(15),
                 duration: Duration::hours(1),
                 importance: 8,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Go shop for white clothing".to_string(),
                 deadline: now + Duration::days(33) + Duration::hours(15),
                 duration: Duration::hours(2),
                 importance: 3,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Prepare epic-sounding one-liners".to_string(),
                 deadline: now + Duration::hours(34),
                 duration: Duration::hours(2),
                 importance: 10,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
             Task {
                 content: "Recharge staff batteries".to_string(),
                 deadline: now + Duration::days(1) + Duration::hours(15),
                 duration: Duration::minutes(30),
                 importance: 5,
+                available_from: None,
+                depends_on: vec![],
+                min_chunk_duration: None,
+                pinned_at: None,
             },
         ]
     }
@@ -851,96 +3776,88 @@ mod tests {
     fn schedule_gandalfs_schedule_by_importance() {
         let tasks = taskset_of_gandalf();
         let start = Utc::now();
-        let schedule = Schedule::schedule_within_segment(
+        let schedule = strictly_within_segment(
             start,
             tasks.clone(),
             anytime(),
-            SchedulingStrategy::Importance,
+            &[],
+            &Dependencies::default(),
+            &ImportanceStrategy,
         )
         .unwrap();
         let mut expected_when = start;
         // 7. Prepare epic-sounding one-liners
-        assert_eq!(schedule.0[0].task, tasks[7]);
-        assert_eq!(schedule.0[0].when, expected_when);
+        assert_eq!(schedule.tasks[0].task, tasks[7]);
+        assert_eq!(schedule.tasks[0].when, expected_when);
         expected_when = expected_when + Duration::hours(2);
         // 5. Find some good pipe-weed
-        assert_eq!(schedule.0[1].task, tasks[5]);
-        assert_eq!(schedule.0[1].when, expected_when);
+        assert_eq!(schedule.tasks[1].task, tasks[5]);
+        assert_eq!(schedule.tasks[1].when, expected_when);
         expected_when = expected_when + Duration::hours(1);
         // 8. Recharge staff batteries
-        assert_eq!(schedule.0[2].task, tasks[8]);
-        assert_eq!(schedule.0[2].when, expected_when);
+        assert_eq!(schedule.tasks[2].task, tasks[8]);
+        assert_eq!(schedule.tasks[2].when, expected_when);
         expected_when = expected_when + Duration::minutes(30);
         // 3. Make some firework for the hobbits
-        assert_eq!(schedule.0[3].task, tasks[3]);
-        assert_eq!(schedule.0[3].when, expected_when);
+        assert_eq!(schedule.tasks[3].task, tasks[3]);
+        assert_eq!(schedule.tasks[3].when, expected_when);
         expected_when = expected_when + Duration::hours(3);
         // 0. Think of plan to get rid of The Ring
-        assert_eq!(schedule.0[4].task, tasks[0]);
-        assert_eq!(schedule.0[4].when, expected_when);
+        assert_eq!(schedule.tasks[4].task, tasks[0]);
+        assert_eq!(schedule.tasks[4].when, expected_when);
         expected_when = expected_when + Duration::days(2);
         // 1. Ask advice from Saruman
-        assert_eq!(schedule.0[5].task, tasks[1]);
-        assert_eq!(schedule.0[5].when, expected_when);
+        assert_eq!(schedule.tasks[5].task, tasks[1]);
+        assert_eq!(schedule.tasks[5].when, expected_when);
         expected_when = expected_when + Duration::days(3);
         // 6. Go shop for white clothing
-        assert_eq!(schedule.0[6].task, tasks[6]);
-        assert_eq!(schedule.0[6].when, expected_when);
+        assert_eq!(schedule.tasks[6].task, tasks[6]);
+        assert_eq!(schedule.tasks[6].when, expected_when);
         expected_when = expected_when + Duration::hours(2);
         // 2. Visit Bilbo in Rivendel
-        assert_eq!(schedule.0[7].task, tasks[2]);
-        assert_eq!(schedule.0[7].when, expected_when);
+        assert_eq!(schedule.tasks[7].task, tasks[2]);
+        assert_eq!(schedule.tasks[7].when, expected_when);
         expected_when = expected_when + Duration::days(2);
         // 4. Get riders of Rohan to help Gondor
-        assert_eq!(schedule.0[8].task, tasks[4]);
-        assert_eq!(schedule.0[8].when, expected_when);
+        assert_eq!(schedule.tasks[8].task, tasks[4]);
+        assert_eq!(schedule.tasks[8].when, expected_when);
     }
 
     fn taskset_with_missed_deadline() -> Vec<Task> {
-        let task1 = Task {
-            content: "conquer the world".to_string(),
-            deadline: Utc::now() + Duration::days(3),
-            duration: Duration::days(1),
-            importance: 5,
-        };
-        let task2 = Task {
-            content: "save the world".to_string(),
-            deadline: Utc::now() - Duration::days(1),
-            duration: Duration::minutes(5),
-            importance: 5,
-        };
+        let task1 = task(
+            "conquer the world",
+            Utc::now() + Duration::days(3),
+            Duration::days(1),
+        );
+        let task2 = task(
+            "save the world",
+            Utc::now() - Duration::days(1),
+            Duration::minutes(5),
+        );
         vec![task1, task2]
     }
 
     fn taskset_with_impossible_deadline() -> Vec<Task> {
-        let task1 = Task {
-            content: "conquer the world".to_string(),
-            deadline: Utc::now() + Duration::days(3),
-            duration: Duration::days(1),
-            importance: 5,
-        };
-        let task2 = Task {
-            content: "save the world".to_string(),
-            deadline: Utc::now() + Duration::hours(23),
-            duration: Duration::days(1),
-            importance: 5,
-        };
+        let task1 = task(
+            "conquer the world",
+            Utc::now() + Duration::days(3),
+            Duration::days(1),
+        );
+        let task2 = task(
+            "save the world",
+            Utc::now() + Duration::hours(23),
+            Duration::days(1),
+        );
         vec![task1, task2]
     }
 
     fn taskset_impossible_combination(now: DateTime<Utc>) -> Vec<Task> {
-        let task1 = Task {
-            content: "Learn Rust".to_string(),
-            deadline: now + Duration::days(1),
-            duration: Duration::days(1),
-            importance: 5,
-        };
-        let task2 = Task {
-            content: "Program Eva".to_string(),
-            deadline: now + Duration::days(2),
-            duration: Duration::days(1) + Duration::minutes(1),
-            importance: 5,
-        };
+        let task1 = task("Learn Rust", now + Duration::days(1), Duration::days(1));
+        let task2 = task(
+            "Program Eva",
+            now + Duration::days(2),
+            Duration::days(1) + Duration::minutes(1),
+        );
         vec![task1, task2]
     }
 }
@@ -2,6 +2,7 @@ use std::cmp::{max, min};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::iter::Sum;
 use std::ops::{Add, Range, Sub};
 use std::rc::Rc;
 
@@ -33,6 +34,8 @@ pub enum Node<T, D> {
         free: Range<T>,
         left: Box<Node<T, D>>,
         right: Box<Node<T, D>>,
+        /// The length of the longest path from this node down to a leaf.
+        height: usize,
     },
 }
 
@@ -64,7 +67,6 @@ where
     /// Tries to schedule `data` at the exact `start` with the given `duration`.
     ///
     /// Returns whether the scheduling succeeded.
-    #[allow(dead_code)]
     pub fn schedule_exact<W>(&mut self, start: T, duration: W, data: D) -> bool
     where
         T: Add<W, Output = T>,
@@ -145,18 +147,11 @@ where
         if min_start.map_or(true, |min_start| min_start <= scope.start - duration) {
             // Schedule on [scope.start - duration, scope.start]
             let start = scope.start - duration;
-            let end = scope.start;
-            let new_node = Node::Leaf { start, end, data };
-            self.root = Some(Node::Intermediate {
-                left: Box::new(new_node),
-                right: Box::new(
-                    self.root
-                        .take()
-                        .expect("Internal error: root could not be taken"),
-                ),
-                free: scope.start..scope.start,
-            });
-            self.scope = Some(start..scope.end);
+            self.root
+                .as_mut()
+                .expect("Internal error: root could not be taken as mut ref")
+                .insert_first(start, scope.start, data);
+            scope.start = start;
             return Some(start);
         }
 
@@ -218,23 +213,142 @@ where
             // Schedule on [scope.end, scope.end + duration]
             let start = scope.end;
             let end = scope.end + duration;
-            let new_node = Node::Leaf { start, end, data };
-            self.root = Some(Node::Intermediate {
-                left: Box::new(
-                    self.root
-                        .take()
-                        .expect("Internal error: root could not be taken"),
-                ),
-                right: Box::new(new_node),
-                free: scope.end..scope.end,
-            });
-            self.scope = Some(scope.start..end);
+            self.root
+                .as_mut()
+                .expect("Internal error: root could not be taken as mut ref")
+                .insert_last(start, end, data);
+            scope.end = end;
             return Some(start);
         }
 
         None
     }
 
+    /// Tries to schedule `duration` as close as possible before `end`, split up over the free
+    /// ranges after `min_start`. No chunk is shorter than `min_chunk`, unless `duration` itself
+    /// is. The data of every chunk is made by `data`, given the index of the chunk in
+    /// chronological order.
+    ///
+    /// Returns whether the scheduling succeeded. If it didn't, nothing was scheduled.
+    pub fn schedule_chunks_close_before<W>(
+        &mut self,
+        end: T,
+        duration: W,
+        min_chunk: W,
+        min_start: T,
+        data: impl FnMut(usize) -> D,
+    ) -> bool
+    where
+        T: Add<W, Output = T> + Sub<W, Output = T> + Sub<Output = W>,
+        W: Copy + Debug + Ord + Sub<Output = W>,
+    {
+        let free_ranges = self.free_ranges(min_start..end);
+        let chunks = plan_chunks(
+            free_ranges.into_iter().rev(),
+            duration,
+            min_chunk,
+            |range, length| range.end - length..range.end,
+        );
+        match chunks {
+            Some(mut chunks) => {
+                chunks.reverse();
+                self.schedule_chunks(chunks, data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tries to schedule `duration` as close as possible after `start`, split up over the free
+    /// ranges before `max_end`. No chunk is shorter than `min_chunk`, unless `duration` itself
+    /// is. The data of every chunk is made by `data`, given the index of the chunk in
+    /// chronological order.
+    ///
+    /// Returns whether the scheduling succeeded. If it didn't, nothing was scheduled.
+    pub fn schedule_chunks_close_after<W>(
+        &mut self,
+        start: T,
+        duration: W,
+        min_chunk: W,
+        max_end: T,
+        data: impl FnMut(usize) -> D,
+    ) -> bool
+    where
+        T: Add<W, Output = T> + Sub<Output = W>,
+        W: Copy + Debug + Ord + Sub<Output = W>,
+    {
+        let free_ranges = self.free_ranges(start..max_end);
+        let chunks = plan_chunks(free_ranges, duration, min_chunk, |range, length| {
+            range.start..range.start + length
+        });
+        match chunks {
+            Some(chunks) => {
+                self.schedule_chunks(chunks, data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Schedules chunks that are known to be free.
+    fn schedule_chunks<W>(&mut self, chunks: Vec<Range<T>>, mut data: impl FnMut(usize) -> D)
+    where
+        T: Add<W, Output = T> + Sub<Output = W>,
+    {
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let scheduled = self.schedule_exact(chunk.start, chunk.end - chunk.start, data(i));
+            assert!(scheduled, "Internal error: a chunk didn't fit in a free range");
+        }
+    }
+
+    /// Returns the ranges within `range` where nothing is scheduled, in chronological order.
+    pub fn free_ranges(&self, range: Range<T>) -> Vec<Range<T>> {
+        let mut free_ranges = vec![];
+        let mut free_start = range.start;
+        for entry in self.iter() {
+            if range.end <= entry.start {
+                break;
+            }
+            if free_start < entry.start {
+                free_ranges.push(free_start..entry.start);
+            }
+            free_start = max(free_start, entry.end);
+        }
+        if free_start < range.end {
+            free_ranges.push(free_start..range.end);
+        }
+        free_ranges
+    }
+
+    /// Returns the first range within `range` where nothing is scheduled that is at least
+    /// `min_length` long, if there is one.
+    pub fn first_free_range<W>(&self, range: Range<T>, min_length: W) -> Option<Range<T>>
+    where
+        T: Sub<Output = W>,
+        W: Ord,
+    {
+        let mut at = range.start;
+        loop {
+            let free = self.free_range_at(at, range.clone())?;
+            if free.end - free.start >= min_length {
+                return Some(free);
+            }
+            at = free.end;
+        }
+    }
+
+    /// Returns how long nothing is scheduled within `range`.
+    pub fn free_time<W>(&self, range: Range<T>) -> W
+    where
+        T: Sub<Output = W>,
+        W: Sum,
+    {
+        self.free_ranges(range)
+            .into_iter()
+            .map(|free| free.end - free.start)
+            .sum()
+    }
+
     /// Common scheduling cases between all scheduling strategies. It handles the cases where
     /// (a) the schedule tree is empty;
     /// (b) the most optimal start and end fall completely before the left-most child in the tree
@@ -242,34 +356,22 @@ where
     ///
     /// Returns the start of the scheduling if it succeeded, otherwise None
     fn try_schedule_trivial_cases(&mut self, start: T, end: T, data: Rc<D>) -> Option<T> {
-        let new_node = Node::Leaf { start, end, data };
-
-        match (self.root.take(), self.scope.take()) {
+        match (self.root.as_mut(), self.scope.as_mut()) {
             (None, None) => {
-                self.root = Some(new_node);
+                self.root = Some(Node::Leaf { start, end, data });
                 self.scope = Some(start..end);
                 Some(start)
             }
             (Some(root), Some(scope)) => {
                 if end <= scope.start {
-                    self.root = Some(Node::Intermediate {
-                        left: Box::new(new_node),
-                        right: Box::new(root),
-                        free: end..scope.start,
-                    });
-                    self.scope = Some(start..scope.end);
+                    root.insert_first(start, end, data);
+                    scope.start = start;
                     Some(start)
                 } else if scope.end <= start {
-                    self.root = Some(Node::Intermediate {
-                        left: Box::new(root),
-                        right: Box::new(new_node),
-                        free: scope.end..start,
-                    });
-                    self.scope = Some(scope.start..end);
+                    root.insert_last(start, end, data);
+                    scope.end = end;
                     Some(start)
                 } else {
-                    self.root = Some(root);
-                    self.scope = Some(scope);
                     None
                 }
             }
@@ -314,6 +416,82 @@ where
         self.data_map.get(data)
     }
 
+    /// Returns the range from start to end in which `data` is scheduled.
+    pub fn scheduled_range(&self, data: &D) -> Option<Range<T>> {
+        let start = *self.data_map.get(data)?;
+        let mut node = self.root.as_ref()?;
+        loop {
+            match node {
+                Node::Leaf {
+                    start: leaf_start,
+                    end,
+                    data: leaf_data,
+                } => {
+                    return if *leaf_start == start && **leaf_data == *data {
+                        Some(start..*end)
+                    } else {
+                        None
+                    };
+                }
+                Node::Intermediate { free, left, right, .. } => {
+                    node = if start < free.start { left } else { right };
+                }
+            }
+        }
+    }
+
+    /// Returns the free range within `bounds` that contains `at`, or the first one after `at` when
+    /// something is scheduled at `at`. The time before the first entry and after the last one is
+    /// free as well, as far as `bounds` reaches.
+    pub fn free_range_at(&self, at: T, bounds: Range<T>) -> Option<Range<T>> {
+        let (root, scope) = match (&self.root, &self.scope) {
+            (Some(root), Some(scope)) => (root, scope),
+            _ => return Some(bounds).filter(|free| free.start < free.end && at < free.end),
+        };
+        let candidates = [
+            (at < scope.start).then_some(bounds.start..scope.start),
+            root.free_range_at(at),
+            Some(scope.end..bounds.end),
+        ];
+        candidates
+            .iter()
+            .flatten()
+            .map(|free| max(free.start, bounds.start)..min(free.end, bounds.end))
+            .find(|free| free.start < free.end && at < free.end)
+    }
+
+    /// Returns the entry that ends exactly at `at`, if any.
+    pub fn entry_ending_at(&self, at: T) -> Option<Entry<T, &D>> {
+        self.leaf_towards(|free| at <= free.start)
+            .filter(|entry| entry.end == at)
+    }
+
+    /// Returns the entry that starts exactly at `at`, if any.
+    pub fn entry_starting_at(&self, at: T) -> Option<Entry<T, &D>> {
+        self.leaf_towards(|free| at < free.end)
+            .filter(|entry| entry.start == at)
+    }
+
+    /// Descends the tree to a leaf, going left at every intermediate node for which `go_left`
+    /// returns true, given the free range between its children.
+    fn leaf_towards(&self, go_left: impl Fn(&Range<T>) -> bool) -> Option<Entry<T, &D>> {
+        let mut node = self.root.as_ref()?;
+        loop {
+            match node {
+                Node::Leaf { start, end, data } => {
+                    return Some(Entry {
+                        start: *start,
+                        end: *end,
+                        data: data.as_ref(),
+                    });
+                }
+                Node::Intermediate { free, left, right, .. } => {
+                    node = if go_left(free) { left } else { right };
+                }
+            }
+        }
+    }
+
     fn remove_from_map<'a>(&mut self, data: &'a D) -> Option<T> {
         self.data_map.remove(data)
     }
@@ -335,9 +513,16 @@ where
     ///
     /// Returns the start of the scheduling if it succeeded, otherwise None
     fn insert(&mut self, start: T, end: T, data: Rc<D>) -> Option<T> {
+        let start = self.insert_(start, end, data)?;
+        self.rebalance();
+        Some(start)
+    }
+
+    /// See `insert` for details. It leaves the rebalancing of this node to `insert`.
+    fn insert_(&mut self, start: T, end: T, data: Rc<D>) -> Option<T> {
         match self {
             Node::Leaf { .. } => None,
-            Node::Intermediate { left, right, free } => {
+            Node::Intermediate { left, right, free, .. } => {
                 if end <= free.start {
                     left.insert(start, end, data)
                 } else if free.end <= start {
@@ -366,13 +551,31 @@ where
         min_start: Option<T>,
         data: Rc<D>,
     ) -> Option<T>
+    where
+        T: Sub<W, Output = T>,
+        W: Copy + Debug,
+    {
+        let start = self.insert_before_(end, duration, min_start, data)?;
+        self.rebalance();
+        Some(start)
+    }
+
+    /// See `insert_before` for details. It leaves the rebalancing of this node to
+    /// `insert_before`.
+    fn insert_before_<W>(
+        &mut self,
+        end: T,
+        duration: W,
+        min_start: Option<T>,
+        data: Rc<D>,
+    ) -> Option<T>
     where
         T: Sub<W, Output = T>,
         W: Copy + Debug,
     {
         match self {
             Node::Leaf { .. } => None,
-            Node::Intermediate { left, right, free } => {
+            Node::Intermediate { left, right, free, .. } => {
                 // If the end is inside the right child, try that first
                 if free.end < end {
                     return_on_some!(right.insert_before(end, duration, min_start, Rc::clone(&data)))
@@ -387,7 +590,7 @@ where
                 }
 
                 // If min_start is contained in free, don't bother checking the left child
-                if min_start.map_or(true, |min_start| free.start <= min_start) {
+                if min_start.is_some_and(|min_start| free.start <= min_start) {
                     return None;
                 }
                 // Last, try to insert it in the left child
@@ -408,13 +611,30 @@ where
         max_end: Option<T>,
         data: Rc<D>,
     ) -> Option<T>
+    where
+        T: Ord + Add<W, Output = T>,
+        W: Copy + Debug,
+    {
+        let start = self.insert_after_(start, duration, max_end, data)?;
+        self.rebalance();
+        Some(start)
+    }
+
+    /// See `insert_after` for details. It leaves the rebalancing of this node to `insert_after`.
+    fn insert_after_<W>(
+        &mut self,
+        start: T,
+        duration: W,
+        max_end: Option<T>,
+        data: Rc<D>,
+    ) -> Option<T>
     where
         T: Ord + Add<W, Output = T>,
         W: Copy + Debug,
     {
         match self {
             Node::Leaf { .. } => None,
-            Node::Intermediate { left, right, free } => {
+            Node::Intermediate { left, right, free, .. } => {
                 // If the start is inside the left child, try that first
                 if start < free.start {
                     return_on_some!(left.insert_after(start, duration, max_end, Rc::clone(&data)))
@@ -428,7 +648,7 @@ where
                     return Some(start);
                 }
                 // If max_end is contained in free, don't bother checking the right child
-                if max_end.map_or(true, |max_end| max_end <= free.end) {
+                if max_end.is_some_and(|max_end| max_end <= free.end) {
                     return None;
                 }
                 // Last, try to insert it in the right child
@@ -445,9 +665,9 @@ where
     where
         D: PartialEq,
     {
-        match self {
+        let unscheduled = match self {
             Node::Leaf { .. } => panic!("Internal error: `unschedule` called on a leaf node"),
-            Node::Intermediate { left, right, free } => {
+            Node::Intermediate { left, right, free, .. } => {
                 if start < free.start {
                     match left {
                         box Node::Leaf {
@@ -516,25 +736,186 @@ where
                     None
                 }
             }
+        };
+        if unscheduled.is_some() {
+            self.rebalance();
         }
+        unscheduled
     }
 
-    /// Calculates the scope of all descendants of this node.
-    fn find_scope(&self) -> Range<T> {
+    /// Returns the first non-empty free range between the descendants of this node that ends after
+    /// `at`, if any.
+    fn free_range_at(&self, at: T) -> Option<Range<T>> {
+        match self {
+            Node::Leaf { .. } => None,
+            Node::Intermediate { free, left, right, .. } => {
+                if at < free.start {
+                    left.free_range_at(at).or_else(|| {
+                        if free.start < free.end {
+                            Some(free.clone())
+                        } else {
+                            right.free_range_at(at)
+                        }
+                    })
+                } else if at < free.end {
+                    Some(free.clone())
+                } else {
+                    right.free_range_at(at)
+                }
+            }
+        }
+    }
+
+    /// Inserts a leaf node with given `start`, `end` and `data` before all descendants of this
+    /// node. It must end before the first of them starts.
+    fn insert_first(&mut self, start: T, end: T, data: Rc<D>) {
+        match self {
+            Node::Leaf { start: first, .. } => {
+                let free = end..*first;
+                take_mut::take(self, |first| {
+                    Node::intermediate(Node::Leaf { start, end, data }, free, first)
+                });
+            }
+            Node::Intermediate { left, .. } => {
+                left.insert_first(start, end, data);
+                self.rebalance();
+            }
+        }
+    }
+
+    /// Inserts a leaf node with given `start`, `end` and `data` after all descendants of this
+    /// node. It must start after the last of them ends.
+    fn insert_last(&mut self, start: T, end: T, data: Rc<D>) {
+        match self {
+            Node::Leaf { end: last, .. } => {
+                let free = *last..start;
+                take_mut::take(self, |last| {
+                    Node::intermediate(last, free, Node::Leaf { start, end, data })
+                });
+            }
+            Node::Intermediate { right, .. } => {
+                right.insert_last(start, end, data);
+                self.rebalance();
+            }
+        }
+    }
+
+    /// Returns an intermediate node with the given children and the free range between them.
+    fn intermediate(left: Node<T, D>, free: Range<T>, right: Node<T, D>) -> Node<T, D> {
+        Node::Intermediate {
+            height: 1 + max(left.height(), right.height()),
+            free,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn height(&self) -> usize {
         match self {
-            Node::Leaf { start, end, .. } => *start..*end,
-            Node::Intermediate { left, right, .. } => {
-                let start = left.find_scope().start;
-                let end = right.find_scope().end;
-                start..end
+            Node::Leaf { .. } => 0,
+            Node::Intermediate { height, .. } => *height,
+        }
+    }
+
+    /// Restores the balance of this node after one of its children grew or shrank by one level,
+    /// AVL style: the heights of the children never differ by more than one. The free ranges
+    /// stay between the same children when they are rotated, so they don't change.
+    fn rebalance(&mut self) {
+        let (left, right, height) = match self {
+            Node::Leaf { .. } => return,
+            Node::Intermediate {
+                left,
+                right,
+                height,
+                ..
+            } => (left, right, height),
+        };
+        if left.height() > right.height() + 1 {
+            if left.left_height() < left.right_height() {
+                left.rotate_left();
             }
+            self.rotate_right();
+        } else if right.height() > left.height() + 1 {
+            if right.right_height() < right.left_height() {
+                right.rotate_right();
+            }
+            self.rotate_left();
+        } else {
+            *height = 1 + max(left.height(), right.height());
+        }
+    }
+
+    fn left_height(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Intermediate { left, .. } => left.height(),
+        }
+    }
+
+    fn right_height(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Intermediate { right, .. } => right.height(),
+        }
+    }
+
+    /// Makes the right child of this node take its place, with this node as its left child.
+    fn rotate_left(&mut self) {
+        take_mut::take(self, |node| match node {
+            Node::Intermediate {
+                free,
+                left,
+                right:
+                    box Node::Intermediate {
+                        free: right_free,
+                        left: middle,
+                        right,
+                        ..
+                    },
+                ..
+            } => Node::intermediate(Node::intermediate(*left, free, *middle), right_free, *right),
+            _ => panic!("Internal error: a node without intermediate right child was rotated"),
+        });
+    }
+
+    /// Makes the left child of this node take its place, with this node as its right child.
+    fn rotate_right(&mut self) {
+        take_mut::take(self, |node| match node {
+            Node::Intermediate {
+                free,
+                left:
+                    box Node::Intermediate {
+                        free: left_free,
+                        left,
+                        right: middle,
+                        ..
+                    },
+                right,
+                ..
+            } => Node::intermediate(*left, left_free, Node::intermediate(*middle, free, *right)),
+            _ => panic!("Internal error: a node without intermediate left child was rotated"),
+        });
+    }
+
+    /// Calculates the scope of all descendants of this node.
+    fn find_scope(&self) -> Range<T> {
+        let mut first = self;
+        while let Node::Intermediate { left, .. } = first {
+            first = left;
+        }
+        let mut last = self;
+        while let Node::Intermediate { right, .. } = last {
+            last = right;
+        }
+        match (first, last) {
+            (Node::Leaf { start, .. }, Node::Leaf { end, .. }) => *start..*end,
+            _ => unreachable!(),
         }
     }
 }
 
-/// Inserts a leaf node with given start, end and data in place of the right node of some other
-/// node `x`. The original right node of `x` becomes the right node of the right node of `x` and
-/// the new node becomes the left node of the right node of `x`. The free range of `x` is also
+/// Inserts a leaf node with given start, end and data in the free range of some other node `x`,
+/// which makes it the first descendant of the right node of `x`. The free range of `x` is also
 /// passed and updated.
 fn unchecked_insert<T, D>(
     start: T,
@@ -549,17 +930,50 @@ fn unchecked_insert<T, D>(
     assert!(free.start <= start);
     assert!(end <= free.end);
 
-    let new_node = Node::Leaf { start, end, data };
-
-    take_mut::take(right, |right_value| Node::Intermediate {
-        left: Box::new(new_node),
-        right: Box::new(right_value),
-        free: end..free.end,
-    });
-
+    right.insert_first(start, end, data);
     *free = free.start..start;
 }
 
+/// Divides `duration` over the given free ranges in the given order, taking as much of every range
+/// as possible while keeping every chunk at least `min_chunk` long. `place` returns which part of
+/// a free range a chunk of a given length takes up.
+///
+/// Returns the chunks if all of `duration` could be divided.
+fn plan_chunks<T, W>(
+    free_ranges: impl IntoIterator<Item = Range<T>>,
+    duration: W,
+    min_chunk: W,
+    place: impl Fn(&Range<T>, W) -> Range<T>,
+) -> Option<Vec<Range<T>>>
+where
+    T: Copy + Ord + Sub<Output = W>,
+    W: Copy + Ord + Sub<Output = W>,
+{
+    let min_chunk = min(min_chunk, duration);
+    let mut remaining = duration;
+    let mut chunks = vec![];
+    for range in free_ranges {
+        let length = range.end - range.start;
+        let chunk_length = if remaining <= length {
+            remaining
+        } else if min_chunk <= remaining - length {
+            length
+        } else {
+            // Leave enough for one more chunk
+            remaining - min_chunk
+        };
+        if range.start == range.end || chunk_length < min_chunk {
+            continue;
+        }
+        chunks.push(place(&range, chunk_length));
+        if chunk_length == remaining {
+            return Some(chunks);
+        }
+        remaining = remaining - chunk_length;
+    }
+    None
+}
+
 #[derive(Debug)]
 pub struct Entry<T, D> {
     pub start: T,
@@ -656,11 +1070,13 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::ops::Range;
 
     use assert_matches::assert_matches;
 
     use super::*;
+    use crate::util::Lcg;
 
     #[test]
     fn test_schedule_exact() {
@@ -730,43 +1146,45 @@ mod tests {
         let scheduled = tree.schedule_exact(9, 2, &data[5]);
         assert!(!scheduled);
 
-        //     free:9..9
-        //    /         \
-        // 5..9      free:10..10
-        //            /       \
-        //         9..10   free:12..13
-        //                   /     \
-        //               10..12   13..18
+        //           free:10..10
+        //          /           \
+        //     free:9..9      free:12..13
+        //      /     \        /       \
+        //   5..9   9..10   10..12    13..18
         let scheduled = tree.schedule_exact(9, 1, &data[6]);
         assert!(scheduled);
         assert!(tree.scope == Some(5..18));
         assert_matches!(
             tree.root,
             Some(Node::Intermediate {
-                free: Range { start: 9, end: 9 },
-                left: box Node::Leaf {
-                    start: 5, end: 9, ..
-                },
-                right:
+                free: Range { start: 10, end: 10 },
+                left:
                     box Node::Intermediate {
-                        free: Range { start: 10, end: 10 },
+                        free: Range { start: 9, end: 9 },
                         left:
+                            box Node::Leaf {
+                                start: 5, end: 9, ..
+                            },
+                        right:
                             box Node::Leaf {
                                 start: 9, end: 10, ..
                             },
+                        ..
+                    },
+                right:
+                    box Node::Intermediate {
+                        free: Range { start: 12, end: 13 },
+                        left:
+                            box Node::Leaf {
+                                start: 10, end: 12, ..
+                            },
                         right:
-                            box Node::Intermediate {
-                                free: Range { start: 12, end: 13 },
-                                left:
-                                    box Node::Leaf {
-                                        start: 10, end: 12, ..
-                                    },
-                                right:
-                                    box Node::Leaf {
-                                        start: 13, end: 18, ..
-                                    },
+                            box Node::Leaf {
+                                start: 13, end: 18, ..
                             },
+                        ..
                     },
+                height: 2,
             })
         );
     }
@@ -804,6 +1222,7 @@ mod tests {
                 right: box Node::Leaf {
                     start: 13, end: 18, ..
                 },
+                ..
             })
         );
 
@@ -820,6 +1239,7 @@ mod tests {
                 right: box Node::Leaf {
                     start: 13, end: 18, ..
                 },
+                ..
             })
         );
 
@@ -849,75 +1269,75 @@ mod tests {
                             box Node::Leaf {
                                 start: 13, end: 18, ..
                             },
+                        ..
                     },
+                ..
             })
         );
 
         let scheduled = tree.schedule_close_before(19, 2, Some(4), &data[4]);
         assert!(!scheduled);
 
-        //     free:5..5
-        //     /       \
-        //  3..5    free:10..11
-        //           /        \
-        //        5..10     free:13..13
-        //                    /     \
-        //                 11..13  13..18
+        //            free:10..11
+        //           /          \
+        //     free:5..5      free:13..13
+        //      /     \         /      \
+        //   3..5   5..10    11..13   13..18
         let scheduled = tree.schedule_close_before(19, 2, Some(3), &data[5]);
         assert!(scheduled);
         assert!(tree.scope == Some(3..18));
         assert_matches!(
             tree.root,
             Some(Node::Intermediate {
-                free: Range { start: 5, end: 5 },
-                left: box Node::Leaf {
-                    start: 3, end: 5, ..
-                },
-                right:
+                free: Range { start: 10, end: 11 },
+                left:
                     box Node::Intermediate {
-                        free: Range { start: 10, end: 11 },
+                        free: Range { start: 5, end: 5 },
                         left:
+                            box Node::Leaf {
+                                start: 3, end: 5, ..
+                            },
+                        right:
                             box Node::Leaf {
                                 start: 5, end: 10, ..
                             },
+                        ..
+                    },
+                right:
+                    box Node::Intermediate {
+                        free: Range { start: 13, end: 13 },
+                        left:
+                            box Node::Leaf {
+                                start: 11, end: 13, ..
+                            },
                         right:
-                            box Node::Intermediate {
-                                free: Range { start: 13, end: 13 },
-                                left:
-                                    box Node::Leaf {
-                                        start: 11, end: 13, ..
-                                    },
-                                right:
-                                    box Node::Leaf {
-                                        start: 13, end: 18, ..
-                                    },
+                            box Node::Leaf {
+                                start: 13, end: 18, ..
                             },
+                        ..
                     },
+                height: 2,
             })
         );
 
-        //           free:18..30
-        //          /           \
-        //     free:5..5       25..30
-        //     /       \
-        //  3..5    free:10..11
-        //           /        \
-        //        5..10     free:13..13
-        //                    /     \
-        //                 11..13  13..18
+        //            free:10..11
+        //           /          \
+        //     free:5..5      free:13..13
+        //      /     \         /      \
+        //   3..5   5..10    11..13   free:18..25
+        //                             /      \
+        //                          13..18   25..30
         let scheduled = tree.schedule_close_before(30, 5, Some(19), &data[6]);
         assert!(scheduled);
         assert!(tree.scope == Some(3..30));
 
-        //                free:18..21
-        //              /             \
-        //     free:5..5               free:24..25
-        //     /       \                /        \
-        //  3..5    free:10..11      21..24     25..30
-        //           /        \
-        //        5..10     free:13..13
-        //                    /     \
-        //                 11..13  13..18
+        //                free:10..11
+        //            /                 \
+        //     free:5..5               free:18..21
+        //      /     \              /             \
+        //   3..5   5..10     free:13..13        free:24..25
+        //                     /      \           /       \
+        //                  11..13   13..18    21..24    25..30
         let scheduled = tree.schedule_close_before(24, 3, None, &data[7]);
         assert!(scheduled);
         assert!(tree.scope == Some(3..30));
@@ -925,7 +1345,7 @@ mod tests {
         assert_matches!(
             tree.root,
             Some(Node::Intermediate {
-                free: Range { start: 18, end: 21 },
+                free: Range { start: 10, end: 11 },
                 left:
                     box Node::Intermediate {
                         free: Range { start: 5, end: 5 },
@@ -934,38 +1354,43 @@ mod tests {
                                 start: 3, end: 5, ..
                             },
                         right:
+                            box Node::Leaf {
+                                start: 5, end: 10, ..
+                            },
+                        ..
+                    },
+                right:
+                    box Node::Intermediate {
+                        free: Range { start: 18, end: 21 },
+                        left:
                             box Node::Intermediate {
-                                free: Range { start: 10, end: 11 },
+                                free: Range { start: 13, end: 13 },
                                 left:
                                     box Node::Leaf {
-                                        start: 5, end: 10, ..
+                                        start: 11, end: 13, ..
                                     },
                                 right:
-                                    box Node::Intermediate {
-                                        free: Range { start: 13, end: 13 },
-                                        left:
-                                            box Node::Leaf {
-                                                start: 11, end: 13, ..
-                                            },
-                                        right:
-                                            box Node::Leaf {
-                                                start: 13, end: 18, ..
-                                            },
+                                    box Node::Leaf {
+                                        start: 13, end: 18, ..
                                     },
-                            },
-                    },
-                right:
-                    box Node::Intermediate {
-                        free: Range { start: 24, end: 25 },
-                        left:
-                            box Node::Leaf {
-                                start: 21, end: 24, ..
+                                ..
                             },
                         right:
-                            box Node::Leaf {
-                                start: 25, end: 30, ..
+                            box Node::Intermediate {
+                                free: Range { start: 24, end: 25 },
+                                left:
+                                    box Node::Leaf {
+                                        start: 21, end: 24, ..
+                                    },
+                                right:
+                                    box Node::Leaf {
+                                        start: 25, end: 30, ..
+                                    },
+                                ..
                             },
+                        ..
                     },
+                height: 3,
             })
         );
     }
@@ -1003,6 +1428,7 @@ mod tests {
                 right: box Node::Leaf {
                     start: 13, end: 18, ..
                 },
+                ..
             })
         );
 
@@ -1019,6 +1445,7 @@ mod tests {
                 right: box Node::Leaf {
                     start: 13, end: 18, ..
                 },
+                ..
             })
         );
 
@@ -1048,27 +1475,27 @@ mod tests {
                             box Node::Leaf {
                                 start: 13, end: 18, ..
                             },
+                        ..
                     },
+                ..
             })
         );
 
         let scheduled = tree.schedule_close_after(4, 2, Some(19), &data[4]);
         assert!(!scheduled);
 
-        //         free:18..18
-        //         /          \
-        //   free:10..10     18..20
-        //    /        \
-        // 5..10     free:13..13
-        //             /     \
-        //          10..13  13..18
+        //            free:13..13
+        //           /           \
+        //     free:10..10     free:18..18
+        //      /      \        /      \
+        //   5..10   10..13  13..18   18..20
         let scheduled = tree.schedule_close_after(4, 2, Some(20), &data[5]);
         assert!(scheduled);
         assert!(tree.scope == Some(5..20));
         assert_matches!(
             tree.root,
             Some(Node::Intermediate {
-                free: Range { start: 18, end: 18 },
+                free: Range { start: 13, end: 13 },
                 left:
                     box Node::Intermediate {
                         free: Range { start: 10, end: 10 },
@@ -1077,93 +1504,98 @@ mod tests {
                                 start: 5, end: 10, ..
                             },
                         right:
-                            box Node::Intermediate {
-                                free: Range { start: 13, end: 13 },
-                                left:
-                                    box Node::Leaf {
-                                        start: 10, end: 13, ..
-                                    },
-                                right:
-                                    box Node::Leaf {
-                                        start: 13, end: 18, ..
-                                    },
+                            box Node::Leaf {
+                                start: 10, end: 13, ..
                             },
+                        ..
                     },
-                right: box Node::Leaf {
-                    start: 18, end: 20, ..
-                },
+                right:
+                    box Node::Intermediate {
+                        free: Range { start: 18, end: 18 },
+                        left:
+                            box Node::Leaf {
+                                start: 13, end: 18, ..
+                            },
+                        right:
+                            box Node::Leaf {
+                                start: 18, end: 20, ..
+                            },
+                        ..
+                    },
+                height: 2,
             })
         );
 
-        //                free:20..25
-        //              /             \
-        //         free:18..18       25..30
-        //         /          \
-        //   free:10..10     18..20
-        //    /        \
-        // 5..10     free:13..13
-        //             /     \
-        //          10..13  13..18
+        //            free:13..13
+        //           /           \
+        //     free:10..10     free:18..18
+        //      /      \        /      \
+        //   5..10   10..13  13..18   free:20..25
+        //                             /      \
+        //                          18..20   25..30
         let scheduled = tree.schedule_close_after(25, 5, None, &data[6]);
         assert!(scheduled);
         assert!(tree.scope == Some(5..30));
 
-        //                      free:20..21
-        //                    /             \
-        //         free:18..18               free:23..25
-        //         /          \              /         \
-        //   free:10..10     18..20      21..23       25..30
-        //    /        \
-        // 5..10     free:13..13
-        //             /     \
-        //          10..13  13..18
+        //                free:13..13
+        //            /                 \
+        //     free:10..10             free:20..21
+        //      /      \             /            \
+        //   5..10   10..13    free:18..18      free:23..25
+        //                      /      \          /      \
+        //                   13..18   18..20   21..23   25..30
         let scheduled = tree.schedule_close_after(21, 2, None, &data[7]);
         assert!(scheduled);
         assert!(tree.scope == Some(5..30));
         assert_matches!(
             tree.root,
             Some(Node::Intermediate {
-                free: Range { start: 20, end: 21 },
+                free: Range { start: 13, end: 13 },
                 left:
                     box Node::Intermediate {
-                        free: Range { start: 18, end: 18 },
+                        free: Range { start: 10, end: 10 },
                         left:
-                            box Node::Intermediate {
-                                free: Range { start: 10, end: 10 },
-                                left:
-                                    box Node::Leaf {
-                                        start: 5, end: 10, ..
-                                    },
-                                right:
-                                    box Node::Intermediate {
-                                        free: Range { start: 13, end: 13 },
-                                        left:
-                                            box Node::Leaf {
-                                                start: 10, end: 13, ..
-                                            },
-                                        right:
-                                            box Node::Leaf {
-                                                start: 13, end: 18, ..
-                                            },
-                                    },
+                            box Node::Leaf {
+                                start: 5, end: 10, ..
                             },
                         right:
                             box Node::Leaf {
-                                start: 18, end: 20, ..
+                                start: 10, end: 13, ..
                             },
+                        ..
                     },
                 right:
                     box Node::Intermediate {
-                        free: Range { start: 23, end: 25 },
+                        free: Range { start: 20, end: 21 },
                         left:
-                            box Node::Leaf {
-                                start: 21, end: 23, ..
+                            box Node::Intermediate {
+                                free: Range { start: 18, end: 18 },
+                                left:
+                                    box Node::Leaf {
+                                        start: 13, end: 18, ..
+                                    },
+                                right:
+                                    box Node::Leaf {
+                                        start: 18, end: 20, ..
+                                    },
+                                ..
                             },
                         right:
-                            box Node::Leaf {
-                                start: 25, end: 30, ..
+                            box Node::Intermediate {
+                                free: Range { start: 23, end: 25 },
+                                left:
+                                    box Node::Leaf {
+                                        start: 21, end: 23, ..
+                                    },
+                                right:
+                                    box Node::Leaf {
+                                        start: 25, end: 30, ..
+                                    },
+                                ..
                             },
+                        ..
                     },
+                height: 3,
             })
         );
     }
@@ -1266,6 +1698,7 @@ mod tests {
                 right: box Node::Leaf {
                     start: 13, end: 18, ..
                 },
+                ..
             })
         );
 
@@ -1323,6 +1756,7 @@ mod tests {
                 right: box Node::Leaf {
                     start: 13, end: 18, ..
                 },
+                ..
             })
         );
 
@@ -1352,6 +1786,205 @@ mod tests {
         assert!(tree.data_map.is_empty());
     }
 
+    #[test]
+    fn test_free_ranges_and_scheduled_range() {
+        let data = generate_data(10);
+
+        let mut tree: ScheduleTree<i8, i8> = ScheduleTree::new();
+        assert_eq!(tree.free_ranges(0..10), vec![0..10]);
+        assert_eq!(tree.free_range_at(3, 0..10), Some(0..10));
+        assert_eq!(tree.free_range_at(10, 0..10), None);
+
+        tree.schedule_exact(5, 4, data[0]);
+        tree.schedule_exact(13, 5, data[1]);
+        tree.schedule_exact(9, 1, data[2]);
+        assert_eq!(tree.free_ranges(0..30), [0..5, 10..13, 18..30]);
+        assert_eq!(tree.free_ranges(6..15), vec![10..13]);
+        assert_eq!(tree.free_ranges(6..8), []);
+        assert_eq!(tree.free_range_at(0, 0..30), Some(0..5));
+        assert_eq!(tree.free_range_at(6, 0..30), Some(10..13));
+        assert_eq!(tree.free_range_at(11, 0..30), Some(10..13));
+        assert_eq!(tree.free_range_at(13, 0..30), Some(18..30));
+        assert_eq!(tree.free_range_at(13, 0..18), None);
+        assert_eq!(tree.free_range_at(0, 5..30), Some(10..13));
+        assert_eq!(tree.first_free_range(0..30, 4), Some(0..5));
+        assert_eq!(tree.first_free_range(3..30, 3), Some(10..13));
+        assert_eq!(tree.first_free_range(3..30, 4), Some(18..30));
+        assert_eq!(tree.first_free_range(3..21, 4), None);
+        assert_eq!(tree.free_time(0..30), 20);
+        assert_eq!(tree.free_time(6..15), 3);
+        assert_eq!(tree.free_time(6..8), 0);
+
+        assert_eq!(tree.scheduled_range(&data[0]), Some(5..9));
+        assert_eq!(tree.scheduled_range(&data[1]), Some(13..18));
+        assert_eq!(tree.scheduled_range(&data[2]), Some(9..10));
+        assert_eq!(tree.scheduled_range(&data[3]), None);
+    }
+
+    #[test]
+    fn test_entries_touching() {
+        let mut tree: ScheduleTree<i8, i8> = ScheduleTree::new();
+        assert_matches!(tree.entry_ending_at(0), None);
+        tree.schedule_exact(0, 2, 0);
+        tree.schedule_exact(2, 3, 1);
+        tree.schedule_exact(8, 1, 2);
+        assert_matches!(tree.entry_ending_at(2), Some(Entry { start: 0, end: 2, data: 0 }));
+        assert_matches!(tree.entry_ending_at(5), Some(Entry { start: 2, end: 5, data: 1 }));
+        assert_matches!(tree.entry_ending_at(6), None);
+        assert_matches!(tree.entry_ending_at(9), Some(Entry { start: 8, end: 9, data: 2 }));
+        assert_matches!(tree.entry_starting_at(0), Some(Entry { start: 0, end: 2, data: 0 }));
+        assert_matches!(tree.entry_starting_at(2), Some(Entry { start: 2, end: 5, data: 1 }));
+        assert_matches!(tree.entry_starting_at(5), None);
+        assert_matches!(tree.entry_starting_at(8), Some(Entry { start: 8, end: 9, data: 2 }));
+    }
+
+    #[test]
+    fn test_schedule_chunks() {
+        let data = generate_data(10);
+
+        // Free: 0..5, 10..13, 18..
+        let mut tree: ScheduleTree<i8, i8> = ScheduleTree::new();
+        tree.schedule_exact(5, 5, data[0]);
+        tree.schedule_exact(13, 5, data[1]);
+
+        // 6 doesn't fit in chunks of at least 4 before 18
+        assert!(!tree.schedule_chunks_close_before(18, 6, 4, 0, |i| data[2 + i]));
+        assert_eq!(tree.iter().count(), 2);
+
+        // ... but it does in chunks of at least 2, taking as much as possible near the end
+        assert!(tree.schedule_chunks_close_before(18, 6, 2, 0, |i| data[2 + i]));
+        assert_eq!(tree.scheduled_range(&data[2]), Some(2..5));
+        assert_eq!(tree.scheduled_range(&data[3]), Some(10..13));
+        tree.unschedule(&data[2]);
+        tree.unschedule(&data[3]);
+
+        // The last chunk leaves enough room for the one before it
+        assert!(tree.schedule_chunks_close_before(18, 4, 2, 0, |i| data[2 + i]));
+        assert_eq!(tree.scheduled_range(&data[2]), Some(3..5));
+        assert_eq!(tree.scheduled_range(&data[3]), Some(11..13));
+        tree.unschedule(&data[2]);
+        tree.unschedule(&data[3]);
+
+        assert!(tree.schedule_chunks_close_after(1, 6, 2, 20, |i| data[2 + i]));
+        assert_eq!(tree.scheduled_range(&data[2]), Some(1..5));
+        assert_eq!(tree.scheduled_range(&data[3]), Some(10..12));
+        assert!(!tree.schedule_chunks_close_after(0, 6, 2, 20, |i| data[4 + i]));
+        assert!(tree.schedule_chunks_close_after(0, 1, 2, 20, |i| data[4 + i]));
+        assert_eq!(tree.scheduled_range(&data[4]), Some(0..1));
+    }
+
+    #[test]
+    fn test_chronological_scheduling_stays_balanced() {
+        let mut tree = ScheduleTree::new();
+        let mut expected = BTreeMap::new();
+        for id in 0..1000 {
+            let start = 2 * id as i32;
+            assert!(tree.schedule_close_after(start, 1, None, id));
+            expected.insert(start, (start + 1, id));
+            check_tree(&tree, &expected);
+        }
+        for id in 1000..2000 {
+            let end = 2000 - 2 * id as i32;
+            assert!(tree.schedule_close_before(end, 1, None, id));
+            expected.insert(end - 1, (end, id));
+            check_tree(&tree, &expected);
+        }
+        for id in (0..2000).step_by(2) {
+            assert!(tree.unschedule(&id).is_some());
+            expected.retain(|_, &mut (_, data)| data != id);
+            check_tree(&tree, &expected);
+        }
+    }
+
+    #[test]
+    fn test_random_scheduling_stays_balanced() {
+        let mut random = Lcg(7);
+        for _ in 0..20 {
+            let mut tree = ScheduleTree::new();
+            let mut expected = BTreeMap::new();
+            for id in 0..300 {
+                if !expected.is_empty() && random.below(3) == 0 {
+                    let nth = random.below(expected.len() as u64) as usize;
+                    let (&start, &(end, data)) = expected.iter().nth(nth).unwrap();
+                    expected.remove(&start);
+                    let entry = tree.unschedule(&data).unwrap();
+                    assert_eq!((entry.start, entry.end, entry.data), (start, end, data));
+                } else {
+                    let at = random.below(1000) as i32;
+                    let duration = 1 + random.below(10) as i32;
+                    let bound = (random.below(2) == 0).then_some(50);
+                    let scheduled = match random.below(3) {
+                        0 => {
+                            let overlaps = expected
+                                .iter()
+                                .any(|(&start, &(end, _))| start < at + duration && at < end);
+                            let scheduled = tree.schedule_exact(at, duration, id);
+                            assert_eq!(scheduled, !overlaps);
+                            assert!(!scheduled || tree.when_scheduled(&id) == Some(&at));
+                            scheduled
+                        }
+                        1 => {
+                            let min_start = bound.map(|bound| at - bound);
+                            tree.schedule_close_before(at + duration, duration, min_start, id)
+                        }
+                        _ => {
+                            let max_end = bound.map(|bound| at + duration + bound);
+                            tree.schedule_close_after(at, duration, max_end, id)
+                        }
+                    };
+                    if scheduled {
+                        let range = tree.scheduled_range(&id).unwrap();
+                        assert_eq!(range.end - range.start, duration);
+                        expected.insert(range.start, (range.end, id));
+                    }
+                }
+                check_tree(&tree, &expected);
+            }
+        }
+    }
+
+    /// Checks that `tree` holds exactly the entries in `expected`, by start, in chronological
+    /// order, and that all of its nodes are balanced and know the free range between their
+    /// children.
+    fn check_tree(tree: &ScheduleTree<i32, u32>, expected: &BTreeMap<i32, (i32, u32)>) {
+        assert_eq!(tree.scope, tree.root.as_ref().map(check_node));
+        let entries = tree
+            .iter()
+            .map(|entry| (entry.start, (entry.end, *entry.data)))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected.clone().into_iter().collect::<Vec<_>>());
+        assert!(entries.windows(2).all(|pair| (pair[0].1).0 <= pair[1].0));
+        assert_eq!(tree.data_map.len(), expected.len());
+        // The height of an AVL tree with n leaves is at most about 1.44 log2(n)
+        let height = tree.root.as_ref().map_or(0, Node::height);
+        assert!(height as f64 <= 1.45 * (expected.len() as f64 + 2.0).log2());
+    }
+
+    /// Checks that `node` and its descendants are balanced, that they know their height and the
+    /// free range between their children. Returns the scope of `node`.
+    fn check_node(node: &Node<i32, u32>) -> Range<i32> {
+        match node {
+            Node::Leaf { start, end, .. } => {
+                assert!(start < end);
+                *start..*end
+            }
+            Node::Intermediate {
+                free,
+                left,
+                right,
+                height,
+            } => {
+                let left_scope = check_node(left);
+                let right_scope = check_node(right);
+                assert_eq!(*free, left_scope.end..right_scope.start);
+                assert!(free.start <= free.end);
+                assert_eq!(*height, 1 + max(left.height(), right.height()));
+                assert!(left.height().abs_diff(right.height()) <= 1);
+                left_scope.start..right_scope.end
+            }
+        }
+    }
+
     fn generate_data(n: i8) -> Vec<i8> {
         (0..n).collect()
     }
@@ -0,0 +1,535 @@
+use std::cmp::{max, min, Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Debug;
+use std::ops::Range;
+use std::time::{self, Instant};
+
+use chrono::prelude::*;
+use chrono::Duration;
+use itertools::Itertools;
+
+/// A way of deciding where the tasks go in a schedule.
+///
+/// A strategy only decides the order in which tasks are put in the schedule and how close to
+/// their deadlines they go. The `Planner` it works with keeps the tasks clear of each other, of
+/// fixed events and of the time outside of their time segment, and keeps track of every step to
+/// explain the schedule afterwards.
+pub trait Strategy: Debug {
+    /// The name to choose the strategy by, like "importance".
+    fn name(&self) -> &str;
+
+    /// Schedules as many of the `planner`'s tasks as possible before their deadlines, and gives
+    /// up on the ones that don't fit.
+    fn schedule(&self, planner: &mut dyn Planner);
+}
+
+/// The schedule of a single time segment, while a `Strategy` is working on it.
+///
+/// Tasks are referred to by their index, from 0 up to `task_count()`. The methods panic when
+/// they're given an index that's out of range.
+pub trait Planner {
+    /// The moment before which nothing can be scheduled.
+    fn start(&self) -> DateTime<Utc>;
+    fn task_count(&self) -> usize;
+    fn deadline(&self, task: usize) -> DateTime<Utc>;
+    fn duration(&self, task: usize) -> Duration;
+    fn importance(&self, task: usize) -> u32;
+    /// The shortest chunk `task` can be split into, if it can be split at all.
+    fn min_chunk_duration(&self, task: usize) -> Option<Duration>;
+    /// The earliest moment `task` can start: not before `start()`, not before it's available and
+    /// not before its prerequisites end, wherever they're scheduled.
+    fn earliest_start(&self, task: usize) -> DateTime<Utc>;
+    /// The latest moment `task` can end: not after its deadline, and leaving time for the tasks
+    /// that depend on it.
+    fn latest_end(&self, task: usize) -> DateTime<Utc>;
+    /// When `task` is scheduled, from the start of its first chunk until the end of its last, if
+    /// it is.
+    fn scheduled(&self, task: usize) -> Option<Range<DateTime<Utc>>>;
+    /// The range of free time that contains `at`, or the first one after `at` when `at` isn't
+    /// free. Free time is time that's in the time segment and not taken by events or tasks.
+    /// Returns `None` when there's no free time left after `at` in which tasks could go.
+    fn free_range_at(&self, at: DateTime<Utc>) -> Option<Range<DateTime<Utc>>>;
+    /// Schedules `task` as close as possible before `max_end`, but not before `min_start`. When
+    /// the task can be split up and there's no gap big enough for all of it, it's scheduled in
+    /// chunks instead.
+    ///
+    /// Returns whether the scheduling succeeded.
+    fn schedule_close_before(
+        &mut self,
+        task: usize,
+        min_start: DateTime<Utc>,
+        max_end: DateTime<Utc>,
+    ) -> bool;
+    /// Schedules `task` as close as possible after `min_start`, without letting it end after
+    /// `max_end`. A task that can be split up, is split up to fill the gaps. Does nothing when the
+    /// task is already scheduled.
+    ///
+    /// Returns whether the scheduling succeeded.
+    fn schedule_close_after(
+        &mut self,
+        task: usize,
+        min_start: DateTime<Utc>,
+        max_end: DateTime<Utc>,
+    ) -> bool;
+    /// Moves the scheduled `task` as close after `min_start` as possible, without letting it end
+    /// any later. A task that can be split up, is split up to fill the gaps.
+    ///
+    /// Returns whether the task moved.
+    fn pull_forward(&mut self, task: usize, min_start: DateTime<Utc>) -> bool;
+    /// Takes `task` out of the schedule again, so it can be scheduled somewhere else.
+    fn unschedule(&mut self, task: usize);
+    /// Leaves `task` out of the schedule, because it doesn't fit before its deadline. Tasks that
+    /// aren't scheduled when the strategy is done are left out as well.
+    fn give_up(&mut self, task: usize);
+}
+
+/// Schedules tasks according to importance while making sure all deadlines are met.
+///
+/// First, all tasks --- starting with the least important until the most important --- are
+/// scheduled as close as possible to their deadline. Next, the free time is filled up from the
+/// present onwards: each gap gets the most important task from later on that can move into it,
+/// until no task can move into it anymore. For ties on importance, more urgent tasks are scheduled
+/// later in the first phase and sooner in the second phase.
+///
+/// In both phases, a task is kept between the end of its prerequisites and the start of the tasks
+/// that depend on it.
+///
+/// This algorithm doesn't work right when the lengths of the tasks aren't about the same, but it
+/// will do for now. `OptimalStrategy` finds the best schedule for small sets of tasks instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportanceStrategy;
+
+impl Strategy for ImportanceStrategy {
+    fn name(&self) -> &str {
+        "importance"
+    }
+
+    fn schedule(&self, planner: &mut dyn Planner) {
+        let start = planner.start();
+        // Start by scheduling the least important tasks closest to the deadline, and so on.
+        let mut tasks = (0..planner.task_count())
+            .sorted_by_key(|&task| {
+                (
+                    planner.importance(task),
+                    start.signed_duration_since(planner.deadline(task)),
+                )
+            })
+            .collect_vec();
+        pack_before_deadlines(planner, &tasks);
+        tasks.retain(|&task| planner.scheduled(task).is_some());
+        tasks.reverse();
+        fill_gaps(planner, tasks);
+    }
+}
+
+/// Moves the scheduled `tasks` towards the present, going through the free time from the start
+/// onwards. Every gap is given to the first of `tasks` that can move into it, until none of them
+/// can anymore.
+///
+/// Moving a task only frees up time later on, so a gap that's passed by never has to be visited
+/// again, except when it becomes bigger because the task right after it moves away. That keeps
+/// the number of tasks that are tried out for every gap low.
+fn fill_gaps(planner: &mut dyn Planner, mut tasks: Vec<usize>) {
+    let mut places: HashMap<usize, Range<DateTime<Utc>>> = tasks
+        .iter()
+        .filter_map(|&task| Some((task, planner.scheduled(task)?)))
+        .collect();
+    // The moments to look for free time from, earliest first.
+    let mut todo = BinaryHeap::new();
+    todo.push(Reverse(planner.start()));
+    while let Some(Reverse(at)) = todo.pop() {
+        // Moments that come up more than once only have to be looked at once.
+        while todo.peek() == Some(&Reverse(at)) {
+            todo.pop();
+        }
+        let gap = match planner.free_range_at(at) {
+            Some(gap) => gap,
+            None => break,
+        };
+        // Tasks that are done before the gap won't move anymore.
+        tasks.retain(|task| gap.start < places[task].end);
+        if tasks.is_empty() {
+            break;
+        }
+        let fits = |planner: &dyn Planner, task: usize| {
+            let shortest = match planner.min_chunk_duration(task) {
+                Some(min_chunk) => min(min_chunk, planner.duration(task)),
+                None => planner.duration(task),
+            };
+            let place = &places[&task];
+            // A task right after the gap can use its own time as well.
+            let end = if place.start == gap.end {
+                place.end
+            } else {
+                gap.end
+            };
+            gap.end <= place.start
+                && gap.start + shortest <= end
+                && max(gap.start, planner.earliest_start(task)) + shortest <= end
+        };
+        // The time before the gap is of no use to any of the tasks anymore, so don't look there.
+        let moved = tasks.iter().cloned().find(|&task| {
+            fits(&*planner, task) && {
+                let min_start = max(gap.start, planner.earliest_start(task));
+                planner.pull_forward(task, min_start)
+            }
+        });
+        match moved {
+            Some(task) => {
+                let old_place = places[&task].clone();
+                places.insert(task, planner.scheduled(task).unwrap_or(old_place.clone()));
+                // What's left of the gap might fit another task, and so might the time the task
+                // left behind, together with the free time around it.
+                todo.push(Reverse(gap.start));
+                todo.push(Reverse(old_place.start));
+            }
+            None => todo.push(Reverse(gap.end)),
+        }
+    }
+}
+
+/// Schedules tasks according to deadline first and then according to importance.
+///
+/// First, all tasks --- starting with the least important until the most important --- are
+/// scheduled as close as possible to their deadline. Next, all tasks are put as close to the
+/// present as possible, keeping the order from the first scheduling phase.
+///
+/// This algorithm is how Myrjam Van de Vijver does her personal scheduling. A benefit of doing it
+/// this way, is that it is highly robust against contingencies like falling sick. A disadvantage
+/// is that it gives more priority to urgent but less important tasks than to important but less
+/// urgent tasks.
+///
+/// Like with the importance strategy, tasks never start before their prerequisites end.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UrgencyStrategy;
+
+impl Strategy for UrgencyStrategy {
+    fn name(&self) -> &str {
+        "urgency"
+    }
+
+    fn schedule(&self, planner: &mut dyn Planner) {
+        // Start by scheduling the least important tasks closest to the deadline, and so on.
+        let tasks = (0..planner.task_count())
+            .sorted_by_key(|&task| planner.importance(task))
+            .collect_vec();
+        pack_before_deadlines(planner, &tasks);
+        // Next, shift the all tasks towards the present, filling up the gaps.
+        let tasks = (0..planner.task_count())
+            .filter_map(|task| Some((planner.scheduled(task)?.start, task)))
+            .sorted()
+            .map(|(_, task)| task)
+            .collect_vec();
+        for task in tasks {
+            let min_start = planner.earliest_start(task);
+            planner.pull_forward(task, min_start);
+        }
+    }
+}
+
+/// Schedules the tasks in order of deadline, each as close to the present as possible.
+///
+/// Tasks with the same deadline are ordered by importance, the most important first. Deadlines
+/// take the tasks that depend on a task into account, so a prerequisite always comes before the
+/// tasks that wait for it. When a task doesn't fit before its deadline anymore, it is left out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EarliestDeadlineFirstStrategy;
+
+impl Strategy for EarliestDeadlineFirstStrategy {
+    fn name(&self) -> &str {
+        "edf"
+    }
+
+    fn schedule(&self, planner: &mut dyn Planner) {
+        let tasks = by_deadline(planner, 0..planner.task_count());
+        for task in tasks {
+            if !place_early(planner, task) {
+                planner.give_up(task);
+            }
+        }
+    }
+}
+
+/// Schedules the tasks that are most important for their duration first, as long as that doesn't
+/// keep other tasks from making their deadline.
+///
+/// The tasks are sorted once by their importance per hour (weighted shortest processing time), the
+/// most urgent first for ties. First, they're all scheduled as close as possible to their
+/// deadline, starting with the ones that are worth the least per hour, so those are the ones left
+/// out when not everything fits. Next, the free time is filled up from the present onwards: each
+/// gap gets the first task in that order that can move into it, like with `ImportanceStrategy`.
+/// Because tasks only ever move forward from where they made their deadline, they all still do.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeightedShortestJobFirstStrategy;
+
+impl Strategy for WeightedShortestJobFirstStrategy {
+    fn name(&self) -> &str {
+        "wspt"
+    }
+
+    fn schedule(&self, planner: &mut dyn Planner) {
+        // Most importance per unit of time first, the most urgent first for ties.
+        let mut tasks = (0..planner.task_count())
+            .sorted_by(|&a, &b| {
+                weight_per_duration(planner, b, a)
+                    .then_with(|| planner.latest_end(a).cmp(&planner.latest_end(b)))
+            })
+            .collect_vec();
+        tasks.reverse();
+        pack_before_deadlines(planner, &tasks);
+        tasks.retain(|&task| planner.scheduled(task).is_some());
+        tasks.reverse();
+        fill_gaps(planner, tasks);
+    }
+}
+
+/// Searches for the schedule that minimises the weighted completion time, for sets of tasks small
+/// enough to do that in time.
+///
+/// The best schedule leaves out as little importance as possible, and then has the lowest sum of
+/// each task's importance times the time from the start until it's done. Every order of the
+/// tasks is considered, putting each task as early as possible (branch and bound). Tasks that can
+/// be split up are split to fill the gaps on the way, so the result is the best of those orders,
+/// but not necessarily the best of all the ways to split the tasks.
+///
+/// Every time segment is searched on its own, and gets `time_limit` for it. When the search of a
+/// segment takes longer than that, its tasks are scheduled by `ImportanceStrategy` instead.
+#[derive(Debug, Clone, Copy)]
+pub struct OptimalStrategy {
+    /// How long to search for the best schedule of a time segment.
+    pub time_limit: time::Duration,
+}
+
+impl Default for OptimalStrategy {
+    fn default() -> Self {
+        OptimalStrategy {
+            time_limit: time::Duration::from_secs(1),
+        }
+    }
+}
+
+impl Strategy for OptimalStrategy {
+    fn name(&self) -> &str {
+        "optimal"
+    }
+
+    fn schedule(&self, planner: &mut dyn Planner) {
+        let mut search = Search {
+            planner: &mut *planner,
+            give_up_at: Instant::now() + self.time_limit,
+            order: vec![],
+            best: None,
+        };
+        let tasks = (0..search.planner.task_count()).collect_vec();
+        let best = search.branch(&tasks, Score::default()).and(search.best);
+        match best {
+            Some((_, order)) => {
+                for &task in &order {
+                    place_early(planner, task);
+                }
+                for task in 0..planner.task_count() {
+                    if planner.scheduled(task).is_none() {
+                        planner.give_up(task);
+                    }
+                }
+            }
+            None => ImportanceStrategy.schedule(planner),
+        }
+    }
+}
+
+/// How bad a schedule is, compared lexicographically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Score {
+    /// The total importance of the tasks that are left out.
+    left_out: u64,
+    /// The sum of each task's importance times the seconds from the start until it's done.
+    weighted_completion: i128,
+}
+
+/// The state of the branch and bound search of `OptimalStrategy`.
+struct Search<'a> {
+    planner: &'a mut dyn Planner,
+    give_up_at: Instant,
+    /// The tasks that are scheduled in the current branch, in the order they were put in.
+    order: Vec<usize>,
+    /// The best score so far, with the order of the tasks that reaches it.
+    best: Option<(Score, Vec<usize>)>,
+}
+
+impl<'a> Search<'a> {
+    /// Tries every way to schedule `tasks` after the ones in `order`, which reached `score`.
+    /// Leaves the schedule like it found it.
+    ///
+    /// Returns `None` when the time is up.
+    fn branch(&mut self, tasks: &[usize], mut score: Score) -> Option<()> {
+        if Instant::now() > self.give_up_at {
+            return None;
+        }
+        // Scheduling more tasks never makes room, so the tasks that don't fit now never will.
+        let mut fitting = vec![];
+        for &task in tasks {
+            if place_early(self.planner, task) {
+                let end = self.planner.scheduled(task).map(|range| range.end);
+                self.planner.unschedule(task);
+                fitting.push((task, end.unwrap_or_else(|| self.planner.start())));
+            } else {
+                score.left_out += u64::from(self.planner.importance(task));
+            }
+        }
+        if fitting.is_empty() {
+            if self.best.as_ref().is_none_or(|(best, _)| score < *best) {
+                self.best = Some((score, self.order.clone()));
+            }
+            return Some(());
+        }
+        if let Some((best, _)) = &self.best {
+            let bound = Score {
+                left_out: score.left_out,
+                weighted_completion: score.weighted_completion
+                    + self.completion_bound(fitting.iter().map(|&(task, _)| task)),
+            };
+            // Leaving out any more tasks only makes it worse, because the score is compared on
+            // what's left out first.
+            if bound >= *best {
+                return Some(());
+            }
+        }
+        // Try the tasks that are worth the most per unit of time first, to find good schedules
+        // early on and cut off more branches.
+        fitting.sort_by(|&(a, _), &(b, _)| {
+            weight_per_duration(self.planner, b, a)
+                .then_with(|| self.planner.latest_end(a).cmp(&self.planner.latest_end(b)))
+        });
+        for &(task, end) in &fitting {
+            let rest = fitting
+                .iter()
+                .map(|&(other, _)| other)
+                .filter(|&other| other != task)
+                .collect_vec();
+            let mut score = score;
+            score.weighted_completion += self.weighted_completion(task, end);
+            place_early(self.planner, task);
+            self.order.push(task);
+            let result = self.branch(&rest, score);
+            self.order.pop();
+            self.planner.unschedule(task);
+            result?;
+        }
+        Some(())
+    }
+
+    fn weighted_completion(&self, task: usize, end: DateTime<Utc>) -> i128 {
+        let seconds = end
+            .signed_duration_since(self.planner.start())
+            .num_seconds();
+        i128::from(self.planner.importance(task)) * i128::from(seconds)
+    }
+
+    /// A lower bound on the weighted completion time of `tasks`, when they're all scheduled.
+    fn completion_bound(&self, tasks: impl Iterator<Item = usize>) -> i128 {
+        let planner = &*self.planner;
+        let tasks = tasks.collect_vec();
+        // Every task is done at least its duration after it can start.
+        let each_alone: i128 = tasks
+            .iter()
+            .map(|&task| {
+                self.weighted_completion(
+                    task,
+                    planner.earliest_start(task) + planner.duration(task),
+                )
+            })
+            .sum();
+        // The tasks can't overlap either, and without gaps the most important tasks per unit of
+        // time go first.
+        let mut end = match tasks.iter().map(|&task| planner.earliest_start(task)).min() {
+            Some(start) => start,
+            None => return 0,
+        };
+        let one_after_the_other = tasks
+            .iter()
+            .cloned()
+            .sorted_by(|&a, &b| weight_per_duration(planner, b, a))
+            .map(|task| {
+                end += planner.duration(task);
+                self.weighted_completion(task, end)
+            })
+            .sum();
+        each_alone.max(one_after_the_other)
+    }
+}
+
+/// Compares the importance per unit of time of tasks `a` and `b`.
+fn weight_per_duration(planner: &dyn Planner, a: usize, b: usize) -> Ordering {
+    // Cross-multiply to compare importance(a) / duration(a) with importance(b) / duration(b).
+    let weight = |task: usize, other: usize| {
+        i128::from(planner.importance(task)) * i128::from(planner.duration(other).num_seconds())
+    };
+    weight(a, b).cmp(&weight(b, a))
+}
+
+/// Orders `tasks` by the latest moment they can end, the most important first for ties.
+fn by_deadline(planner: &dyn Planner, tasks: impl Iterator<Item = usize>) -> Vec<usize> {
+    tasks
+        .sorted_by_key(|&task| (planner.latest_end(task), Reverse(planner.importance(task))))
+        .collect_vec()
+}
+
+/// Schedules `task` as close to the present as possible, without missing its deadline.
+///
+/// Returns whether it fits.
+fn place_early(planner: &mut dyn Planner, task: usize) -> bool {
+    let min_start = planner.earliest_start(task);
+    let max_end = planner.latest_end(task);
+    planner.schedule_close_after(task, min_start, max_end)
+}
+
+/// Schedules `tasks` in the given order, each as close as possible to its deadline.
+///
+/// When a task doesn't fit, less important tasks that are in its way are taken out again, the
+/// least important first, until it does. The tasks that were taken out are scheduled again
+/// afterwards, if they still fit. That way, it's the least important tasks that are given up on.
+fn pack_before_deadlines(planner: &mut dyn Planner, tasks: &[usize]) {
+    let mut packed: Vec<usize> = vec![];
+    for &task in tasks {
+        // Leave room for the prerequisites and the tasks that depend on this one.
+        let min_start = planner.earliest_start(task);
+        let max_end = planner.latest_end(task);
+        let mut taken_out = vec![];
+        while !planner.schedule_close_before(task, min_start, max_end) {
+            let in_the_way = packed
+                .iter()
+                .enumerate()
+                .filter(|&(_, &other)| {
+                    planner.importance(other) < planner.importance(task)
+                        && planner
+                            .scheduled(other)
+                            .is_some_and(|place| place.start < max_end && min_start < place.end)
+                })
+                .min_by_key(|&(_, &other)| planner.importance(other))
+                .map(|(i, _)| i);
+            match in_the_way {
+                Some(i) => {
+                    let other = packed.remove(i);
+                    planner.unschedule(other);
+                    taken_out.push(other);
+                }
+                None => break,
+            }
+        }
+        if planner.scheduled(task).is_some() {
+            packed.push(task);
+        } else {
+            planner.give_up(task);
+        }
+        for other in taken_out {
+            let min_start = planner.earliest_start(other);
+            let max_end = planner.latest_end(other);
+            if planner.schedule_close_before(other, min_start, max_end) {
+                packed.push(other);
+            } else {
+                planner.give_up(other);
+            }
+        }
+    }
+}
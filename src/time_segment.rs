@@ -8,6 +8,11 @@ pub trait TimeSegment: Clone {
     fn start(&self) -> DateTime<Utc>;
     fn period(&self) -> Duration;
 
+    /// The id of the time segment, if it's one from the database.
+    fn id(&self) -> Option<u32> {
+        None
+    }
+
     /// Construct the inverse of the time segment, i.e. the time segment made up
     /// of all time that the given time segment _doesn't_ cover.
     fn inverse(&self) -> UnnamedTimeSegment {
@@ -128,12 +133,17 @@ pub trait TimeSegment: Clone {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NamedTimeSegment {
     pub id: u32,
     pub name: String,
     // ranges is assumed to be in order
     pub ranges: Vec<Range<DateTime<Utc>>>,
     pub start: DateTime<Utc>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::util::serialize::duration")
+    )]
     pub period: Duration,
     pub hue: u16,
 }
@@ -168,6 +178,10 @@ impl TimeSegment for NamedTimeSegment {
     fn period(&self) -> Duration {
         self.period
     }
+
+    fn id(&self) -> Option<u32> {
+        Some(self.id)
+    }
 }
 
 impl TimeSegment for UnnamedTimeSegment {
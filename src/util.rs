@@ -19,3 +19,103 @@ impl<T: Copy> WithSideEffects for Option<T> {
         })
     }
 }
+
+/// A small pseudorandom number generator, to make up test and benchmark data that is the same on
+/// every run.
+#[cfg(any(test, feature = "bench"))]
+pub struct Lcg(pub u64);
+
+#[cfg(any(test, feature = "bench"))]
+impl Lcg {
+    /// Returns the next number, below `bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 33) % bound
+    }
+}
+
+/// Helpers for the fields that serde can't serialize by itself.
+#[cfg(feature = "serde")]
+pub(crate) mod serialize {
+    use chrono::Duration;
+    use serde::Serializer;
+
+    use crate::recurrence::Recurrence;
+
+    /// Serializes a duration as a whole number of seconds.
+    pub fn duration<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn optional_duration<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.num_seconds()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Serializes a recurrence as an iCalendar recurrence rule, like `FREQ=WEEKLY;INTERVAL=2`.
+    pub fn optional_recurrence<S: Serializer>(
+        recurrence: &Option<Recurrence>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match recurrence {
+            Some(recurrence) => serializer.collect_str(recurrence),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use chrono::prelude::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    use crate::Task;
+
+    #[test]
+    fn serializes_tasks() {
+        let task = Task {
+            id: 1,
+            content: "Stand-up".to_owned(),
+            deadline: "2026-10-19T09:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            available_from: None,
+            duration: Duration::minutes(15),
+            importance: 5,
+            time_segment_id: 0,
+            recurrence: Some("FREQ=DAILY;COUNT=3".parse().unwrap()),
+            min_chunk_duration: None,
+            depends_on: vec![2],
+            completed_at: None,
+            ical_uid: None,
+            pin: None,
+            blocked: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&task).unwrap(),
+            json!({
+                "id": 1,
+                "content": "Stand-up",
+                "deadline": "2026-10-19T09:00:00Z",
+                "available_from": null,
+                "duration": 900,
+                "importance": 5,
+                "time_segment_id": 0,
+                "recurrence": "FREQ=DAILY;COUNT=3",
+                "min_chunk_duration": null,
+                "depends_on": [2],
+                "completed_at": null,
+                "ical_uid": null,
+                "pin": null,
+                "blocked": null,
+            })
+        );
+    }
+}